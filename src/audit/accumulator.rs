@@ -0,0 +1,61 @@
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+
+/// What a view gathers from ping events, folded one event at a time so that
+/// every log file can be read into its own accumulator on a worker thread.
+///
+/// Loading starts each worker from a clone of an empty accumulator, so
+/// accumulators may carry the settings they are recorded with.
+pub trait Accumulator: Clone + Send + Sync {
+    /// Whether what is gathered depends on every earlier ping of a target,
+    /// as a rolling baseline does. Such accumulators are fed the log files
+    /// of a source one after the other on one thread instead.
+    const ORDERED: bool = false;
+
+    /// Add one event. The events of a target arrive in time order.
+    fn record(&mut self, event: &PingEvent);
+
+    /// Add one event the loader is done with, which accumulators keeping
+    /// whole events store without copying.
+    fn record_owned(&mut self, event: PingEvent) {
+        self.record(&event);
+    }
+
+    /// Add what another accumulator gathered from later logs.
+    fn merge(&mut self, later: Self);
+
+    /// Rewrite the target of everything gathered, merging targets that end
+    /// up the same, such as to credit events that name no machine to the
+    /// machine of their source.
+    fn retarget(&mut self, rekey: impl FnMut(&mut TargetKey));
+}
+
+/// Nothing beyond the target histories every load keeps.
+impl Accumulator for () {
+    fn record(&mut self, _event: &PingEvent) {}
+
+    fn merge(&mut self, _later: Self) {}
+
+    fn retarget(&mut self, _rekey: impl FnMut(&mut TargetKey)) {}
+}
+
+/// Every event, for views that need each ping.
+impl Accumulator for Vec<PingEvent> {
+    fn record(&mut self, event: &PingEvent) {
+        self.push(event.clone());
+    }
+
+    fn record_owned(&mut self, event: PingEvent) {
+        self.push(event);
+    }
+
+    fn merge(&mut self, later: Self) {
+        self.extend(later);
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for event in self {
+            rekey(&mut event.target);
+        }
+    }
+}
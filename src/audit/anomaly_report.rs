@@ -0,0 +1,129 @@
+use crate::audit::Cell;
+use crate::audit::Histories;
+use crate::audit::LatencyDegradation;
+use crate::audit::LatencyReplay;
+use crate::audit::ReportBody;
+use crate::audit::Table;
+use crate::audit::format_duration;
+use crate::audit::serialize_seconds;
+use chrono::Duration;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Latency degradations of one target.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetDegradations {
+    pub target: String,
+    pub degradations: usize,
+    #[serde(rename = "degraded_seconds", serialize_with = "serialize_seconds")]
+    pub degraded: Duration,
+    #[serde(rename = "longest_seconds", serialize_with = "serialize_seconds")]
+    pub longest: Duration,
+    pub peak_ms: f64,
+}
+
+/// The `audit anomalies` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnomalyReport {
+    pub z: f64,
+    #[serde(rename = "duration_seconds", serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+    pub alpha: f64,
+    pub targets: Vec<TargetDegradations>,
+    pub degradations: Vec<LatencyDegradation>,
+}
+
+impl AnomalyReport {
+    #[must_use]
+    pub fn build(replay: &LatencyReplay, histories: &Histories) -> Self {
+        let degradations = replay.degradations(histories);
+        let mut targets: BTreeMap<&str, TargetDegradations> = BTreeMap::new();
+        for degradation in &degradations {
+            let summary =
+                targets
+                    .entry(&degradation.target)
+                    .or_insert_with(|| TargetDegradations {
+                        target: degradation.target.clone(),
+                        degradations: 0,
+                        degraded: Duration::zero(),
+                        longest: Duration::zero(),
+                        peak_ms: 0.0,
+                    });
+            summary.degradations += 1;
+            summary.degraded += degradation.duration();
+            summary.longest = summary.longest.max(degradation.duration());
+            summary.peak_ms = summary.peak_ms.max(degradation.peak_ms);
+        }
+        let settings = &replay.options().settings;
+        Self {
+            z: settings.z,
+            duration: Duration::from_std(settings.duration).unwrap_or(Duration::MAX),
+            alpha: settings.alpha,
+            targets: targets.into_values().collect(),
+            degradations,
+        }
+    }
+}
+
+impl ReportBody for AnomalyReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut summary = Table::new(
+            "Latency Degradations by Target",
+            &["Target", "Degradations", "Degraded", "Longest", "Peak ms"],
+        )
+        .with_bar(1);
+        for target in &self.targets {
+            summary.push_row(vec![
+                target.target.as_str().into(),
+                target.degradations.into(),
+                target.degraded.into(),
+                target.longest.into(),
+                Cell::Number(target.peak_ms),
+            ]);
+        }
+        summary.push_note(format!(
+            "A degradation is latency staying more than {} standard deviations above the target's rolling baseline (EWMA, alpha {}) for at least {}.",
+            self.z,
+            self.alpha,
+            format_duration(self.duration)
+        ));
+        if self.degradations.is_empty() {
+            summary.push_note("No latency degradations found.");
+            return vec![summary];
+        }
+
+        let mut timeline = Table::new(
+            "Latency Degradations",
+            &[
+                "Start",
+                "Duration",
+                "Target",
+                "Slow pings",
+                "Peak ms",
+                "Baseline ms",
+                "Threshold ms",
+            ],
+        );
+        for degradation in &self.degradations {
+            let duration: Cell = if degradation.recovered {
+                degradation.duration().into()
+            } else {
+                format!(
+                    "{} (not recovered)",
+                    format_duration(degradation.duration())
+                )
+                .into()
+            };
+            timeline.push_row(vec![
+                Cell::Time(degradation.start),
+                duration,
+                degradation.target.as_str().into(),
+                degradation.pings.into(),
+                Cell::Number(degradation.peak_ms),
+                Cell::Number(degradation.baseline_ms),
+                Cell::Number(degradation.threshold_ms),
+            ]);
+        }
+        vec![summary, timeline]
+    }
+}
@@ -0,0 +1,826 @@
+use crate::audit::Accumulator;
+use crate::audit::Compression;
+use crate::audit::EventFilter;
+use crate::audit::FilterVerdict;
+use crate::audit::HourlyTally;
+use crate::audit::Loaded;
+use crate::audit::LogFile;
+use crate::audit::LogLine;
+use crate::audit::PingEvent;
+use crate::audit::Presence;
+use crate::audit::SourceSummary;
+use crate::audit::Tally;
+use crate::audit::TargetKey;
+use crate::audit::TargetTimeline;
+use crate::audit::TimeRange;
+use crate::audit::discover_log_files;
+use crate::audit::hour_start;
+use crate::audit::loader::fallback_machine;
+use crate::audit::loader::is_archive;
+use crate::audit::loader::parallel_map;
+use crate::audit::loader::read_archive;
+use crate::audit::parse_log_line;
+use crate::audit::span_start;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use eyre::Context;
+use eyre::Result;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Name of the index file in the piing cache directory.
+pub const AUDIT_INDEX_FILE: &str = "audit_index.json";
+
+/// Version of the index layout; an index of another version is rebuilt.
+const INDEX_VERSION: u32 = 4;
+
+/// Per-hour summaries of log files, kept between audits so that only new and
+/// changed files are parsed again.
+///
+/// Files are keyed by path and recognised by size and last write time. Log
+/// files only ever grow, so a file that grew is indexed again from its last
+/// indexed hour rather than from the start. Compressed files are indexed
+/// whole, with offsets counted in decompressed bytes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditIndex {
+    version: u32,
+    files: BTreeMap<PathBuf, IndexedFile>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+/// The summary of one log file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub size: u64,
+    /// Last write time in milliseconds since the Unix epoch.
+    pub modified_ms: i64,
+    /// Length of the complete lines indexed so far.
+    pub parsed: u64,
+    /// Complete lines that could not be read.
+    pub malformed: usize,
+    /// Whether the last line is unfinished.
+    pub truncated: bool,
+    pub hours: Vec<IndexedHour>,
+}
+
+/// The pings logged in one file during one hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedHour {
+    pub start: DateTime<Local>,
+    /// Byte offset of the first line logged in the hour.
+    pub offset: u64,
+    /// Byte offset just past the last line logged in the hour, so that the
+    /// hour can be read again when an audit needs only part of it.
+    pub end: u64,
+    pub targets: Vec<IndexedTarget>,
+}
+
+/// The pings of one target during an indexed hour, as counted by default:
+/// those in maintenance windows or resume grace are only counted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedTarget {
+    pub target: TargetKey,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub tally: Tally,
+    pub maintenance: usize,
+    pub resume_grace: usize,
+    /// When the target was heard from, outside maintenance and resume grace.
+    pub presence: Option<Presence>,
+}
+
+impl IndexedHour {
+    fn new(start: DateTime<Local>, offset: u64) -> Self {
+        Self {
+            start,
+            offset,
+            end: offset,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Count a ping logged in the hour by the line ending at `end`, and return
+    /// the position of its target and whether an audit keeps it by default.
+    fn record(&mut self, event: &PingEvent, end: u64) -> (usize, bool) {
+        self.end = self.end.max(end);
+        let position = self
+            .targets
+            .iter()
+            .position(|indexed| {
+                indexed.target == event.target
+                    && indexed.name == event.target_name
+                    && indexed.tags == event.tags
+            })
+            .unwrap_or_else(|| {
+                self.targets.push(IndexedTarget {
+                    target: event.target.clone(),
+                    name: event.target_name.clone(),
+                    tags: event.tags.clone(),
+                    tally: Tally::default(),
+                    maintenance: 0,
+                    resume_grace: 0,
+                    presence: None,
+                });
+                self.targets.len() - 1
+            });
+        let indexed = &mut self.targets[position];
+        let kept = match EventFilter::default().check(event) {
+            FilterVerdict::Keep => {
+                indexed.tally.record(event);
+                true
+            }
+            FilterVerdict::Maintenance => {
+                indexed.maintenance += 1;
+                false
+            }
+            FilterVerdict::ResumeGrace => {
+                indexed.resume_grace += 1;
+                false
+            }
+            FilterVerdict::OutOfScope => false,
+        };
+        (position, kept)
+    }
+}
+
+/// An hour still being indexed, with the times of the pings kept per target.
+struct PendingHour {
+    hour: IndexedHour,
+    timestamps: Vec<Vec<DateTime<Local>>>,
+}
+
+impl PendingHour {
+    fn record(&mut self, event: &PingEvent, end: u64) {
+        let (position, kept) = self.hour.record(event, end);
+        if self.timestamps.len() <= position {
+            self.timestamps.resize_with(position + 1, Vec::new);
+        }
+        if kept {
+            self.timestamps[position].push(event.timestamp);
+        }
+    }
+
+    fn finish(mut self) -> IndexedHour {
+        for (indexed, mut timestamps) in self.hour.targets.iter_mut().zip(self.timestamps) {
+            timestamps.sort_unstable();
+            indexed.presence = Presence::of(&timestamps);
+        }
+        self.hour
+    }
+}
+
+/// What indexing the lines of a log file found.
+#[derive(Debug)]
+struct IndexedLines {
+    hours: Vec<IndexedHour>,
+    /// Length of the complete lines.
+    parsed: u64,
+    /// Lines that could not be read, not counting those indexed before.
+    malformed: usize,
+    /// Whether the last line is unfinished.
+    truncated: bool,
+}
+
+/// Index the complete lines of a log file read from byte `offset`, adding
+/// to the hours already indexed. Lines before byte `known` that belong to
+/// hours before `resumed` were indexed already and are skipped.
+fn index_lines(
+    mut reader: impl BufRead,
+    offset: u64,
+    hours: Vec<IndexedHour>,
+    (known, resumed): (u64, Option<DateTime<Local>>),
+) -> std::io::Result<IndexedLines> {
+    let mut done: BTreeMap<DateTime<Local>, IndexedHour> =
+        hours.into_iter().map(|hour| (hour.start, hour)).collect();
+    let mut pending: BTreeMap<DateTime<Local>, PendingHour> = BTreeMap::new();
+    let mut position = offset;
+    let mut malformed = 0;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        // A line without its newline is still being written.
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        let line_start = position;
+        position += read as u64;
+        let event = match std::str::from_utf8(&line).map_or(LogLine::Malformed, parse_log_line) {
+            LogLine::Ping(event) => event,
+            LogLine::Other => continue,
+            LogLine::Malformed => {
+                malformed += usize::from(line_start >= known);
+                continue;
+            }
+        };
+        let start = hour_start(event.timestamp);
+        if line_start < known && resumed.is_some_and(|resumed| start < resumed) {
+            continue;
+        }
+        if let Some(hour) = done.get_mut(&start) {
+            // A line logged late for an hour that is already complete.
+            hour.record(&event, position);
+            continue;
+        }
+        pending
+            .entry(start)
+            .or_insert_with(|| PendingHour {
+                hour: IndexedHour::new(start, line_start),
+                timestamps: Vec::new(),
+            })
+            .record(&event, position);
+        // Lines are written in time order give or take a moment, so hours
+        // well before the latest line are complete.
+        while let Some(entry) = pending.first_entry()
+            && *entry.key() < start - Duration::hours(1)
+        {
+            let hour = entry.remove().finish();
+            done.insert(hour.start, hour);
+        }
+    }
+    for pending in pending.into_values() {
+        let hour = pending.finish();
+        done.insert(hour.start, hour);
+    }
+    Ok(IndexedLines {
+        hours: done.into_values().collect(),
+        parsed: position,
+        malformed,
+        truncated: !line.is_empty(),
+    })
+}
+
+/// Index a new or changed log file. A plain file that only grew since its
+/// `previous` entry is indexed again from its last indexed hour.
+fn index_file(
+    log_file: &LogFile,
+    (size, modified_ms): (u64, i64),
+    previous: Option<&IndexedFile>,
+) -> Result<IndexedFile> {
+    let path = &log_file.path;
+    let previous =
+        previous.filter(|entry| log_file.compression == Compression::None && entry.parsed <= size);
+    // Index the last indexed hour again, with the lines added since.
+    let (hours, resumed) = match previous.map(|entry| entry.hours.split_last()) {
+        Some(Some((resumed, hours))) => (hours.to_vec(), Some(resumed)),
+        _ => (Vec::new(), None),
+    };
+    let offset = resumed.map_or(0, |hour| hour.offset);
+    let reader = log_file
+        .open_at(offset)
+        .wrap_err_with(|| format!("Failed to open log file: {}", path.display()))?;
+    let lines = index_lines(
+        reader,
+        offset,
+        hours,
+        (
+            previous.map_or(0, |entry| entry.parsed),
+            resumed.map(|hour| hour.start),
+        ),
+    )
+    .wrap_err_with(|| format!("Failed to read log file: {}", path.display()))?;
+    Ok(IndexedFile {
+        size,
+        modified_ms,
+        parsed: lines.parsed,
+        malformed: lines.malformed + previous.map_or(0, |entry| entry.malformed),
+        truncated: lines.truncated,
+        hours: lines.hours,
+    })
+}
+
+impl AuditIndex {
+    /// Read the index at `path`, or start an empty one when it is missing,
+    /// unreadable or of another version.
+    #[must_use]
+    pub fn load(path: &Path) -> Self {
+        let index = File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, Self>(BufReader::new(file)).ok());
+        match index {
+            Some(index) if index.version == INDEX_VERSION => index,
+            _ => Self {
+                version: INDEX_VERSION,
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Write the index to `path` if it changed since it was loaded. The index
+    /// is written beside the old one and then moved over it, so an audit
+    /// running at the same time never reads half an index.
+    ///
+    /// # Errors
+    /// Returns an error if the index cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        let temporary = path.with_extension("json.tmp");
+        let file = File::create(&temporary)
+            .wrap_err_with(|| format!("Failed to create audit index: {}", temporary.display()))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .wrap_err_with(|| format!("Failed to write audit index: {}", temporary.display()))?;
+        std::fs::rename(&temporary, path)
+            .wrap_err_with(|| format!("Failed to replace audit index: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Drop the files of a logs directory, or of its subdirectories, that no
+    /// longer exist.
+    fn forget_missing(&mut self, dir: &Path, present: &BTreeSet<PathBuf>) {
+        let before = self.files.len();
+        self.files
+            .retain(|path, _| !path.starts_with(dir) || present.contains(path));
+        self.changed |= self.files.len() != before;
+    }
+
+    /// Bring the entries of the log files up to date, indexing new and
+    /// changed files in parallel. Returns how many files were indexed.
+    fn refresh(&mut self, log_files: &[LogFile]) -> Result<usize> {
+        let mut stale = Vec::new();
+        for log_file in log_files {
+            let path = &log_file.path;
+            let metadata = std::fs::metadata(path)
+                .wrap_err_with(|| format!("Failed to read log file: {}", path.display()))?;
+            let size = metadata.len();
+            let modified_ms = metadata
+                .modified()
+                .map(|modified| DateTime::<Local>::from(modified).timestamp_millis())
+                .unwrap_or_default();
+            let unchanged = self
+                .files
+                .get(path)
+                .is_some_and(|entry| entry.size == size && entry.modified_ms == modified_ms);
+            if !unchanged {
+                stale.push((log_file, (size, modified_ms)));
+            }
+        }
+        let indexed = parallel_map(&stale, |(log_file, stat)| {
+            index_file(log_file, *stat, self.files.get(&log_file.path))
+        });
+        for ((log_file, _), entry) in stale.iter().zip(indexed) {
+            self.files.insert(log_file.path.clone(), entry?);
+            self.changed = true;
+        }
+        Ok(stale.len())
+    }
+}
+
+/// Ping tallies and target timelines read through the audit index.
+#[derive(Debug, Clone, Default)]
+pub struct HourlyEvents {
+    /// Tallies ordered by start, then by target.
+    pub hours: Vec<HourlyTally>,
+    /// Timelines ordered by target label.
+    pub timelines: Vec<TargetTimeline>,
+    /// The filter the pings were selected with.
+    pub filter: EventFilter,
+    pub source: SourceSummary,
+}
+
+/// The pings of a target kept over an hour or part of one.
+#[derive(Debug, Clone)]
+struct Piece {
+    start: DateTime<Local>,
+    target: TargetKey,
+    name: Option<String>,
+    tally: Tally,
+    presence: Option<Presence>,
+}
+
+/// What reading pings from the log files gathers: pieces per target and
+/// hour, cut at the cuts. Only the latest piece of each target keeps the
+/// times of its pings, to find its presence once the target moves on.
+#[derive(Debug, Clone)]
+struct SpanPieces {
+    cuts: Vec<DateTime<Local>>,
+    done: Vec<Piece>,
+    open: BTreeMap<TargetKey, (Piece, Vec<DateTime<Local>>)>,
+}
+
+impl SpanPieces {
+    fn new(cuts: Vec<DateTime<Local>>) -> Self {
+        Self {
+            cuts,
+            done: Vec::new(),
+            open: BTreeMap::new(),
+        }
+    }
+
+    fn close(&mut self, (mut piece, mut timestamps): (Piece, Vec<DateTime<Local>>)) {
+        timestamps.sort_unstable();
+        piece.presence = Presence::of(&timestamps);
+        self.done.push(piece);
+    }
+
+    /// Continue with the latest piece of a target from later pings.
+    fn add(&mut self, (later, mut timestamps): (Piece, Vec<DateTime<Local>>)) {
+        match self.open.get_mut(&later.target) {
+            Some((piece, earlier)) if piece.start == later.start => {
+                if later.name.is_some() {
+                    piece.name = later.name;
+                }
+                piece.tally.merge(&later.tally);
+                earlier.append(&mut timestamps);
+            }
+            _ => {
+                if let Some(earlier) = self.open.insert(later.target.clone(), (later, timestamps)) {
+                    self.close(earlier);
+                }
+            }
+        }
+    }
+
+    /// Every piece, in no particular order.
+    fn into_pieces(mut self) -> Vec<Piece> {
+        for open in std::mem::take(&mut self.open).into_values() {
+            self.close(open);
+        }
+        self.done
+    }
+}
+
+impl Accumulator for SpanPieces {
+    fn record(&mut self, event: &PingEvent) {
+        let start = span_start(event.timestamp, &self.cuts);
+        if self
+            .open
+            .get(&event.target)
+            .is_some_and(|(piece, _)| piece.start != start)
+            && let Some(open) = self.open.remove(&event.target)
+        {
+            self.close(open);
+        }
+        if !self.open.contains_key(&event.target) {
+            let piece = Piece {
+                start,
+                target: event.target.clone(),
+                name: None,
+                tally: Tally::default(),
+                presence: None,
+            };
+            self.open.insert(event.target.clone(), (piece, Vec::new()));
+        }
+        let Some((piece, timestamps)) = self.open.get_mut(&event.target) else {
+            return;
+        };
+        if event.target_name.is_some() {
+            piece.name.clone_from(&event.target_name);
+        }
+        piece.tally.record(event);
+        timestamps.push(event.timestamp);
+    }
+
+    fn merge(&mut self, later: Self) {
+        self.done.extend(later.done);
+        for open in later.open.into_values() {
+            self.add(open);
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for piece in &mut self.done {
+            rekey(&mut piece.target);
+        }
+        for (mut piece, timestamps) in std::mem::take(&mut self.open).into_values() {
+            rekey(&mut piece.target);
+            self.add((piece, timestamps));
+        }
+    }
+}
+
+/// Read the part of an hour of a log file that the filter selects.
+fn read_hour(
+    log_file: &LogFile,
+    hour: &IndexedHour,
+    filter: &EventFilter,
+    view: &SpanPieces,
+) -> Result<Loaded<SpanPieces>> {
+    let path = &log_file.path;
+    let until = hour.start + Duration::hours(1);
+    let range = TimeRange::new(
+        filter.range.since.max(Some(hour.start)),
+        Some(filter.range.until.map_or(until, |bound| bound.min(until))),
+    );
+    let mut loaded = Loaded::new(
+        view.clone(),
+        EventFilter {
+            range,
+            ..filter.clone()
+        },
+    );
+    let reader = log_file
+        .open_at(hour.offset)
+        .wrap_err_with(|| format!("Failed to open log file: {}", path.display()))?;
+    loaded
+        .read_log(reader.take(hour.end - hour.offset), &mut HashMap::new())
+        .wrap_err_with(|| format!("Failed to read log file: {}", path.display()))?;
+    Ok(loaded)
+}
+
+/// Read the pings of a logs directory selected by the filter, from the index
+/// where whole hours are selected and from the log files for hours that a
+/// cut splits or that hold pings the index leaves out but the filter keeps.
+fn read_directory(
+    index: &mut AuditIndex,
+    dir: &Path,
+    loaded: &mut Loaded<SpanPieces>,
+    pieces: &mut Vec<Piece>,
+    machines: &mut HashMap<String, usize>,
+) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let log_files: Vec<LogFile> = discover_log_files(dir)?;
+    loaded.source.files_found += log_files.len();
+    // Key files by absolute path so that every way of naming a directory
+    // shares the same entries.
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let paths: BTreeSet<PathBuf> = log_files.iter().map(|file| absolute(&file.path)).collect();
+    index.forget_missing(&absolute(dir), &paths);
+
+    let filter = loaded.filter.clone();
+    let empty = SpanPieces::new(loaded.view.cuts.clone());
+    let selected: Vec<LogFile> = log_files
+        .into_iter()
+        .filter(|log_file| log_file.may_overlap(&filter.range))
+        .map(|log_file| LogFile {
+            path: absolute(&log_file.path),
+            ..log_file
+        })
+        .collect();
+    loaded.source.files_read += selected.len();
+    loaded.source.files_indexed += selected.len() - index.refresh(&selected)?;
+
+    for log_file in &selected {
+        let entry = &index.files[&log_file.path];
+        loaded.source.malformed_lines += entry.malformed;
+        loaded.source.truncated_lines += usize::from(entry.truncated);
+        for hour in &entry.hours {
+            let until = hour.start + Duration::hours(1);
+            let cut = empty
+                .cuts
+                .iter()
+                .any(|cut| *cut > hour.start && *cut < until);
+            if !cut && !filter.range.contains(hour.start) {
+                continue;
+            }
+            // The index leaves out pings in maintenance windows and resume
+            // grace, so hours with pings the filter includes are read again.
+            let included = hour.targets.iter().any(|indexed| {
+                (filter.include_maintenance && indexed.maintenance > 0)
+                    || (filter.include_resume_grace && indexed.resume_grace > 0)
+            });
+            if cut || included {
+                let read = read_hour(log_file, hour, &filter, &empty)?;
+                loaded.view.merge(read.view);
+                loaded.source.excluded_maintenance += read.source.excluded_maintenance;
+                loaded.source.excluded_resume_grace += read.source.excluded_resume_grace;
+                continue;
+            }
+            for indexed in &hour.targets {
+                if let Some(machine) = &indexed.target.machine {
+                    *machines.entry(machine.clone()).or_default() +=
+                        indexed.tally.events + indexed.maintenance + indexed.resume_grace;
+                }
+                if !filter.selects(indexed.name.as_deref(), &indexed.target.host, &indexed.tags) {
+                    continue;
+                }
+                loaded.source.excluded_maintenance += indexed.maintenance;
+                loaded.source.excluded_resume_grace += indexed.resume_grace;
+                pieces.push(Piece {
+                    start: hour.start,
+                    target: indexed.target.clone(),
+                    name: indexed.name.clone(),
+                    tally: indexed.tally.clone(),
+                    presence: indexed.presence.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the pings selected by the filter from the given logs directories and
+/// zip archives, as hourly tallies split at the cuts and at the bounds of
+/// the filter's time range.
+///
+/// Log files in directories are summarised in the index at `index_path`,
+/// which is brought up to date first, or in an index kept in memory only
+/// when there is no path. The index counts pings the way audits do by
+/// default, so hours holding pings in maintenance windows or resume grace
+/// that the filter includes are read from the log files too. Machines are
+/// credited as by [`load_into`].
+///
+/// [`load_into`]: crate::audit::load_into
+///
+/// # Errors
+/// Returns an error if a directory, archive or log file cannot be read
+pub fn load_hourly_events(
+    index_path: Option<&Path>,
+    sources: &[PathBuf],
+    filter: &EventFilter,
+    cuts: &[DateTime<Local>],
+) -> Result<HourlyEvents> {
+    let mut index = index_path.map_or_else(AuditIndex::default, AuditIndex::load);
+    let mut cuts = cuts.to_vec();
+    cuts.extend(filter.range.since);
+    cuts.extend(filter.range.until);
+
+    let mut source = SourceSummary {
+        sources: sources.to_vec(),
+        ..SourceSummary::default()
+    };
+    let mut pieces = Vec::new();
+    for path in sources {
+        let first = pieces.len();
+        let mut loaded = Loaded::new(SpanPieces::new(cuts.clone()), filter.clone());
+        let mut machines = HashMap::new();
+        if is_archive(path) {
+            read_archive(path, &mut loaded, &mut machines)?;
+        } else {
+            read_directory(&mut index, path, &mut loaded, &mut pieces, &mut machines)?;
+        }
+        source.add_counts(&loaded.source);
+        pieces.extend(loaded.view.into_pieces());
+
+        let fallback = fallback_machine(machines, path);
+        for piece in &mut pieces[first..] {
+            piece.target.machine.get_or_insert_with(|| fallback.clone());
+        }
+    }
+    if let Some(index_path) = index_path
+        && let Err(error) = index.save(index_path)
+    {
+        warn!("Failed to save the audit index: {error:#}");
+    }
+
+    let machines: BTreeSet<&String> = pieces
+        .iter()
+        .filter_map(|piece| piece.target.machine.as_ref())
+        .collect();
+    source.machines = machines.into_iter().cloned().collect();
+    if source.machines.len() <= 1 {
+        for piece in &mut pieces {
+            piece.target.machine = None;
+        }
+    }
+    pieces.retain(|piece| piece.tally.events > 0);
+    pieces.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.target.cmp(&b.target)));
+    source.events = pieces.iter().map(|piece| piece.tally.events).sum();
+
+    Ok(HourlyEvents {
+        timelines: timelines(&pieces),
+        hours: merge_pieces(pieces),
+        filter: filter.clone(),
+        source,
+    })
+}
+
+/// Merge the pieces of the same target and span, from several files or
+/// machines, into hourly tallies.
+fn merge_pieces(pieces: Vec<Piece>) -> Vec<HourlyTally> {
+    let mut hours: Vec<HourlyTally> = Vec::new();
+    for piece in pieces {
+        if let Some(hour) = hours.last_mut()
+            && hour.start == piece.start
+            && hour.target == piece.target
+        {
+            hour.tally.merge(&piece.tally);
+            if piece.name.is_some() {
+                hour.name = piece.name;
+            }
+            continue;
+        }
+        hours.push(HourlyTally {
+            start: piece.start,
+            target: piece.target,
+            name: piece.name,
+            tally: piece.tally,
+        });
+    }
+    hours
+}
+
+/// The timeline of every target, labelled by the last name it was logged with.
+fn timelines(pieces: &[Piece]) -> Vec<TargetTimeline> {
+    let mut names: HashMap<&TargetKey, &str> = HashMap::new();
+    for piece in pieces {
+        if let Some(name) = &piece.name {
+            names.insert(&piece.target, name);
+        }
+    }
+    let mut presences: BTreeMap<String, Vec<&Presence>> = BTreeMap::new();
+    for piece in pieces {
+        let label = match names.get(&piece.target) {
+            Some(name) => format!("{name} ({})", piece.target),
+            None => piece.target.to_string(),
+        };
+        presences
+            .entry(label)
+            .or_default()
+            .extend(piece.presence.as_ref());
+    }
+    presences
+        .into_iter()
+        .filter_map(|(label, presences)| Presence::timeline(label, presences))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(timestamp: &str, success: bool, maintenance: bool) -> String {
+        format!(
+            r#"{{"timestamp":"{timestamp}","level":"INFO","fields":{{"target":"google_dns","host":"8.8.8.8","mode":"icmp","success":{success},"latency_ms":12,"maintenance":{maintenance}}}}}"#
+        ) + "\n"
+    }
+
+    #[test]
+    fn growing_files_are_indexed_again_from_their_last_hour() {
+        let mut log = String::new();
+        log += &line("2025-12-01T08:59:50-05:00", true, false);
+        log += &line("2025-12-01T09:00:00-05:00", false, false);
+        log += "{\"timestamp\":\"2025-12-01T09:00:05\n";
+        log += &line("2025-12-01T09:00:10-05:00", true, true);
+        let lines = index_lines(log.as_bytes(), 0, Vec::new(), (0, None)).unwrap();
+        assert_eq!(lines.parsed, log.len() as u64);
+        assert_eq!(lines.hours.len(), 2);
+        assert_eq!(lines.malformed, 1);
+        assert!(!lines.truncated);
+
+        // More lines, the last of which is still being written.
+        let known = log.len() as u64;
+        log += &line("2025-12-01T09:00:20-05:00", true, false);
+        let complete = log.len() as u64;
+        log += r#"{"timestamp":"2025-12-01T09:00:30"#;
+        let mut hours = lines.hours;
+        let resumed = hours.pop().unwrap();
+        let offset = usize::try_from(resumed.offset).unwrap();
+        let lines = index_lines(
+            &log.as_bytes()[offset..],
+            resumed.offset,
+            hours,
+            (known, Some(resumed.start)),
+        )
+        .unwrap();
+        assert_eq!(lines.parsed, complete);
+        // The damaged line was counted the first time round.
+        assert_eq!(lines.malformed, 0);
+        assert!(lines.truncated);
+        let hours = lines.hours;
+
+        let [eight, nine] = &hours[..] else {
+            panic!("expected two hours, got {hours:?}");
+        };
+        assert_eq!(eight.targets[0].tally.events, 1);
+        let nine = &nine.targets[0];
+        assert_eq!(nine.tally.events, 2);
+        assert_eq!(nine.tally.failures, 1);
+        assert_eq!(nine.maintenance, 1);
+        assert_eq!(nine.presence.as_ref().unwrap().spacing_ms, 20_000);
+    }
+
+    #[test]
+    fn included_maintenance_hours_are_read_from_the_logs() {
+        let dir = std::env::temp_dir().join(format!("piing_audit_index_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut log = String::new();
+        log += &line("2025-12-01T08:59:50-05:00", true, false);
+        log += &line("2025-12-01T09:00:00-05:00", false, true);
+        log += &line("2025-12-01T09:00:10-05:00", true, false);
+        std::fs::write(dir.join("piing_2025-12-01_08-30-00.log.ndjson"), log).unwrap();
+
+        let sources = [dir.clone()];
+        let excluded = load_hourly_events(None, &sources, &EventFilter::default(), &[]);
+        let filter = EventFilter {
+            include_maintenance: true,
+            ..EventFilter::default()
+        };
+        let included = load_hourly_events(None, &sources, &filter, &[]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (excluded, included) = (excluded.unwrap(), included.unwrap());
+        assert_eq!(excluded.source.events, 2);
+        assert_eq!(excluded.source.excluded_maintenance, 1);
+        assert_eq!(included.source.events, 3);
+        assert_eq!(included.source.excluded_maintenance, 0);
+        let failures: usize = included.hours.iter().map(|hour| hour.tally.failures).sum();
+        assert_eq!(failures, 1);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::audit::Cell;
+use crate::audit::CompareOptions;
+use crate::audit::ComparedPeriods;
+use crate::audit::DeltaInterval;
+use crate::audit::ReportBody;
+use crate::audit::Table;
+use crate::audit::TargetComparison;
+use crate::audit::TimeRange;
+use crate::audit::compare_periods;
+use serde::Serialize;
+
+/// The `audit compare` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompareReport {
+    pub a: TimeRange,
+    pub b: TimeRange,
+    pub confidence: f64,
+    pub resamples: usize,
+    pub targets: Vec<TargetComparison>,
+}
+
+impl CompareReport {
+    #[must_use]
+    pub fn build(periods: &ComparedPeriods, options: &CompareOptions) -> Self {
+        let (a, b) = periods.ranges();
+        Self {
+            targets: compare_periods(periods, options),
+            a,
+            b,
+            confidence: options.confidence,
+            resamples: options.resamples,
+        }
+    }
+}
+
+fn interval_cell(interval: Option<DeltaInterval>) -> Cell {
+    interval.map_or(Cell::Missing, |interval| {
+        format!("{:+.2} to {:+.2}", interval.low, interval.high).into()
+    })
+}
+
+fn delta_ms(delta: Option<f64>) -> Cell {
+    delta.map_or(Cell::Missing, Cell::Delta)
+}
+
+impl ReportBody for CompareReport {
+    fn tables(&self) -> Vec<Table> {
+        let ci = format!("{}% CI", self.confidence);
+        let mut failures = Table::new(
+            "Failure Rate",
+            &[
+                "Target",
+                "A events",
+                "A rate",
+                "B events",
+                "B rate",
+                "Change pp",
+                ci.as_str(),
+                "Verdict",
+            ],
+        );
+        for target in &self.targets {
+            failures.push_row(vec![
+                target.target.as_str().into(),
+                target.a.events.into(),
+                target.a.failure_rate.map_or(Cell::Missing, Cell::Percent),
+                target.b.events.into(),
+                target.b.failure_rate.map_or(Cell::Missing, Cell::Percent),
+                target.failure_rate_delta.map_or(Cell::Missing, Cell::Delta),
+                interval_cell(target.failure_rate_interval),
+                target.failure_rate_change.to_string().into(),
+            ]);
+        }
+        failures.push_note(format!("A: {}", self.a));
+        failures.push_note(format!("B: {}", self.b));
+        if self.targets.is_empty() {
+            failures.push_note("No ping events in either period.");
+            return vec![failures];
+        }
+        failures.push_note(format!(
+            "Intervals come from {} bootstrap resamples of whole hours of pings; a change is better or worse only when its interval excludes zero.",
+            self.resamples
+        ));
+
+        let mut incidents = Table::new(
+            "Incidents",
+            &[
+                "Target",
+                "A incidents",
+                "A per day",
+                "B incidents",
+                "B per day",
+                "Change per day",
+            ],
+        );
+        for target in &self.targets {
+            incidents.push_row(vec![
+                target.target.as_str().into(),
+                target.a.incidents.into(),
+                target
+                    .a
+                    .incidents_per_day
+                    .map_or(Cell::Missing, Cell::Number),
+                target.b.incidents.into(),
+                target
+                    .b
+                    .incidents_per_day
+                    .map_or(Cell::Missing, Cell::Number),
+                target
+                    .incidents_per_day_delta
+                    .map_or(Cell::Missing, Cell::Delta),
+            ]);
+        }
+        incidents
+            .push_note("Incidents per day are counted over monitored time, without data gaps.");
+
+        let mut latency = Table::new(
+            "Latency",
+            &[
+                "Target",
+                "A p50",
+                "B p50",
+                "Change p50",
+                "Change p95",
+                "Change p99",
+                "Change mean",
+                ci.as_str(),
+                "Verdict",
+            ],
+        );
+        for target in &self.targets {
+            latency.push_row(vec![
+                target.target.as_str().into(),
+                target.a.latency.p50_ms.into(),
+                target.b.latency.p50_ms.into(),
+                delta_ms(target.p50_delta_ms),
+                delta_ms(target.p95_delta_ms),
+                delta_ms(target.p99_delta_ms),
+                target.mean_latency_delta.map_or(Cell::Missing, Cell::Delta),
+                interval_cell(target.mean_latency_interval),
+                target.mean_latency_change.to_string().into(),
+            ]);
+        }
+        latency.push_note("Latencies and their changes are in milliseconds; changes are B - A.");
+        vec![failures, incidents, latency]
+    }
+}
@@ -0,0 +1,513 @@
+use crate::audit::Accumulator;
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::IncidentGrouping;
+use crate::audit::IncidentOptions;
+use crate::audit::LatencyStats;
+use crate::audit::LatencySummary;
+use crate::audit::PingEvent;
+use crate::audit::ReportPeriod;
+use crate::audit::TargetKey;
+use crate::audit::TimeRange;
+use crate::audit::extract_incidents;
+use crate::audit::incident_stats;
+use crate::audit::serialize_seconds;
+use chrono::Duration;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// Settings for comparing two periods.
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Number of bootstrap resamples per interval.
+    pub resamples: usize,
+    /// Confidence level of the intervals, as a percentage.
+    pub confidence: f64,
+    pub gap_factor: i32,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            resamples: 1_000,
+            confidence: 95.0,
+            gap_factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
+/// How a metric moved from period A to period B, judged by whether its
+/// confidence interval excludes zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Better,
+    Worse,
+    /// The interval includes zero: the difference may be noise.
+    Unclear,
+    /// Too little data in one of the periods to tell.
+    Unknown,
+}
+
+impl Change {
+    /// Judge a change of a metric for which lower is better.
+    fn of(interval: Option<DeltaInterval>) -> Self {
+        match interval {
+            Some(interval) if interval.high < 0.0 => Change::Better,
+            Some(interval) if interval.low > 0.0 => Change::Worse,
+            Some(_) => Change::Unclear,
+            None => Change::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Change::Better => "better",
+            Change::Worse => "worse",
+            Change::Unclear => "unclear",
+            Change::Unknown => "-",
+        })
+    }
+}
+
+/// A bootstrap confidence interval of the difference B - A.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DeltaInterval {
+    pub low: f64,
+    pub high: f64,
+}
+
+/// One target's (or all targets') pings in one period.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PeriodSummary {
+    pub events: usize,
+    pub failures: usize,
+    pub failure_rate: Option<f64>,
+    pub incidents: usize,
+    /// Time covered by pings, less data gaps.
+    #[serde(rename = "observed_seconds", serialize_with = "serialize_seconds")]
+    pub observed: Duration,
+    pub incidents_per_day: Option<f64>,
+    pub latency: LatencySummary,
+}
+
+/// A target's metrics in both periods, with the changes between them.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetComparison {
+    pub target: String,
+    pub a: PeriodSummary,
+    pub b: PeriodSummary,
+    /// Change in failure rate, in percentage points.
+    pub failure_rate_delta: Option<f64>,
+    pub failure_rate_interval: Option<DeltaInterval>,
+    pub failure_rate_change: Change,
+    pub incidents_per_day_delta: Option<f64>,
+    /// Change in mean latency, in milliseconds.
+    pub mean_latency_delta: Option<f64>,
+    pub mean_latency_interval: Option<DeltaInterval>,
+    pub mean_latency_change: Change,
+    pub p50_delta_ms: Option<f64>,
+    pub p95_delta_ms: Option<f64>,
+    pub p99_delta_ms: Option<f64>,
+}
+
+/// One hour of one target's pings: the unit the bootstrap resamples, so that
+/// failures clustered in an outage are not mistaken for independent evidence.
+#[derive(Debug, Clone, Copy, Default)]
+struct HourBlock {
+    events: u64,
+    failures: u64,
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+impl HourBlock {
+    fn add(&mut self, other: &HourBlock) {
+        self.events += other.events;
+        self.failures += other.failures;
+        self.latency_sum += other.latency_sum;
+        self.latency_count += other.latency_count;
+    }
+}
+
+/// What comparing two periods gathers from the pings of each: the target
+/// histories, and per target the counts, latencies and hour blocks.
+#[derive(Debug, Clone)]
+pub struct ComparedPeriods {
+    a: PeriodPings,
+    b: PeriodPings,
+}
+
+impl ComparedPeriods {
+    /// Gather the pings of periods `a` and `b`, which must not overlap.
+    #[must_use]
+    pub fn new(a: TimeRange, b: TimeRange) -> Self {
+        Self {
+            a: PeriodPings::new(a),
+            b: PeriodPings::new(b),
+        }
+    }
+
+    /// The periods compared, A first.
+    #[must_use]
+    pub fn ranges(&self) -> (TimeRange, TimeRange) {
+        (self.a.range, self.b.range)
+    }
+}
+
+impl Accumulator for ComparedPeriods {
+    fn record(&mut self, event: &PingEvent) {
+        for period in [&mut self.a, &mut self.b] {
+            if period.range.contains(event.timestamp) {
+                period.record(event);
+            }
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        self.a.merge(later.a);
+        self.b.merge(later.b);
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        self.a.retarget(&mut rekey);
+        self.b.retarget(&mut rekey);
+    }
+}
+
+/// The pings of one period.
+#[derive(Debug, Clone)]
+struct PeriodPings {
+    range: TimeRange,
+    histories: Histories,
+    targets: BTreeMap<TargetKey, TargetPings>,
+}
+
+impl PeriodPings {
+    fn new(range: TimeRange) -> Self {
+        Self {
+            range,
+            histories: Histories::default(),
+            targets: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, event: &PingEvent) {
+        self.histories.record(event);
+        if let Some(pings) = self.targets.get_mut(&event.target) {
+            pings.record(event);
+        } else {
+            let mut pings = TargetPings::default();
+            pings.record(event);
+            self.targets.insert(event.target.clone(), pings);
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        self.histories.merge(later.histories);
+        for (target, pings) in later.targets {
+            self.targets.entry(target).or_default().merge(&pings);
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        self.histories.retarget(&mut rekey);
+        for (mut target, pings) in std::mem::take(&mut self.targets) {
+            rekey(&mut target);
+            self.targets.entry(target).or_default().merge(&pings);
+        }
+    }
+}
+
+/// One target's pings in a period, or a group's.
+#[derive(Debug, Clone, Default)]
+struct TargetPings {
+    events: usize,
+    failures: usize,
+    latency: LatencyStats,
+    /// Hour blocks by hours since the Unix epoch.
+    hours: BTreeMap<i64, HourBlock>,
+}
+
+impl TargetPings {
+    fn record(&mut self, event: &PingEvent) {
+        let block = self
+            .hours
+            .entry(event.timestamp.timestamp().div_euclid(3_600))
+            .or_default();
+        self.events += 1;
+        block.events += 1;
+        if !event.success {
+            self.failures += 1;
+            block.failures += 1;
+        }
+        if event.success
+            && let Some(latency_ms) = event.latency_ms
+        {
+            self.latency.record(latency_ms);
+            block.latency_sum += latency_ms;
+            block.latency_count += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &TargetPings) {
+        self.events += other.events;
+        self.failures += other.failures;
+        self.latency.merge(&other.latency);
+        for (hour, block) in &other.hours {
+            self.hours.entry(*hour).or_default().add(block);
+        }
+    }
+}
+
+/// A small deterministic generator (`SplitMix64`), so that the same logs always
+/// give the same intervals.
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly chosen index below `len`, which must not be zero.
+    fn index(&mut self, len: usize) -> usize {
+        let len = u64::try_from(len).unwrap_or(u64::MAX);
+        usize::try_from(self.next() % len).unwrap_or_default()
+    }
+}
+
+struct GroupPeriod {
+    summary: PeriodSummary,
+    blocks: Vec<HourBlock>,
+}
+
+fn summarise(
+    period: &PeriodPings,
+    grouping: IncidentGrouping,
+    gap_factor: i32,
+) -> BTreeMap<String, GroupPeriod> {
+    let options = IncidentOptions {
+        grouping,
+        gap_factor,
+        ..IncidentOptions::default()
+    };
+    let histories = &period.histories;
+    let incidents = extract_incidents(histories, &options);
+    let stats: BTreeMap<String, (usize, Duration)> =
+        incident_stats(histories, &incidents, &options, ReportPeriod::All)
+            .into_iter()
+            .map(|stats| (stats.group, (stats.incidents, stats.observed)))
+            .collect();
+
+    let mut periods = BTreeMap::new();
+    for (group, members) in histories.groups(grouping) {
+        let mut pings = TargetPings::default();
+        for member in members {
+            if let Some(target) = period.targets.get(&member.target) {
+                pings.merge(target);
+            }
+        }
+        let (incidents, observed) = stats.get(&group).copied().unwrap_or_default();
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "counts and millisecond spans stay far below 2^52"
+        )]
+        let summary = PeriodSummary {
+            events: pings.events,
+            failures: pings.failures,
+            failure_rate: (pings.events > 0)
+                .then(|| pings.failures as f64 / pings.events as f64 * 100.0),
+            incidents,
+            observed,
+            incidents_per_day: (observed > Duration::zero()).then(|| {
+                incidents as f64 / observed.num_milliseconds() as f64
+                    * Duration::days(1).num_milliseconds() as f64
+            }),
+            latency: pings.latency.summary(),
+        };
+        periods.insert(
+            group,
+            GroupPeriod {
+                summary,
+                blocks: pings.hours.into_values().collect(),
+            },
+        );
+    }
+    periods
+}
+
+/// Bootstrap the confidence interval of `metric(B) - metric(A)` by resampling
+/// hour blocks of each period with replacement.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss,
+    reason = "the quantile index is clamped to the number of resamples"
+)]
+fn bootstrap(
+    a: &[HourBlock],
+    b: &[HourBlock],
+    options: &CompareOptions,
+    rng: &mut SplitMix64,
+    metric: impl Fn(&HourBlock) -> Option<f64>,
+) -> Option<DeltaInterval> {
+    if a.len() < 2 || b.len() < 2 || options.resamples == 0 {
+        return None;
+    }
+    let mut resample = |blocks: &[HourBlock]| {
+        let mut total = HourBlock::default();
+        for _ in 0..blocks.len() {
+            total.add(&blocks[rng.index(blocks.len())]);
+        }
+        metric(&total)
+    };
+    let mut deltas: Vec<f64> = (0..options.resamples)
+        .filter_map(|_| Some(resample(b)? - resample(a)?))
+        .collect();
+    if deltas.len() < options.resamples / 2 {
+        return None;
+    }
+    deltas.sort_by(f64::total_cmp);
+    let tail = (1.0 - options.confidence / 100.0) / 2.0;
+    let quantile = |share: f64| {
+        let index = (share * (deltas.len() - 1) as f64).round() as usize;
+        deltas[index.min(deltas.len() - 1)]
+    };
+    Some(DeltaInterval {
+        low: quantile(tail),
+        high: quantile(1.0 - tail),
+    })
+}
+
+#[expect(clippy::cast_precision_loss, reason = "counts stay far below 2^52")]
+fn failure_rate(block: &HourBlock) -> Option<f64> {
+    (block.events > 0).then(|| block.failures as f64 / block.events as f64 * 100.0)
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "ping counts stay far below 2^52"
+)]
+fn mean_latency(block: &HourBlock) -> Option<f64> {
+    (block.latency_count > 0).then(|| block.latency_sum / block.latency_count as f64)
+}
+
+fn percentile_delta(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    Some(b? - a?)
+}
+
+/// Compare every target between the pings of period A and of period B,
+/// preceded by all targets together when there are several.
+#[must_use]
+pub fn compare_periods(
+    periods: &ComparedPeriods,
+    options: &CompareOptions,
+) -> Vec<TargetComparison> {
+    let mut rng = SplitMix64(0x5EED);
+    let mut comparisons = Vec::new();
+    let targets: BTreeSet<&TargetKey> = periods
+        .a
+        .targets
+        .keys()
+        .chain(periods.b.targets.keys())
+        .collect();
+    for grouping in [IncidentGrouping::All, IncidentGrouping::Target] {
+        if grouping == IncidentGrouping::All && targets.len() <= 1 {
+            continue;
+        }
+        let mut periods_a = summarise(&periods.a, grouping, options.gap_factor);
+        let mut periods_b = summarise(&periods.b, grouping, options.gap_factor);
+        let groups: BTreeSet<String> = periods_a.keys().chain(periods_b.keys()).cloned().collect();
+        for group in groups {
+            let a = periods_a.remove(&group);
+            let b = periods_b.remove(&group);
+            let (blocks_a, blocks_b) = (
+                a.as_ref().map(|a| a.blocks.as_slice()).unwrap_or_default(),
+                b.as_ref().map(|b| b.blocks.as_slice()).unwrap_or_default(),
+            );
+            let failure_rate_interval =
+                bootstrap(blocks_a, blocks_b, options, &mut rng, failure_rate);
+            let mean_latency_interval =
+                bootstrap(blocks_a, blocks_b, options, &mut rng, mean_latency);
+            let a = a.map(|a| a.summary).unwrap_or_default();
+            let b = b.map(|b| b.summary).unwrap_or_default();
+            comparisons.push(TargetComparison {
+                target: group,
+                failure_rate_delta: b.failure_rate.zip(a.failure_rate).map(|(b, a)| b - a),
+                failure_rate_interval,
+                failure_rate_change: Change::of(failure_rate_interval),
+                incidents_per_day_delta: b
+                    .incidents_per_day
+                    .zip(a.incidents_per_day)
+                    .map(|(b, a)| b - a),
+                mean_latency_delta: b.latency.mean_ms.zip(a.latency.mean_ms).map(|(b, a)| b - a),
+                mean_latency_interval,
+                mean_latency_change: Change::of(mean_latency_interval),
+                p50_delta_ms: percentile_delta(a.latency.p50_ms, b.latency.p50_ms),
+                p95_delta_ms: percentile_delta(a.latency.p95_ms, b.latency.p95_ms),
+                p99_delta_ms: percentile_delta(a.latency.p99_ms, b.latency.p99_ms),
+                a,
+                b,
+            });
+        }
+    }
+    comparisons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use chrono::Local;
+    use chrono::TimeZone;
+
+    fn hours(day: u32, fails_every: Option<u32>) -> Vec<PingEvent> {
+        let start = Local.with_ymd_and_hms(2025, 12, day, 0, 0, 0).unwrap();
+        (0..24 * 60)
+            .map(|minute| {
+                let at = start + Duration::minutes(i64::from(minute));
+                let success = fails_every.is_none_or(|every| minute % every != 0);
+                PingEvent::test("8.8.8.8", at, success).with_latency(20.0)
+            })
+            .collect()
+    }
+
+    fn day(day: u32) -> TimeRange {
+        let start: DateTime<Local> = Local.with_ymd_and_hms(2025, 12, day, 0, 0, 0).unwrap();
+        TimeRange::new(Some(start), Some(start + Duration::days(1)))
+    }
+
+    fn compared(events: &[PingEvent], a: TimeRange, b: TimeRange) -> ComparedPeriods {
+        let mut periods = ComparedPeriods::new(a, b);
+        for event in events {
+            periods.record(event);
+        }
+        periods
+    }
+
+    #[test]
+    fn fewer_failures_are_better_and_equal_periods_are_unclear() {
+        let mut events = hours(1, Some(10));
+        events.extend(hours(2, None));
+        events.extend(hours(3, None));
+        let options = CompareOptions::default();
+
+        let improved = &compare_periods(&compared(&events, day(1), day(2)), &options)[0];
+        assert_eq!(improved.a.failures, 144);
+        assert!((improved.failure_rate_delta.unwrap() + 10.0).abs() < 1e-9);
+        assert_eq!(improved.failure_rate_change, Change::Better);
+        assert_eq!(improved.p50_delta_ms, Some(0.0));
+
+        let same = &compare_periods(&compared(&events, day(2), day(3)), &options)[0];
+        assert_eq!(same.failure_rate_change, Change::Unclear);
+        assert_eq!(same.mean_latency_change, Change::Unclear);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::IncidentGrouping;
+use crate::audit::Run;
+use crate::audit::gap_limit;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+/// How widely a correlated incident spread across the monitored targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureScope {
+    /// Every target with data failed at once, which points at the local
+    /// network or the uplink.
+    AllTargets,
+    /// Several, but not all, targets failed at once.
+    Subset,
+    /// Only one target failed, which points at that host.
+    SingleTarget,
+}
+
+impl FailureScope {
+    pub const ALL: [FailureScope; 3] = [
+        FailureScope::AllTargets,
+        FailureScope::Subset,
+        FailureScope::SingleTarget,
+    ];
+
+    fn classify(failing: usize, monitored: usize) -> Self {
+        if failing <= 1 {
+            FailureScope::SingleTarget
+        } else if failing >= monitored {
+            FailureScope::AllTargets
+        } else {
+            FailureScope::Subset
+        }
+    }
+}
+
+impl Display for FailureScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FailureScope::AllTargets => "all targets",
+            FailureScope::Subset => "subset",
+            FailureScope::SingleTarget => "single target",
+        })
+    }
+}
+
+/// Settings for correlating failures across targets.
+#[derive(Debug, Clone)]
+pub struct CorrelationOptions {
+    /// Failures within the same bucket of this length happened at the same time.
+    pub bucket: Duration,
+    /// Silences longer than this many times a target's usual interval leave
+    /// its state unknown instead of carrying its last outcome forward.
+    pub gap_factor: i32,
+}
+
+impl Default for CorrelationOptions {
+    fn default() -> Self {
+        Self {
+            bucket: Duration::seconds(1),
+            gap_factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
+/// A period during which at least one target was failing, with the targets
+/// that failed together.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrelatedIncident {
+    /// Time of the first failed ping.
+    pub start: DateTime<Local>,
+    /// Time of the ping that cleared the last failure, or of the last
+    /// failure when the logs end or a data gap starts before recovery.
+    pub end: DateTime<Local>,
+    pub scope: FailureScope,
+    /// The largest set of targets failing at the same time.
+    pub targets: Vec<String>,
+    /// Targets with data when that set was failing.
+    pub monitored: usize,
+    /// The largest set of machines with a target failing at the same time;
+    /// empty unless the logs of several machines are audited together.
+    pub machines: Vec<String>,
+    /// Machines with data when that set was failing.
+    pub monitored_machines: usize,
+    pub failed_probes: usize,
+    pub recovered: bool,
+}
+
+impl CorrelatedIncident {
+    fn starting(start: DateTime<Local>) -> Self {
+        Self {
+            start,
+            end: start,
+            scope: FailureScope::SingleTarget,
+            targets: Vec::new(),
+            monitored: 0,
+            machines: Vec::new(),
+            monitored_machines: 0,
+            failed_probes: 0,
+            recovered: false,
+        }
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Whether a target failed on every machine with data at the same time,
+    /// which points at an outage shared by the whole site.
+    #[must_use]
+    pub fn hit_every_machine(&self) -> bool {
+        self.machines.len() > 1 && self.machines.len() >= self.monitored_machines
+    }
+}
+
+/// Find periods during which any target was failing and classify each by the
+/// largest set of targets failing at the same time.
+///
+/// Runs of pings are walked in time buckets. A target counts as failing in a
+/// bucket when a run of failures started in it, or when its last run failed
+/// and it has not gone silent for longer than its gap limit since the run
+/// ended.
+#[must_use]
+pub fn correlate_failures(
+    histories: &Histories,
+    options: &CorrelationOptions,
+) -> Vec<CorrelatedIncident> {
+    let groups = histories.groups(IncidentGrouping::Target);
+    let labels: Vec<&String> = groups.keys().collect();
+    let machine_of: Vec<Option<&String>> = groups
+        .values()
+        .map(|members| {
+            members
+                .first()
+                .and_then(|history| history.target.machine.as_ref())
+        })
+        .collect();
+    let limits: Vec<Duration> = groups
+        .values()
+        .map(|members| gap_limit(members, options.gap_factor))
+        .collect();
+    // Each run with its target, in order of first ping.
+    let mut runs: Vec<(usize, &Run)> = groups
+        .values()
+        .enumerate()
+        .flat_map(|(target, members)| {
+            members
+                .iter()
+                .flat_map(move |history| history.runs.iter().map(move |run| (target, run)))
+        })
+        .collect();
+    runs.sort_by_key(|(_, run)| run.first);
+
+    let bucket_ms = options.bucket.num_milliseconds().max(1);
+    let bucket_of = |timestamp: DateTime<Local>| timestamp.timestamp_millis().div_euclid(bucket_ms);
+    // The time and outcome of each target's latest run, while it has data;
+    // a run counts from its first ping and keeps its target from going
+    // silent until its last.
+    let mut last: Vec<Option<(DateTime<Local>, bool)>> = vec![None; labels.len()];
+    let mut closed = Vec::new();
+    let mut open: Option<CorrelatedIncident> = None;
+    let mut last_recovery: Option<DateTime<Local>> = None;
+    let mut index = 0;
+
+    while let Some(&(_, run)) = runs.get(index) {
+        let first = run.first;
+        let bucket = bucket_of(first);
+        let bucket_start =
+            first - Duration::milliseconds(first.timestamp_millis() - bucket * bucket_ms);
+        let mut expired_failure = false;
+        for (state, limit) in last.iter_mut().zip(&limits) {
+            if let Some((seen, success)) = *state
+                && bucket_start - seen > *limit
+            {
+                expired_failure |= !success;
+                *state = None;
+            }
+        }
+        if !last.iter().flatten().any(|(_, success)| !success)
+            && let Some(mut incident) = open.take()
+        {
+            if !expired_failure {
+                recover(&mut incident, last_recovery);
+            }
+            closed.push(incident);
+        }
+
+        let mut failed_here = vec![false; labels.len()];
+        let mut failures = 0;
+        let mut first_failure = None;
+        let mut latest_failure = None;
+        while let Some(&(target, run)) = runs.get(index)
+            && bucket_of(run.first) == bucket
+        {
+            if run.success {
+                if last[target].is_some_and(|(_, success)| !success) {
+                    last_recovery = Some(run.first);
+                }
+            } else {
+                failed_here[target] = true;
+                failures += run.pings;
+                first_failure = first_failure.or(Some(run.first));
+                latest_failure = latest_failure.max(Some(run.last));
+            }
+            last[target] = Some((run.last, run.success));
+            index += 1;
+        }
+
+        let failing: Vec<usize> = (0..labels.len())
+            .filter(|target| failed_here[*target] || last[*target].is_some_and(|(_, s)| !s))
+            .collect();
+        if failing.is_empty() {
+            if let Some(mut incident) = open.take() {
+                recover(&mut incident, last_recovery);
+                closed.push(incident);
+            }
+            continue;
+        }
+        let incident = open
+            .get_or_insert_with(|| CorrelatedIncident::starting(first_failure.unwrap_or(first)));
+        incident.failed_probes += failures;
+        if let Some(latest) = latest_failure {
+            incident.end = incident.end.max(latest);
+        }
+        let monitored: Vec<usize> = (0..labels.len())
+            .filter(|target| last[*target].is_some())
+            .collect();
+        widen(incident, &failing, &monitored, &labels, &machine_of);
+    }
+    closed.extend(open);
+    closed
+}
+
+/// Record the failing targets, and separately their machines, whenever more
+/// are failing than the incident has seen so far.
+fn widen(
+    incident: &mut CorrelatedIncident,
+    failing: &[usize],
+    monitored: &[usize],
+    labels: &[&String],
+    machine_of: &[Option<&String>],
+) {
+    if failing.len() > incident.targets.len() {
+        incident.targets = failing
+            .iter()
+            .map(|target| labels[*target].clone())
+            .collect();
+        incident.monitored = monitored.len();
+        incident.scope = FailureScope::classify(failing.len(), monitored.len());
+    }
+    let distinct = |targets: &[usize]| -> BTreeSet<String> {
+        targets
+            .iter()
+            .filter_map(|target| machine_of[*target].cloned())
+            .collect()
+    };
+    let failing = distinct(failing);
+    if failing.len() > incident.machines.len() {
+        incident.machines = failing.into_iter().collect();
+        incident.monitored_machines = distinct(monitored).len();
+    }
+}
+
+/// Mark the incident as recovered by the latest successful ping of a failing target.
+fn recover(incident: &mut CorrelatedIncident, recovery: Option<DateTime<Local>>) {
+    if let Some(recovery) = recovery.filter(|time| *time >= incident.end) {
+        incident.end = recovery;
+    }
+    incident.recovered = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::PingEvent;
+    use chrono::TimeZone;
+
+    fn event(host: &str, second: u32, success: bool) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, 0, second).unwrap();
+        PingEvent::test(host, at, success)
+    }
+
+    #[test]
+    fn incidents_are_classified_by_the_targets_failing_together() {
+        let mut events = Vec::new();
+        for second in 0..30 {
+            // Everything fails at 5-6, only 8.8.8.8 at 15-16, and two of the
+            // three hosts at 25.
+            let uplink = (5..7).contains(&second);
+            events.push(event(
+                "8.8.8.8",
+                second,
+                !uplink && !(15..17).contains(&second),
+            ));
+            events.push(event("1.1.1.1", second, !uplink && second != 25));
+            events.push(event("9.9.9.9", second, !uplink && second != 25));
+        }
+        let incidents = correlate_failures(&Histories::of(&events), &CorrelationOptions::default());
+        let scopes: Vec<FailureScope> = incidents.iter().map(|incident| incident.scope).collect();
+        assert_eq!(
+            scopes,
+            [
+                FailureScope::AllTargets,
+                FailureScope::SingleTarget,
+                FailureScope::Subset
+            ]
+        );
+        assert_eq!(incidents[0].duration(), Duration::seconds(2));
+        assert_eq!(incidents[0].failed_probes, 6);
+        assert_eq!(incidents[1].targets, ["icmp 8.8.8.8"]);
+        assert!(incidents.iter().all(|incident| incident.recovered));
+    }
+
+    #[test]
+    fn failures_on_every_machine_are_told_apart_from_one_machine() {
+        let mut events = Vec::new();
+        for second in 0..30 {
+            // Both desks lose 8.8.8.8 at 5-6; only desk-2 fails at 15.
+            for machine in ["desk-1", "desk-2"] {
+                let failed = (5..7).contains(&second) || (machine == "desk-2" && second == 15);
+                events.push(event("8.8.8.8", second, !failed).on_machine(machine));
+            }
+        }
+        let incidents = correlate_failures(&Histories::of(&events), &CorrelationOptions::default());
+        let shared: Vec<bool> = incidents
+            .iter()
+            .map(CorrelatedIncident::hit_every_machine)
+            .collect();
+        assert_eq!(shared, [true, false]);
+        assert_eq!(incidents[0].machines, ["desk-1", "desk-2"]);
+        assert_eq!(incidents[1].machines, ["desk-2"]);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::audit::Cell;
+use crate::audit::CorrelatedIncident;
+use crate::audit::CorrelationOptions;
+use crate::audit::FailureScope;
+use crate::audit::Histories;
+use crate::audit::ReportBody;
+use crate::audit::ReportPeriod;
+use crate::audit::Table;
+use crate::audit::correlate_failures;
+use crate::audit::format_duration;
+use crate::audit::serialize_seconds;
+use chrono::Duration;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Correlated incidents of one scope.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopeSummary {
+    pub scope: FailureScope,
+    pub incidents: usize,
+    #[serde(rename = "downtime_seconds", serialize_with = "serialize_seconds")]
+    pub downtime: Duration,
+    /// Share (0-100) of all correlated incidents.
+    pub share: Option<f64>,
+}
+
+/// Correlated incidents in which the same set of targets failed together.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetSetSummary {
+    pub targets: Vec<String>,
+    pub scope: FailureScope,
+    pub incidents: usize,
+    #[serde(rename = "downtime_seconds", serialize_with = "serialize_seconds")]
+    pub downtime: Duration,
+}
+
+/// Correlated incidents per scope starting in one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopePeriodRow {
+    pub period: String,
+    pub all_targets: usize,
+    pub subset: usize,
+    pub single_target: usize,
+}
+
+/// The `audit correlated` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrelatedReport {
+    #[serde(rename = "bucket_seconds", serialize_with = "serialize_seconds")]
+    pub bucket: Duration,
+    pub scopes: Vec<ScopeSummary>,
+    pub target_sets: Vec<TargetSetSummary>,
+    pub by_period: Vec<ScopePeriodRow>,
+    pub incidents: Vec<CorrelatedIncident>,
+}
+
+impl CorrelatedReport {
+    #[must_use]
+    pub fn build(
+        histories: &Histories,
+        options: &CorrelationOptions,
+        period: ReportPeriod,
+    ) -> Self {
+        let incidents = correlate_failures(histories, options);
+
+        let scopes = FailureScope::ALL
+            .into_iter()
+            .map(|scope| {
+                let matching: Vec<&CorrelatedIncident> = incidents
+                    .iter()
+                    .filter(|incident| incident.scope == scope)
+                    .collect();
+                #[expect(
+                    clippy::cast_precision_loss,
+                    reason = "incident counts stay far below 2^52"
+                )]
+                let share = (!incidents.is_empty())
+                    .then(|| matching.len() as f64 / incidents.len() as f64 * 100.0);
+                ScopeSummary {
+                    scope,
+                    incidents: matching.len(),
+                    downtime: matching.iter().fold(Duration::zero(), |total, incident| {
+                        total + incident.duration()
+                    }),
+                    share,
+                }
+            })
+            .collect();
+
+        let mut sets: BTreeMap<&[String], TargetSetSummary> = BTreeMap::new();
+        let mut periods: BTreeMap<String, ScopePeriodRow> = BTreeMap::new();
+        for incident in &incidents {
+            let set = sets
+                .entry(&incident.targets)
+                .or_insert_with(|| TargetSetSummary {
+                    targets: incident.targets.clone(),
+                    scope: incident.scope,
+                    incidents: 0,
+                    downtime: Duration::zero(),
+                });
+            set.incidents += 1;
+            set.downtime += incident.duration();
+
+            let label = period.label(incident.start);
+            let row = periods
+                .entry(label.clone())
+                .or_insert_with(|| ScopePeriodRow {
+                    period: label,
+                    all_targets: 0,
+                    subset: 0,
+                    single_target: 0,
+                });
+            match incident.scope {
+                FailureScope::AllTargets => row.all_targets += 1,
+                FailureScope::Subset => row.subset += 1,
+                FailureScope::SingleTarget => row.single_target += 1,
+            }
+        }
+        let mut target_sets: Vec<TargetSetSummary> = sets.into_values().collect();
+        target_sets.sort_by(|a, b| b.incidents.cmp(&a.incidents).then(a.scope.cmp(&b.scope)));
+
+        Self {
+            bucket: options.bucket,
+            scopes,
+            target_sets,
+            by_period: periods.into_values().collect(),
+            incidents,
+        }
+    }
+}
+
+impl ReportBody for CorrelatedReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut scopes = Table::new(
+            "Failure Scope",
+            &["Scope", "Incidents", "Share", "Downtime"],
+        )
+        .with_bar(1);
+        for summary in &self.scopes {
+            scopes.push_row(vec![
+                summary.scope.to_string().into(),
+                summary.incidents.into(),
+                summary.share.map_or(Cell::Missing, Cell::Percent),
+                summary.downtime.into(),
+            ]);
+        }
+        scopes.push_note(
+            "Incidents are classified by the largest set of targets failing at the same time: all targets with data (the local network or uplink), a subset of them, or a single target (that host).",
+        );
+        if self.incidents.is_empty() {
+            scopes.push_note("No failures found.");
+            return vec![scopes];
+        }
+
+        let mut sets = Table::new(
+            "Co-failing Target Sets",
+            &["Targets", "Scope", "Incidents", "Downtime"],
+        );
+        for set in &self.target_sets {
+            sets.push_row(vec![
+                set.targets.join(", ").into(),
+                set.scope.to_string().into(),
+                set.incidents.into(),
+                set.downtime.into(),
+            ]);
+        }
+
+        let mut periods = Table::new(
+            "Failure Scope by Period",
+            &["Period", "All targets", "Subset", "Single target"],
+        );
+        for row in &self.by_period {
+            periods.push_row(vec![
+                row.period.as_str().into(),
+                row.all_targets.into(),
+                row.subset.into(),
+                row.single_target.into(),
+            ]);
+        }
+
+        let mut timeline = Table::new(
+            "Correlated Incidents",
+            &["Start", "Duration", "Scope", "Targets", "Failed probes"],
+        );
+        for incident in &self.incidents {
+            let duration: Cell = if incident.recovered {
+                incident.duration().into()
+            } else {
+                format!("{} (not recovered)", format_duration(incident.duration())).into()
+            };
+            timeline.push_row(vec![
+                Cell::Time(incident.start),
+                duration,
+                incident.scope.to_string().into(),
+                incident.targets.join(", ").into(),
+                incident.failed_probes.into(),
+            ]);
+        }
+        vec![scopes, sets, periods, timeline]
+    }
+}
@@ -0,0 +1,188 @@
+use crate::audit::PingEvent;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Why a ping failed, normalised from the logged error message or HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The host name could not be resolved.
+    Dns,
+    /// The target actively refused the connection.
+    Refused,
+    /// No answer came back in time.
+    Timeout,
+    /// The TLS handshake or certificate check failed.
+    Tls,
+    #[serde(rename = "http_4xx")]
+    Http4xx,
+    #[serde(rename = "http_5xx")]
+    Http5xx,
+    /// The host or its network was reported unreachable, usually by ICMP.
+    Unreachable,
+    Other,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 8] = [
+        ErrorCategory::Dns,
+        ErrorCategory::Refused,
+        ErrorCategory::Timeout,
+        ErrorCategory::Tls,
+        ErrorCategory::Http4xx,
+        ErrorCategory::Http5xx,
+        ErrorCategory::Unreachable,
+        ErrorCategory::Other,
+    ];
+
+    /// The category of a ping that failed or got an HTTP error status back,
+    /// or `None` for a healthy ping.
+    #[must_use]
+    pub fn of(event: &PingEvent) -> Option<Self> {
+        Self::of_result(event.success, event.status, event.error.as_deref())
+    }
+
+    /// The category of a ping result given whether it succeeded, the HTTP
+    /// status it got back and its error message.
+    #[must_use]
+    pub fn of_result(success: bool, status: Option<u16>, error: Option<&str>) -> Option<Self> {
+        match status {
+            Some(400..=499) => return Some(ErrorCategory::Http4xx),
+            Some(500..=599) => return Some(ErrorCategory::Http5xx),
+            _ => {}
+        }
+        (!success).then(|| Self::classify(error.unwrap_or_default()))
+    }
+
+    /// Classify an error message as logged by the runtime.
+    ///
+    /// Messages carry the whole error chain, so the more specific causes are
+    /// checked first: a DNS lookup that timed out is a DNS failure. The URLs
+    /// the chain names are left out, so a host such as `ssl.example.com`
+    /// does not read as a TLS failure.
+    #[must_use]
+    pub fn classify(message: &str) -> Self {
+        let message = without_urls(&message.to_lowercase());
+        let mentions = |needles: &[&str]| needles.iter().any(|needle| message.contains(needle));
+        if mentions(&[
+            "dns error",
+            "failed to lookup address",
+            "unable to resolve host",
+            "no ip resolved",
+            "no such host",
+            "name or service not known",
+            "os error 11001",
+        ]) {
+            ErrorCategory::Dns
+        } else if mentions(&["tls", "ssl", "certificate", "handshake"]) {
+            ErrorCategory::Tls
+        } else if mentions(&["refused", "os error 10061"]) {
+            ErrorCategory::Refused
+        } else if mentions(&["unreachable", "no route to host", "os error 10065"]) {
+            ErrorCategory::Unreachable
+        } else if mentions(&[
+            "timed out",
+            "timeout",
+            "deadline has elapsed",
+            "did not properly respond",
+            "os error 10060",
+        ]) {
+            ErrorCategory::Timeout
+        } else if let Some(status) = http_status(&message) {
+            if status >= 500 {
+                ErrorCategory::Http5xx
+            } else {
+                ErrorCategory::Http4xx
+            }
+        } else {
+            ErrorCategory::Other
+        }
+    }
+}
+
+/// The message with every `for url (...)` part of an HTTP error removed.
+fn without_urls(message: &str) -> String {
+    let mut rest = message;
+    let mut kept = String::with_capacity(message.len());
+    while let Some((before, after)) = rest.split_once("for url (") {
+        kept.push_str(before);
+        rest = after.split_once(')').map_or("", |(_, after)| after);
+    }
+    kept.push_str(rest);
+    kept
+}
+
+/// The status code in an HTTP status error such as
+/// `HTTP status server error (503 Service Unavailable)`.
+fn http_status(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("http status")?;
+    let (_, code) = rest.split_once('(')?;
+    code.get(..3)?
+        .parse()
+        .ok()
+        .filter(|status| (400..600).contains(status))
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ErrorCategory::Dns => "DNS",
+            ErrorCategory::Refused => "refused",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Tls => "TLS",
+            ErrorCategory::Http4xx => "HTTP 4xx",
+            ErrorCategory::Http5xx => "HTTP 5xx",
+            ErrorCategory::Unreachable => "unreachable",
+            ErrorCategory::Other => "other",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_logged_error_messages() {
+        let cases = [
+            (
+                "error sending request for url (https://example.com/): client error (Connect): dns error: failed to lookup address information: No such host is known. (os error 11001)",
+                ErrorCategory::Dns,
+            ),
+            (
+                "Unable to resolve host: No such host is known.",
+                ErrorCategory::Dns,
+            ),
+            (
+                "No connection could be made because the target machine actively refused it. (os error 10061)",
+                ErrorCategory::Refused,
+            ),
+            (
+                "ICMP echo failed: Request timed out",
+                ErrorCategory::Timeout,
+            ),
+            (
+                "error sending request: tls handshake eof",
+                ErrorCategory::Tls,
+            ),
+            ("Destination host unreachable", ErrorCategory::Unreachable),
+            (
+                "HTTP status server error (503 Service Unavailable) for url (https://example.com/)",
+                ErrorCategory::Http5xx,
+            ),
+            (
+                "error sending request for url (https://ssl.example.com/): operation timed out",
+                ErrorCategory::Timeout,
+            ),
+            (
+                "error sending request for url (http://tls-gateway.lan/): client error (Connect): tcp connect error: Connection refused (os error 111)",
+                ErrorCategory::Refused,
+            ),
+            ("unknown", ErrorCategory::Other),
+        ];
+        for (message, category) in cases {
+            assert_eq!(ErrorCategory::classify(message), category, "{message}");
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use crate::audit::Accumulator;
+use crate::audit::Cell;
+use crate::audit::ErrorCategory;
+use crate::audit::Histories;
+use crate::audit::PingEvent;
+use crate::audit::ReportBody;
+use crate::audit::ReportPeriod;
+use crate::audit::Table;
+use crate::audit::TargetKey;
+use chrono::Timelike;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// How many distinct error messages the report lists.
+const TOP_MESSAGES: usize = 10;
+
+/// Problems of one category across all selected events.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCount {
+    pub category: ErrorCategory,
+    pub count: usize,
+    /// Share (0-100) of all problems.
+    pub share: Option<f64>,
+}
+
+/// Problems by category within one target, hour of day or period.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBreakdown {
+    pub label: String,
+    pub pings: usize,
+    pub problems: usize,
+    pub categories: BTreeMap<ErrorCategory, usize>,
+}
+
+impl ErrorBreakdown {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            pings: 0,
+            problems: 0,
+            categories: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, category: Option<ErrorCategory>) {
+        self.pings += 1;
+        if let Some(category) = category {
+            self.problems += 1;
+            *self.categories.entry(category).or_default() += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &ErrorBreakdown) {
+        self.pings += other.pings;
+        self.problems += other.problems;
+        for (category, count) in &other.categories {
+            *self.categories.entry(*category).or_default() += count;
+        }
+    }
+
+    /// The most frequent category and its share (0-100) of the problems.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "ping counts stay far below 2^52"
+    )]
+    pub fn top(&self) -> Option<(ErrorCategory, f64)> {
+        let (category, count) = self
+            .categories
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))?;
+        Some((*category, *count as f64 / self.problems as f64 * 100.0))
+    }
+}
+
+/// A distinct error message and how often it was logged.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorMessage {
+    pub message: String,
+    pub category: ErrorCategory,
+    pub count: usize,
+}
+
+/// Problems counted per target, hour of day and period, and per message.
+#[derive(Debug, Clone)]
+pub struct ErrorTally {
+    period: ReportPeriod,
+    targets: BTreeMap<TargetKey, ErrorBreakdown>,
+    hours: Vec<ErrorBreakdown>,
+    periods: BTreeMap<String, ErrorBreakdown>,
+    messages: HashMap<(String, ErrorCategory), usize>,
+}
+
+impl ErrorTally {
+    /// An empty tally breaking problems down by `period`.
+    #[must_use]
+    pub fn new(period: ReportPeriod) -> Self {
+        Self {
+            period,
+            targets: BTreeMap::new(),
+            hours: (0..24)
+                .map(|hour: u32| ErrorBreakdown::new(hour.to_string()))
+                .collect(),
+            periods: BTreeMap::new(),
+            messages: HashMap::new(),
+        }
+    }
+}
+
+impl Accumulator for ErrorTally {
+    fn record(&mut self, event: &PingEvent) {
+        let category = ErrorCategory::of(event);
+        if let Some(target) = self.targets.get_mut(&event.target) {
+            target.record(category);
+        } else {
+            let mut target = ErrorBreakdown::new(String::new());
+            target.record(category);
+            self.targets.insert(event.target.clone(), target);
+        }
+        self.hours[event.timestamp.hour() as usize].record(category);
+        let label = self.period.label(event.timestamp);
+        self.periods
+            .entry(label.clone())
+            .or_insert_with(|| ErrorBreakdown::new(label))
+            .record(category);
+        if let Some(category) = category
+            && let Some(message) = &event.error
+        {
+            *self
+                .messages
+                .entry((message.clone(), category))
+                .or_default() += 1;
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (target, breakdown) in later.targets {
+            self.targets
+                .entry(target)
+                .or_insert_with(|| ErrorBreakdown::new(String::new()))
+                .merge(&breakdown);
+        }
+        for (hour, breakdown) in self.hours.iter_mut().zip(&later.hours) {
+            hour.merge(breakdown);
+        }
+        for (label, breakdown) in later.periods {
+            self.periods
+                .entry(label.clone())
+                .or_insert_with(|| ErrorBreakdown::new(label))
+                .merge(&breakdown);
+        }
+        for (message, count) in later.messages {
+            *self.messages.entry(message).or_default() += count;
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for (mut target, breakdown) in std::mem::take(&mut self.targets) {
+            rekey(&mut target);
+            self.targets
+                .entry(target)
+                .or_insert_with(|| ErrorBreakdown::new(String::new()))
+                .merge(&breakdown);
+        }
+    }
+}
+
+/// The `audit errors` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub problems: usize,
+    pub categories: Vec<CategoryCount>,
+    pub by_target: Vec<ErrorBreakdown>,
+    pub by_hour: Vec<ErrorBreakdown>,
+    pub by_period: Vec<ErrorBreakdown>,
+    /// The most frequent error messages.
+    pub messages: Vec<ErrorMessage>,
+}
+
+impl ErrorReport {
+    /// Build the errors view, labelling targets by their histories.
+    #[must_use]
+    pub fn build(tally: ErrorTally, histories: &Histories) -> Self {
+        let mut by_target: Vec<ErrorBreakdown> = tally
+            .targets
+            .into_iter()
+            .map(|(target, breakdown)| ErrorBreakdown {
+                label: histories.label(&target),
+                ..breakdown
+            })
+            .collect();
+        by_target.sort_by(|a, b| a.label.cmp(&b.label));
+
+        let problems = by_target.iter().map(|target| target.problems).sum();
+        let categories = ErrorCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let count = by_target
+                    .iter()
+                    .filter_map(|target| target.categories.get(&category))
+                    .sum();
+                #[expect(
+                    clippy::cast_precision_loss,
+                    reason = "ping counts stay far below 2^52"
+                )]
+                let share = (problems > 0).then(|| count as f64 / problems as f64 * 100.0);
+                CategoryCount {
+                    category,
+                    count,
+                    share,
+                }
+            })
+            .collect();
+        let mut messages: Vec<ErrorMessage> = tally
+            .messages
+            .into_iter()
+            .map(|((message, category), count)| ErrorMessage {
+                message,
+                category,
+                count,
+            })
+            .collect();
+        messages.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.message.cmp(&b.message))
+        });
+        messages.truncate(TOP_MESSAGES);
+
+        Self {
+            problems,
+            categories,
+            by_target,
+            by_hour: tally.hours,
+            by_period: tally.periods.into_values().collect(),
+            messages,
+        }
+    }
+}
+
+/// A table of breakdowns with one column per category that occurred.
+fn breakdown_table(
+    title: &str,
+    first_column: &str,
+    categories: &[ErrorCategory],
+    rows: &[ErrorBreakdown],
+) -> Table {
+    let names: Vec<String> = categories.iter().map(ToString::to_string).collect();
+    let mut headers = vec![first_column, "Pings", "Problems"];
+    headers.extend(names.iter().map(String::as_str));
+    headers.push("Top");
+    let mut table = Table::new(title, &headers);
+    for row in rows {
+        let mut cells: Vec<Cell> = vec![
+            row.label.as_str().into(),
+            row.pings.into(),
+            row.problems.into(),
+        ];
+        cells.extend(categories.iter().map(|category| {
+            row.categories
+                .get(category)
+                .copied()
+                .unwrap_or_default()
+                .into()
+        }));
+        cells.push(row.top().map_or(Cell::Missing, |(category, share)| {
+            format!("{category} ({share:.0}%)").into()
+        }));
+        table.push_row(cells);
+    }
+    table
+}
+
+impl ReportBody for ErrorReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut summary =
+            Table::new("Error Categories", &["Category", "Problems", "Share"]).with_bar(1);
+        for count in &self.categories {
+            summary.push_row(vec![
+                count.category.to_string().into(),
+                count.count.into(),
+                count.share.map_or(Cell::Missing, Cell::Percent),
+            ]);
+        }
+        summary.push_note(
+            "Problems are failed pings, grouped by their error message, and HTTP pings answered with a 4xx or 5xx status, which piing otherwise counts as successful.",
+        );
+        if self.problems == 0 {
+            summary.push_note("No problems found.");
+            return vec![summary];
+        }
+
+        let present: Vec<ErrorCategory> = self
+            .categories
+            .iter()
+            .filter(|count| count.count > 0)
+            .map(|count| count.category)
+            .collect();
+        let by_target = breakdown_table("Errors by Target", "Target", &present, &self.by_target);
+        let by_hour = breakdown_table("Errors by Hour of Day", "Hour", &present, &self.by_hour);
+        let by_period = breakdown_table("Errors by Period", "Period", &present, &self.by_period);
+
+        let mut tables = vec![summary, by_target, by_hour, by_period];
+        if !self.messages.is_empty() {
+            let mut messages = Table::new("Most Common Errors", &["Error", "Category", "Count"]);
+            for message in &self.messages {
+                messages.push_row(vec![
+                    message.message.as_str().into(),
+                    message.category.to_string().into(),
+                    message.count.into(),
+                ]);
+            }
+            tables.push(messages);
+        }
+        tables
+    }
+}
@@ -0,0 +1,302 @@
+use crate::ping_record::PING_RECORD_VERSION;
+use crate::ping_record::PingRecord;
+use chrono::DateTime;
+use chrono::Local;
+use serde::Deserialize;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+
+/// The fields that tell a record such as a ping record apart from a line of
+/// the tracing layer, and which version of it to read.
+#[derive(Debug, Deserialize)]
+struct RecordProbe<'a> {
+    #[serde(borrow, default)]
+    record: Option<Cow<'a, str>>,
+    #[serde(default)]
+    version: Option<u32>,
+}
+
+/// One line of the ndjson log written by the JSON tracing layer.
+#[derive(Debug, Deserialize)]
+struct LogEntry {
+    timestamp: String,
+    fields: LogFields,
+}
+
+#[derive(Debug, Deserialize)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "mirrors the flags logged with every ping"
+)]
+struct LogFields {
+    success: bool,
+    /// Name of the `piing_target` block; absent in logs written before it was recorded.
+    #[serde(default)]
+    target: Option<String>,
+    /// Identifies the computer that logged the ping; absent in logs written
+    /// before machines were recorded.
+    #[serde(default)]
+    machine: Option<String>,
+    #[serde(default)]
+    host: String,
+    #[serde(default)]
+    mode: String,
+    /// Round trip time, logged with a fractional part by some versions.
+    #[serde(default)]
+    latency_ms: Option<f64>,
+    /// HTTP status of a successful HTTP ping.
+    #[serde(default)]
+    status: Option<u16>,
+    /// Error message of a failed ping.
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    vpn_active: bool,
+    /// Comma separated target tags; absent in logs written before tags existed.
+    #[serde(default)]
+    tags: String,
+    /// Whether the ping happened inside a `piing_schedule` maintenance window.
+    #[serde(default)]
+    maintenance: bool,
+    /// Whether the ping failed during the grace period after a system resume.
+    #[serde(default)]
+    resume_grace: bool,
+}
+
+/// Identifies the target a ping event belongs to.
+///
+/// Events are grouped by what was probed rather than by target name, so
+/// renaming a target or reading logs written before names were recorded
+/// does not split its history. When logs of several machines are audited
+/// together, the same target probed from each machine is kept apart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TargetKey {
+    pub host: String,
+    pub mode: String,
+    /// The machine that probed the target, set only when the audited logs
+    /// come from more than one machine.
+    pub machine: Option<String>,
+}
+
+impl fmt::Display for TargetKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mode.is_empty() {
+            write!(f, "{}", self.host)?;
+        } else {
+            write!(f, "{} {}", self.mode, self.host)?;
+        }
+        if let Some(machine) = &self.machine {
+            write!(f, " @ {machine}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A ping result read back from the logs.
+#[derive(Debug, Clone)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "mirrors the flags logged with every ping"
+)]
+pub struct PingEvent {
+    pub timestamp: DateTime<Local>,
+    pub target: TargetKey,
+    /// Name of the `piing_target` block, when the log recorded it.
+    pub target_name: Option<String>,
+    pub success: bool,
+    /// Round trip time of a successful ping.
+    pub latency_ms: Option<f64>,
+    /// HTTP status code the target answered with, for HTTP pings.
+    pub status: Option<u16>,
+    /// Why the ping failed, as logged.
+    pub error: Option<String>,
+    pub vpn_active: bool,
+    pub tags: Vec<String>,
+    pub maintenance: bool,
+    pub resume_grace: bool,
+}
+
+#[cfg(test)]
+impl PingEvent {
+    /// An ICMP ping of `host` at `timestamp`, answered in 10 ms when
+    /// `success`, for tests to adjust with the setters below.
+    #[must_use]
+    pub fn test(host: &str, timestamp: DateTime<Local>, success: bool) -> Self {
+        Self {
+            timestamp,
+            target: TargetKey {
+                host: host.to_string(),
+                mode: "icmp".to_string(),
+                machine: None,
+            },
+            target_name: None,
+            success,
+            latency_ms: success.then_some(10.0),
+            status: None,
+            error: None,
+            vpn_active: false,
+            tags: Vec::new(),
+            maintenance: false,
+            resume_grace: false,
+        }
+    }
+
+    #[must_use]
+    pub fn named(mut self, name: &str) -> Self {
+        self.target_name = Some(name.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn on_machine(mut self, machine: &str) -> Self {
+        self.target.machine = Some(machine.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_latency(mut self, latency_ms: f64) -> Self {
+        self.latency_ms = Some(latency_ms);
+        self
+    }
+
+    #[must_use]
+    pub fn with_vpn(mut self, vpn_active: bool) -> Self {
+        self.vpn_active = vpn_active;
+        self
+    }
+
+    #[must_use]
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(ToString::to_string).collect();
+        self
+    }
+}
+
+/// What a line of a log file holds.
+#[derive(Debug, Clone)]
+pub enum LogLine {
+    Ping(PingEvent),
+    /// Another log event, or a blank line.
+    Other,
+    /// A line that is not valid JSON, or a ping result that cannot be read.
+    Malformed,
+}
+
+/// Parse a line of a log file, either a ping record or a line written by
+/// the JSON tracing layer.
+#[must_use]
+pub fn parse_log_line(line: &str) -> LogLine {
+    if line.trim().is_empty() {
+        return LogLine::Other;
+    }
+    // Lines of the tracing layer name no record, so only lines mentioning
+    // one need a closer look.
+    if line.contains(r#""record""#)
+        && let Ok(probe) = serde_json::from_str::<RecordProbe<'_>>(line)
+        && let Some(kind) = probe.record
+    {
+        if kind != "ping" {
+            return LogLine::Other;
+        }
+        // A version this piing does not know may mean anything.
+        if !probe
+            .version
+            .is_some_and(|version| (1..=PING_RECORD_VERSION).contains(&version))
+        {
+            return LogLine::Malformed;
+        }
+        return serde_json::from_str::<PingRecord>(line).map_or(LogLine::Malformed, |record| {
+            LogLine::Ping(record.to_event())
+        });
+    }
+    if let Some(event) = parse_ping_event(line) {
+        return LogLine::Ping(event);
+    }
+    // Only lines that failed to parse as pings get here, so checking the
+    // JSON a second time costs little.
+    let is_ping = serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .map(|entry| entry["fields"].get("success").is_some());
+    match is_ping {
+        Some(false) => LogLine::Other,
+        Some(true) | None => LogLine::Malformed,
+    }
+}
+
+/// Parse a log line into a ping event, returning `None` for lines that are
+/// not ping results (other log events, blank or malformed lines).
+#[must_use]
+pub fn parse_ping_event(line: &str) -> Option<PingEvent> {
+    let entry: LogEntry = serde_json::from_str(line).ok()?;
+    // Parse timestamp from ISO 8601 format
+    let timestamp = DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()?
+        .with_timezone(&Local);
+    let fields = entry.fields;
+    let tags = fields
+        .tags
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    let latency_ms = fields.latency_ms.filter(|_| fields.success);
+    Some(PingEvent {
+        timestamp,
+        target: TargetKey {
+            host: fields.host,
+            mode: fields.mode,
+            machine: fields.machine,
+        },
+        target_name: fields.target,
+        success: fields.success,
+        latency_ms,
+        status: fields.status,
+        error: fields.error.filter(|_| !fields.success),
+        vpn_active: fields.vpn_active,
+        tags,
+        maintenance: fields.maintenance,
+        resume_grace: fields.resume_grace,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_logged_ping_results_only() {
+        let success = r#"{"timestamp":"2025-12-01T08:00:00.123-05:00","level":"INFO","fields":{"message":"Ping succeeded","target":"google_dns","host":"8.8.8.8","mode":"icmp","success":true,"latency_ms":12,"vpn_active":true,"tags":"dns, isp","maintenance":false}}"#;
+        let event = parse_ping_event(success).unwrap();
+        assert_eq!(event.target.host, "8.8.8.8");
+        assert_eq!(event.target_name.as_deref(), Some("google_dns"));
+        assert_eq!(event.latency_ms, Some(12.0));
+        assert!(event.vpn_active);
+        assert_eq!(event.tags, ["dns", "isp"]);
+        assert_eq!(event.error, None);
+
+        let fractional = success.replace(r#""latency_ms":12,"#, r#""latency_ms":23.2756,"#);
+        let event = parse_ping_event(&fractional).unwrap();
+        assert!(event.success);
+        assert_eq!(event.latency_ms, Some(23.2756));
+
+        let failure = r#"{"timestamp":"2025-12-01T08:00:30-05:00","level":"WARN","fields":{"message":"Ping failed","host":"example.com","mode":"http-get","success":false,"error":"dns error: failed to lookup address information"}}"#;
+        let event = parse_ping_event(failure).unwrap();
+        assert_eq!(
+            event.error.as_deref(),
+            Some("dns error: failed to lookup address information")
+        );
+
+        let other = r#"{"timestamp":"2025-12-01T08:00:00-05:00","level":"DEBUG","fields":{"message":"Logging initialized"}}"#;
+        assert!(parse_ping_event(other).is_none());
+        assert!(matches!(parse_log_line(other), LogLine::Other));
+        assert!(parse_ping_event(r#"{"timestamp":"2025-12"#).is_none());
+        assert!(matches!(
+            parse_log_line(r#"{"timestamp":"2025-12"#),
+            LogLine::Malformed
+        ));
+        let bad_time = failure.replace("2025-12-01T08:00:30", "yesterday");
+        assert!(matches!(parse_log_line(&bad_time), LogLine::Malformed));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::audit::PingEvent;
+use crate::audit::TimeRange;
+use crate::config::tags_match;
+use serde::Serialize;
+
+/// Decides which ping events an audit looks at.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EventFilter {
+    pub range: TimeRange,
+    /// Target tags to match; empty matches every target.
+    pub tags: Vec<String>,
+    /// Target names to match; empty matches every target.
+    pub targets: Vec<String>,
+    /// Hosts to match; empty matches every host.
+    pub hosts: Vec<String>,
+    pub include_maintenance: bool,
+    pub include_resume_grace: bool,
+}
+
+/// Why an event was or was not kept by an [`EventFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterVerdict {
+    Keep,
+    /// Outside the time range or not one of the selected targets.
+    OutOfScope,
+    /// Recorded during a maintenance window.
+    Maintenance,
+    /// A failure recorded during the grace period after a system resume.
+    ResumeGrace,
+}
+
+impl EventFilter {
+    #[must_use]
+    pub fn check(&self, event: &PingEvent) -> FilterVerdict {
+        if !self.range.contains(event.timestamp) || !self.selects_target(event) {
+            return FilterVerdict::OutOfScope;
+        }
+        if event.maintenance && !self.include_maintenance {
+            return FilterVerdict::Maintenance;
+        }
+        if event.resume_grace && !self.include_resume_grace {
+            return FilterVerdict::ResumeGrace;
+        }
+        FilterVerdict::Keep
+    }
+
+    fn selects_target(&self, event: &PingEvent) -> bool {
+        self.selects(
+            event.target_name.as_deref(),
+            &event.target.host,
+            &event.tags,
+        )
+    }
+
+    /// Returns true if the target with this name, host and tags is one of
+    /// the selected targets.
+    #[must_use]
+    pub fn selects(&self, name: Option<&str>, host: &str, tags: &[String]) -> bool {
+        let name_matches = self.targets.is_empty()
+            || name.is_some_and(|name| {
+                self.targets
+                    .iter()
+                    .any(|wanted| wanted.eq_ignore_ascii_case(name))
+            });
+        let host_matches = self.hosts.is_empty()
+            || self
+                .hosts
+                .iter()
+                .any(|wanted| wanted.eq_ignore_ascii_case(host));
+        name_matches && host_matches && tags_match(tags, &self.tags)
+    }
+}
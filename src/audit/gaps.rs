@@ -0,0 +1,305 @@
+use crate::audit::Cell;
+use crate::audit::Histories;
+use crate::audit::Table;
+use crate::audit::TargetHistory;
+use crate::audit::TimeRange;
+use crate::audit::serialize_optional_seconds;
+use crate::audit::serialize_seconds;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use serde::Serialize;
+
+/// A target going without a record for more than this many times its usual
+/// interval is a data gap rather than a quiet period.
+pub const DEFAULT_GAP_FACTOR: i32 = 3;
+
+/// The median spacing between consecutive timestamps, ignoring repeats, or
+/// `None` when there are fewer than two distinct timestamps.
+#[must_use]
+pub fn median_spacing(timestamps: impl IntoIterator<Item = DateTime<Local>>) -> Option<Duration> {
+    let timestamps: Vec<DateTime<Local>> = timestamps.into_iter().collect();
+    let mut spacings: Vec<Duration> = timestamps
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|spacing| *spacing > Duration::zero())
+        .collect();
+    spacings.sort();
+    spacings.get(spacings.len() / 2).copied()
+}
+
+/// The longest silence among the pings of the given targets that is not a
+/// data gap: their largest usual interval times `gap_factor`.
+#[must_use]
+pub fn gap_limit(members: &[&TargetHistory], gap_factor: i32) -> Duration {
+    members
+        .iter()
+        .map(|history| history.usual_interval())
+        .max()
+        .unwrap_or(Duration::seconds(1))
+        * gap_factor
+}
+
+/// A period during which a target has no records, typically because piing
+/// was not running or the machine was asleep.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataGap {
+    pub target: String,
+    /// When the next ping was due.
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl DataGap {
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// How completely one target was monitored.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetCoverage {
+    pub target: String,
+    #[serde(
+        rename = "usual_interval_seconds",
+        serialize_with = "serialize_seconds"
+    )]
+    pub usual_interval: Duration,
+    /// Share (0-100) of the audited window with records.
+    pub coverage: f64,
+    pub gaps: usize,
+    #[serde(rename = "without_data_seconds", serialize_with = "serialize_seconds")]
+    pub without_data: Duration,
+    #[serde(
+        rename = "longest_gap_seconds",
+        serialize_with = "serialize_optional_seconds"
+    )]
+    pub longest_gap: Option<Duration>,
+}
+
+/// Monitoring coverage and data gaps of every target, reported alongside
+/// every audit view so gaps are never mistaken for quiet or failing periods.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoverageReport {
+    pub gap_factor: i32,
+    pub window_start: Option<DateTime<Local>>,
+    pub window_end: Option<DateTime<Local>>,
+    pub targets: Vec<TargetCoverage>,
+    pub gaps: Vec<DataGap>,
+}
+
+/// When one target was heard from: its first and last records, its usual
+/// interval and the silences between consecutive records.
+#[derive(Debug, Clone)]
+pub struct TargetTimeline {
+    pub label: String,
+    pub interval: Duration,
+    pub first: DateTime<Local>,
+    pub last: DateTime<Local>,
+    /// Silences between consecutive records, as the earlier and the later
+    /// record. Holds at least every silence longer than the gap limit;
+    /// shorter ones are ignored.
+    pub silences: Vec<(DateTime<Local>, DateTime<Local>)>,
+}
+
+impl TargetTimeline {
+    /// The timeline of a target's history, keeping the silences longer than
+    /// `gap_factor` times its usual interval.
+    #[must_use]
+    pub fn of(history: &TargetHistory, gap_factor: i32) -> Option<Self> {
+        let interval = history.usual_interval();
+        let limit = interval * gap_factor;
+        Some(Self {
+            label: history.label(),
+            interval,
+            first: history.first()?,
+            last: history.last()?,
+            silences: history
+                .runs
+                .windows(2)
+                .map(|pair| (pair[0].last, pair[1].first))
+                .filter(|(from, to)| *to - *from > limit)
+                .collect(),
+        })
+    }
+}
+
+impl CoverageReport {
+    /// Find the data gaps of every target within the time range. Open ends of
+    /// the range are taken from the first and last event, and the range never
+    /// extends past `now`.
+    #[must_use]
+    pub fn build(
+        histories: &Histories,
+        range: &TimeRange,
+        gap_factor: i32,
+        now: DateTime<Local>,
+    ) -> Self {
+        let mut timelines: Vec<TargetTimeline> = histories
+            .iter()
+            .filter_map(|history| TargetTimeline::of(history, gap_factor))
+            .collect();
+        timelines.sort_by(|a, b| a.label.cmp(&b.label));
+        Self::from_timelines(timelines, range, gap_factor, now)
+    }
+
+    /// Find the data gaps of every target from timelines ordered by label.
+    #[must_use]
+    pub fn from_timelines(
+        timelines: Vec<TargetTimeline>,
+        range: &TimeRange,
+        gap_factor: i32,
+        now: DateTime<Local>,
+    ) -> Self {
+        let first = timelines.iter().map(|timeline| timeline.first).min();
+        let last = timelines.iter().map(|timeline| timeline.last).max();
+        let (Some(start), Some(end)) = (range.since.or(first), range.until.or(last)) else {
+            return Self {
+                gap_factor,
+                ..Self::default()
+            };
+        };
+        let end = end.min(now).max(start);
+        let window = (end - start).max(Duration::milliseconds(1));
+
+        let mut targets = Vec::new();
+        let mut all_gaps = Vec::new();
+        for timeline in timelines {
+            let interval = timeline.interval;
+            let limit = interval * gap_factor;
+            let mut gaps = Vec::new();
+            let mut gap = |from: DateTime<Local>, to: DateTime<Local>| {
+                gaps.push(DataGap {
+                    target: timeline.label.clone(),
+                    start: from,
+                    end: to,
+                });
+            };
+            if timeline.first - start > limit {
+                gap(start, timeline.first);
+            }
+            for (from, to) in &timeline.silences {
+                if *to - *from > limit {
+                    gap(*from + interval, *to);
+                }
+            }
+            if end - timeline.last > limit {
+                gap(timeline.last + interval, end);
+            }
+            gaps.retain(|gap| gap.end > gap.start);
+
+            let without_data = gaps
+                .iter()
+                .fold(Duration::zero(), |total, gap| total + gap.duration());
+            #[expect(
+                clippy::cast_precision_loss,
+                reason = "millisecond spans stay far below 2^52"
+            )]
+            let coverage = (1.0
+                - without_data.num_milliseconds() as f64 / window.num_milliseconds() as f64)
+                * 100.0;
+            targets.push(TargetCoverage {
+                target: timeline.label,
+                usual_interval: interval,
+                coverage: coverage.clamp(0.0, 100.0),
+                gaps: gaps.len(),
+                without_data,
+                longest_gap: gaps.iter().map(DataGap::duration).max(),
+            });
+            all_gaps.extend(gaps);
+        }
+        all_gaps.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.target.cmp(&b.target)));
+
+        Self {
+            gap_factor,
+            window_start: Some(start),
+            window_end: Some(end),
+            targets,
+            gaps: all_gaps,
+        }
+    }
+
+    /// Gaps of the target with the given label.
+    pub fn gaps_of<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a DataGap> {
+        self.gaps.iter().filter(move |gap| gap.target == target)
+    }
+
+    /// The coverage and gap tables shown after every view.
+    #[must_use]
+    pub fn tables(&self) -> Vec<Table> {
+        if self.targets.is_empty() {
+            return Vec::new();
+        }
+        let mut coverage = Table::new(
+            "Monitoring Coverage",
+            &[
+                "Target",
+                "Usual interval",
+                "Coverage",
+                "Gaps",
+                "Without data",
+                "Longest gap",
+            ],
+        );
+        for target in &self.targets {
+            coverage.push_row(vec![
+                target.target.as_str().into(),
+                target.usual_interval.into(),
+                Cell::Percent(target.coverage),
+                target.gaps.into(),
+                target.without_data.into(),
+                target.longest_gap.into(),
+            ]);
+        }
+        coverage.push_note(format!(
+            "A data gap is a period without records for more than {}x a target's usual interval; gaps count neither as failures nor as uptime.",
+            self.gap_factor
+        ));
+
+        let mut gaps = Table::new("Data Gaps", &["Target", "Start", "End", "Duration"]);
+        for gap in &self.gaps {
+            gaps.push_row(vec![
+                gap.target.as_str().into(),
+                Cell::Time(gap.start),
+                Cell::Time(gap.end),
+                gap.duration().into(),
+            ]);
+        }
+        if self.gaps.is_empty() {
+            gaps.push_note("No data gaps found.");
+        }
+        vec![coverage, gaps]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::PingEvent;
+    use chrono::TimeZone;
+
+    fn event(minute: u32) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, minute, 0).unwrap();
+        PingEvent::test("8.8.8.8", at, true)
+    }
+
+    #[test]
+    fn gaps_include_silences_inside_and_at_the_ends_of_the_window() {
+        let events: Vec<PingEvent> = [10, 11, 12, 13, 20, 21, 22].map(event).into();
+        let range = TimeRange::new(
+            Some(Local.with_ymd_and_hms(2025, 12, 1, 8, 0, 0).unwrap()),
+            Some(Local.with_ymd_and_hms(2025, 12, 1, 8, 40, 0).unwrap()),
+        );
+        let report = CoverageReport::build(&Histories::of(&events), &range, 3, Local::now());
+        let durations: Vec<i64> = report
+            .gaps
+            .iter()
+            .map(|gap| gap.duration().num_minutes())
+            .collect();
+        // Before the first ping, between 13 and 20 (from 14, when the next
+        // ping was due) and from 23 to the end of the window.
+        assert_eq!(durations, [10, 6, 17]);
+        assert!((report.targets[0].coverage - 700.0 / 40.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::audit::Accumulator;
+use crate::audit::IncidentGrouping;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use chrono::Timelike;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// Consecutive pings of one target with the same outcome and VPN state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub first: DateTime<Local>,
+    pub last: DateTime<Local>,
+    pub pings: usize,
+    pub success: bool,
+    pub vpn_active: bool,
+}
+
+impl Run {
+    fn of(event: &PingEvent) -> Self {
+        Self {
+            first: event.timestamp,
+            last: event.timestamp,
+            pings: 1,
+            success: event.success,
+            vpn_active: event.vpn_active,
+        }
+    }
+}
+
+/// Whether two times fall within the same hour of the clock.
+fn same_hour(a: DateTime<Local>, b: DateTime<Local>) -> bool {
+    a.date_naive() == b.date_naive() && a.hour() == b.hour()
+}
+
+/// The pings of one target in time order, folded into runs.
+///
+/// A run also ends at the turn of the hour and at a silence more than half
+/// again as long as the spacing before it. Every silence that may be a data
+/// gap then lies between two runs and every run within one period of a
+/// report, so incidents, availability and data gaps come out of the runs
+/// as they would out of every ping.
+#[derive(Debug, Clone)]
+pub struct TargetHistory {
+    pub target: TargetKey,
+    /// The most recently logged name of the target, if any.
+    pub name: Option<String>,
+    /// Every tag the target was logged with.
+    pub tags: BTreeSet<String>,
+    pub runs: Vec<Run>,
+    /// How often each spacing between consecutive pings occurred, in milliseconds.
+    spacings: BTreeMap<i64, usize>,
+    /// The spacing before the latest ping.
+    step: Option<Duration>,
+}
+
+impl TargetHistory {
+    fn new(target: TargetKey) -> Self {
+        Self {
+            target,
+            name: None,
+            tags: BTreeSet::new(),
+            runs: Vec::new(),
+            spacings: BTreeMap::new(),
+            step: None,
+        }
+    }
+
+    /// A label such as `google_dns (icmp 8.8.8.8)`.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({})", self.target),
+            None => self.target.to_string(),
+        }
+    }
+
+    /// Time of the first ping.
+    #[must_use]
+    pub fn first(&self) -> Option<DateTime<Local>> {
+        self.runs.first().map(|run| run.first)
+    }
+
+    /// Time of the last ping.
+    #[must_use]
+    pub fn last(&self) -> Option<DateTime<Local>> {
+        self.runs.last().map(|run| run.last)
+    }
+
+    /// The usual time between consecutive pings: the median spacing, or one
+    /// second when there are too few pings to tell.
+    #[must_use]
+    pub fn usual_interval(&self) -> Duration {
+        let count: usize = self.spacings.values().sum();
+        let mut below = 0;
+        for (spacing, times) in &self.spacings {
+            below += times;
+            if below > count / 2 {
+                return Duration::milliseconds(*spacing);
+            }
+        }
+        Duration::seconds(1)
+    }
+
+    fn record(&mut self, event: &PingEvent) {
+        if event.target_name.is_some() {
+            self.name.clone_from(&event.target_name);
+        }
+        for tag in &event.tags {
+            if !self.tags.contains(tag) {
+                self.tags.insert(tag.clone());
+            }
+        }
+        self.push(Run::of(event));
+    }
+
+    /// Add a run starting after the latest ping, extending the last run
+    /// when it continues it.
+    fn push(&mut self, run: Run) {
+        let Some(last) = self.runs.last() else {
+            self.runs.push(run);
+            return;
+        };
+        let spacing = run.first - last.last;
+        let continues = self.continues(&run);
+        if spacing > Duration::zero() {
+            *self.spacings.entry(spacing.num_milliseconds()).or_default() += 1;
+            self.step = Some(spacing);
+        }
+        self.extend_or_push(run, continues);
+    }
+
+    /// Whether a run starting after the latest ping continues the last run.
+    fn continues(&self, run: &Run) -> bool {
+        self.runs.last().is_some_and(|last| {
+            last.success == run.success
+                && last.vpn_active == run.vpn_active
+                && same_hour(last.last, run.first)
+                && self
+                    .step
+                    .is_some_and(|step| run.first - last.last <= step * 3 / 2)
+        })
+    }
+
+    fn extend_or_push(&mut self, run: Run, continues: bool) {
+        match self.runs.last_mut() {
+            Some(last) if continues => {
+                last.last = last.last.max(run.last);
+                last.pings += run.pings;
+            }
+            _ => self.runs.push(run),
+        }
+    }
+
+    /// Add the history of the same target from other logs.
+    fn absorb(&mut self, mut other: TargetHistory) {
+        self.tags.append(&mut other.tags);
+        let (Some(first), Some(last)) = (self.first(), self.last()) else {
+            self.runs = other.runs;
+            self.spacings = other.spacings;
+            self.step = other.step;
+            self.name = other.name.or(self.name.take());
+            return;
+        };
+        let (Some(other_first), Some(other_last)) = (other.first(), other.last()) else {
+            return;
+        };
+        if other_last < first {
+            std::mem::swap(&mut self.runs, &mut other.runs);
+            std::mem::swap(&mut self.spacings, &mut other.spacings);
+            std::mem::swap(&mut self.step, &mut other.step);
+            std::mem::swap(&mut self.name, &mut other.name);
+        } else if other_first < last {
+            // Overlapping logs, such as the same logs read twice: keep
+            // every run in time order.
+            self.runs.append(&mut other.runs);
+            self.runs.sort_by_key(|run| run.first);
+            add_counts(&mut self.spacings, other.spacings);
+            self.name = other.name.or(self.name.take());
+            return;
+        }
+        let mut runs = other.runs.into_iter();
+        if let Some(run) = runs.next() {
+            self.push(run);
+        }
+        // The later logs could not tell whether their first ping's spacing
+        // was usual, so their second run may continue it now that the
+        // spacing before it is known. Its own spacing is counted already.
+        if let Some(run) = runs.next() {
+            let continues = self.continues(&run);
+            self.extend_or_push(run, continues);
+        }
+        self.runs.extend(runs);
+        add_counts(&mut self.spacings, other.spacings);
+        self.step = other.step.or(self.step);
+        self.name = other.name.or(self.name.take());
+    }
+}
+
+fn add_counts(counts: &mut BTreeMap<i64, usize>, other: BTreeMap<i64, usize>) {
+    for (spacing, times) in other {
+        *counts.entry(spacing).or_default() += times;
+    }
+}
+
+/// The runs of several targets in order of their first ping, each with the
+/// position of its target among `members`.
+#[must_use]
+pub fn interleaved_runs<'a>(members: &[&'a TargetHistory]) -> Vec<(usize, &'a Run)> {
+    let mut runs: Vec<(usize, &Run)> = members
+        .iter()
+        .enumerate()
+        .flat_map(|(member, history)| history.runs.iter().map(move |run| (member, run)))
+        .collect();
+    runs.sort_by_key(|(_, run)| run.first);
+    runs
+}
+
+/// The history of every target: the compact form of the pings that
+/// incidents, availability, correlated failures, VPN changes and monitoring
+/// coverage are built from, kept by every load.
+#[derive(Debug, Clone, Default)]
+pub struct Histories {
+    targets: BTreeMap<TargetKey, TargetHistory>,
+}
+
+impl Histories {
+    /// The histories of events in any order.
+    #[must_use]
+    pub fn of(events: &[PingEvent]) -> Self {
+        let mut ordered: Vec<&PingEvent> = events.iter().collect();
+        ordered.sort_by_key(|event| event.timestamp);
+        let mut histories = Self::default();
+        for event in ordered {
+            histories.record(event);
+        }
+        histories
+    }
+
+    /// Every target's history, ordered by host and mode.
+    pub fn iter(&self) -> impl Iterator<Item = &TargetHistory> {
+        self.targets.values()
+    }
+
+    /// The label of a target, as given by [`TargetHistory::label`].
+    #[must_use]
+    pub fn label(&self, target: &TargetKey) -> String {
+        self.targets
+            .get(target)
+            .map_or_else(|| target.to_string(), TargetHistory::label)
+    }
+
+    /// Split the histories into named groups, like
+    /// [`group_events`](crate::audit::group_events) splits events.
+    #[must_use]
+    pub fn groups(&self, grouping: IncidentGrouping) -> BTreeMap<String, Vec<&TargetHistory>> {
+        let mut groups: BTreeMap<String, Vec<&TargetHistory>> = BTreeMap::new();
+        for history in self.iter() {
+            match grouping {
+                IncidentGrouping::Target => {
+                    groups.entry(history.label()).or_default().push(history);
+                }
+                IncidentGrouping::Tag => {
+                    let tags: BTreeSet<String> =
+                        history.tags.iter().map(|tag| tag.to_lowercase()).collect();
+                    for tag in tags {
+                        groups
+                            .entry(format!("tag:{tag}"))
+                            .or_default()
+                            .push(history);
+                    }
+                }
+                IncidentGrouping::All => {
+                    groups
+                        .entry("all targets".to_string())
+                        .or_default()
+                        .push(history);
+                }
+            }
+        }
+        groups
+    }
+}
+
+impl Accumulator for Histories {
+    fn record(&mut self, event: &PingEvent) {
+        if let Some(history) = self.targets.get_mut(&event.target) {
+            history.record(event);
+        } else {
+            let mut history = TargetHistory::new(event.target.clone());
+            history.record(event);
+            self.targets.insert(event.target.clone(), history);
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (target, history) in later.targets {
+            match self.targets.get_mut(&target) {
+                Some(earlier) => earlier.absorb(history),
+                None => {
+                    self.targets.insert(target, history);
+                }
+            }
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        let targets = std::mem::take(&mut self.targets);
+        for (_, mut history) in targets {
+            rekey(&mut history.target);
+            self.merge(Self {
+                targets: BTreeMap::from([(history.target.clone(), history)]),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(minute: u32, second: u32, success: bool) -> PingEvent {
+        let at = Local
+            .with_ymd_and_hms(2025, 12, 1, 8, minute, second)
+            .unwrap();
+        PingEvent::test("8.8.8.8", at, success)
+    }
+
+    #[test]
+    fn runs_break_at_outcome_changes_silences_and_hours() {
+        let mut events: Vec<PingEvent> = (0..10).map(|minute| event(minute, 0, true)).collect();
+        events.push(event(10, 0, false));
+        events.push(event(11, 0, false));
+        // Silent from 11 to 20, then on past the hour.
+        events.extend((20..60).map(|minute| event(minute, 0, true)));
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 9, 0, 0).unwrap();
+        events.push(PingEvent::test("8.8.8.8", at, true));
+
+        let histories = Histories::of(&events);
+        let history = histories.iter().next().unwrap();
+        let pings: Vec<usize> = history.runs.iter().map(|run| run.pings).collect();
+        // The spacing after the first ping is not known to be usual yet.
+        assert_eq!(pings, [1, 9, 2, 40, 1]);
+        assert_eq!(history.usual_interval(), Duration::minutes(1));
+    }
+
+    #[test]
+    fn histories_of_later_logs_continue_the_earlier_ones() {
+        let events: Vec<PingEvent> = (0..20).map(|minute| event(minute, 0, true)).collect();
+        let mut earlier = Histories::default();
+        let mut later = Histories::default();
+        for event in &events[..10] {
+            earlier.record(event);
+        }
+        for event in &events[10..] {
+            later.record(event);
+        }
+        earlier.merge(later);
+        let history = earlier.iter().next().unwrap();
+        let pings: Vec<usize> = history.runs.iter().map(|run| run.pings).collect();
+        // The same runs as reading every ping at once.
+        assert_eq!(pings, [1, 19]);
+        assert_eq!(history.last(), Some(events[19].timestamp));
+        assert_eq!(history.usual_interval(), Duration::minutes(1));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::audit::Accumulator;
+use crate::audit::LatencyStats;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use crate::audit::TargetTimeline;
+use crate::audit::median_spacing;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use chrono::Timelike;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Pings, failures and latency of a target over some span of time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tally {
+    pub events: usize,
+    pub failures: usize,
+    pub latency: LatencyStats,
+}
+
+impl Tally {
+    pub fn record(&mut self, event: &PingEvent) {
+        self.events += 1;
+        if event.success {
+            if let Some(latency_ms) = event.latency_ms {
+                self.latency.record(latency_ms);
+            }
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Tally) {
+        self.events += other.events;
+        self.failures += other.failures;
+        self.latency.merge(&other.latency);
+    }
+}
+
+/// The pings of one target within one hour, or within the part of an hour
+/// on one side of a cut such as a bound of the audited time range.
+#[derive(Debug, Clone)]
+pub struct HourlyTally {
+    pub start: DateTime<Local>,
+    pub target: TargetKey,
+    /// The most recently logged name of the target within the span, if any.
+    pub name: Option<String>,
+    pub tally: Tally,
+}
+
+/// The start of the local hour the timestamp falls in.
+#[must_use]
+pub fn hour_start(timestamp: DateTime<Local>) -> DateTime<Local> {
+    let into_hour = Duration::minutes(timestamp.minute().into())
+        + Duration::seconds(timestamp.second().into())
+        + Duration::nanoseconds(timestamp.nanosecond().into());
+    timestamp - into_hour
+}
+
+/// The start of the span the timestamp is tallied in: its hour, or the last
+/// cut inside that hour at or before the timestamp.
+#[must_use]
+pub fn span_start(timestamp: DateTime<Local>, cuts: &[DateTime<Local>]) -> DateTime<Local> {
+    let hour = hour_start(timestamp);
+    cuts.iter()
+        .copied()
+        .filter(|cut| *cut > hour && *cut <= timestamp)
+        .max()
+        .unwrap_or(hour)
+}
+
+/// Tally events per target and hour, splitting the hours at the cuts.
+/// Tallies are ordered by start, then by target.
+#[must_use]
+pub fn tally_hours(events: &[PingEvent], cuts: &[DateTime<Local>]) -> Vec<HourlyTally> {
+    let mut hours = HourlyTallies::new(cuts.to_vec());
+    for event in events {
+        hours.record(event);
+    }
+    hours.hours()
+}
+
+/// What tallying pings per target and hour gathers, splitting the hours at
+/// the cuts.
+#[derive(Debug, Clone)]
+pub struct HourlyTallies {
+    cuts: Vec<DateTime<Local>>,
+    hours: BTreeMap<DateTime<Local>, BTreeMap<TargetKey, HourlyTally>>,
+}
+
+impl HourlyTallies {
+    #[must_use]
+    pub fn new(cuts: Vec<DateTime<Local>>) -> Self {
+        Self {
+            cuts,
+            hours: BTreeMap::new(),
+        }
+    }
+
+    /// The tallies, ordered by start, then by target.
+    #[must_use]
+    pub fn hours(&self) -> Vec<HourlyTally> {
+        self.hours
+            .values()
+            .flat_map(BTreeMap::values)
+            .cloned()
+            .collect()
+    }
+
+    fn add(&mut self, later: HourlyTally) {
+        let targets = self.hours.entry(later.start).or_default();
+        match targets.get_mut(&later.target) {
+            Some(hour) => {
+                if later.name.is_some() {
+                    hour.name = later.name;
+                }
+                hour.tally.merge(&later.tally);
+            }
+            None => {
+                targets.insert(later.target.clone(), later);
+            }
+        }
+    }
+}
+
+impl Accumulator for HourlyTallies {
+    fn record(&mut self, event: &PingEvent) {
+        let start = span_start(event.timestamp, &self.cuts);
+        let targets = self.hours.entry(start).or_default();
+        if !targets.contains_key(&event.target) {
+            let hour = HourlyTally {
+                start,
+                target: event.target.clone(),
+                name: None,
+                tally: Tally::default(),
+            };
+            targets.insert(event.target.clone(), hour);
+        }
+        let Some(hour) = targets.get_mut(&event.target) else {
+            return;
+        };
+        if event.target_name.is_some() {
+            hour.name.clone_from(&event.target_name);
+        }
+        hour.tally.record(event);
+    }
+
+    fn merge(&mut self, later: Self) {
+        for hour in later.hours.into_values().flat_map(BTreeMap::into_values) {
+            self.add(hour);
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        let hours = std::mem::take(&mut self.hours);
+        for mut hour in hours.into_values().flat_map(BTreeMap::into_values) {
+            rekey(&mut hour.target);
+            self.add(hour);
+        }
+    }
+}
+
+/// When a target was heard from within an hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    pub first: DateTime<Local>,
+    pub last: DateTime<Local>,
+    /// Median spacing between consecutive records, in milliseconds.
+    pub spacing_ms: i64,
+    /// How many spacings the median was taken over.
+    pub spacings: usize,
+    /// Silences longer than twice the median spacing, as the records on
+    /// either side. Gap limits are at least twice the usual interval, so
+    /// these hold every silence that may turn out to be a data gap.
+    pub silences: Vec<(DateTime<Local>, DateTime<Local>)>,
+}
+
+impl Presence {
+    /// The presence of a target given the times of its records, sorted.
+    #[must_use]
+    pub fn of(timestamps: &[DateTime<Local>]) -> Option<Self> {
+        let spacing = median_spacing(timestamps.iter().copied());
+        let limit = spacing.map_or(Duration::zero(), |spacing| spacing * 2);
+        Some(Self {
+            first: *timestamps.first()?,
+            last: *timestamps.last()?,
+            spacing_ms: spacing.map_or(0, |spacing| spacing.num_milliseconds()),
+            spacings: timestamps
+                .windows(2)
+                .filter(|pair| pair[1] > pair[0])
+                .count(),
+            silences: timestamps
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .filter(|(from, to)| *to - *from > limit)
+                .collect(),
+        })
+    }
+
+    /// Join the hourly presences of one target into its timeline. The usual
+    /// interval is the median of the hourly medians, weighted by how many
+    /// spacings each was taken over.
+    #[must_use]
+    pub fn timeline(label: String, mut presences: Vec<&Presence>) -> Option<TargetTimeline> {
+        presences.sort_by_key(|presence| presence.first);
+        let mut medians: Vec<(i64, usize)> = presences
+            .iter()
+            .filter(|presence| presence.spacings > 0)
+            .map(|presence| (presence.spacing_ms, presence.spacings))
+            .collect();
+        medians.sort_unstable();
+        let half = medians.iter().map(|(_, weight)| weight).sum::<usize>() / 2;
+        let mut seen = 0;
+        let interval = medians
+            .iter()
+            .find(|(_, weight)| {
+                seen += weight;
+                seen > half
+            })
+            .map_or(Duration::seconds(1), |(spacing_ms, _)| {
+                Duration::milliseconds(*spacing_ms)
+            });
+
+        let mut timeline = TargetTimeline {
+            label,
+            interval,
+            first: presences.first()?.first,
+            last: presences.first()?.last,
+            silences: Vec::new(),
+        };
+        for presence in presences {
+            if presence.first > timeline.last {
+                timeline.silences.push((timeline.last, presence.first));
+            }
+            timeline.silences.extend(&presence.silences);
+            timeline.last = timeline.last.max(presence.last);
+        }
+        Some(timeline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn hourly_presences_join_into_the_timeline_of_their_events() {
+        let start = Local.with_ymd_and_hms(2025, 12, 1, 8, 50, 0).unwrap();
+        // A ping every 10 seconds for 20 minutes, with a 5 minute silence
+        // inside the first hour and one across the hour boundary.
+        let timestamps: Vec<DateTime<Local>> = (0..120)
+            .map(|ping| start + Duration::seconds(ping * 10))
+            .filter(|timestamp| {
+                let minute = (*timestamp - start).num_minutes();
+                !(2..7).contains(&minute) && !(9..12).contains(&minute)
+            })
+            .collect();
+        let (before, after): (Vec<_>, Vec<_>) = timestamps.iter().partition(|timestamp| {
+            hour_start(**timestamp) < hour_start(start) + Duration::hours(1)
+        });
+        let hours = [
+            Presence::of(&before).unwrap(),
+            Presence::of(&after).unwrap(),
+        ];
+        let timeline =
+            Presence::timeline("google_dns".to_string(), hours.iter().collect()).unwrap();
+
+        assert_eq!(timeline.interval, Duration::seconds(10));
+        assert_eq!(timeline.first, start);
+        assert_eq!(timeline.last, *timestamps.last().unwrap());
+        let long: Vec<_> = timeline
+            .silences
+            .iter()
+            .map(|(from, to)| ((*from - start).num_seconds(), (*to - start).num_seconds()))
+            .collect();
+        assert_eq!(long, [(110, 420), (530, 720)]);
+    }
+}
@@ -0,0 +1,608 @@
+use crate::audit::Accumulator;
+use crate::audit::AuditReport;
+use crate::audit::CoverageReport;
+use crate::audit::DataGap;
+use crate::audit::Histories;
+use crate::audit::IncidentGrouping;
+use crate::audit::IncidentOptions;
+use crate::audit::IncidentReport;
+use crate::audit::OverviewReport;
+use crate::audit::PingEvent;
+use crate::audit::ReportBody;
+use crate::audit::ReportPeriod;
+use crate::audit::TargetHistory;
+use crate::audit::TargetKey;
+use crate::audit::VpnSpans;
+use crate::audit::extract_incidents;
+use crate::audit::format_duration;
+use crate::audit::html_escape;
+use crate::audit::vpn_active_spans;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::Timelike;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Width of every chart, in SVG user units.
+const WIDTH: f64 = 960.0;
+const RIGHT_MARGIN: f64 = 20.0;
+/// Room left of each latency chart for its axis labels.
+const LATENCY_LEFT: f64 = 60.0;
+/// Height of the plot area of each latency chart.
+const LATENCY_HEIGHT: f64 = 140.0;
+/// Number of time slots each latency line is averaged over.
+const LATENCY_SLOTS: usize = 240;
+const TIMELINE_ROW: f64 = 22.0;
+const HEATMAP_CELL_WIDTH: f64 = 34.0;
+const HEATMAP_CELL_HEIGHT: f64 = 22.0;
+const HEATMAP_LEFT: f64 = 90.0;
+
+const VPN_FILL: &str = "#dbeafe";
+const GAP_FILL: &str = "#d1d5db";
+const LINE_COLOUR: &str = "#2563eb";
+const FAILURE_COLOUR: &str = "#dc2626";
+
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 1000px; color: #111827; }
+h1, h2 { border-bottom: 1px solid #e5e7eb; padding-bottom: 0.2em; }
+h3 { margin-bottom: 0.3em; }
+table { border-collapse: collapse; margin-bottom: 1em; font-size: 0.9em; }
+th, td { border: 1px solid #e5e7eb; padding: 0.2em 0.6em; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+svg { display: block; margin-bottom: 1em; }
+svg text { font-size: 11px; fill: #374151; }
+.legend span { display: inline-block; width: 1em; height: 0.8em; margin: 0 0.3em 0 1em; vertical-align: middle; }
+";
+
+/// Maps timestamps onto the horizontal axis of the time-based charts.
+struct TimeAxis {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    left: f64,
+}
+
+impl TimeAxis {
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "millisecond spans stay far below 2^52"
+    )]
+    fn x(&self, timestamp: DateTime<Local>) -> f64 {
+        let span = (self.end - self.start).num_milliseconds().max(1) as f64;
+        let offset = (timestamp - self.start).num_milliseconds() as f64;
+        self.left + (offset / span) * (WIDTH - self.left - RIGHT_MARGIN)
+    }
+
+    /// The slot (of `slots` equal slots across the axis) holding the timestamp.
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "the position is clamped to the slot range before the cast"
+    )]
+    fn slot(&self, timestamp: DateTime<Local>, slots: usize) -> usize {
+        let share = (self.x(timestamp) - self.left) / (WIDTH - self.left - RIGHT_MARGIN);
+        ((share * slots as f64).floor().max(0.0) as usize).min(slots - 1)
+    }
+
+    /// Draw light VPN shading and time grid lines between `top` and `bottom`,
+    /// with time labels underneath.
+    fn draw_background(
+        &self,
+        out: &mut String,
+        vpn_spans: &[(DateTime<Local>, DateTime<Local>)],
+        top: f64,
+        bottom: f64,
+    ) {
+        for (start, end) in vpn_spans {
+            let x = self.x(*start);
+            let width = (self.x(*end) - x).max(1.0);
+            let _ = writeln!(
+                out,
+                r#"<rect x="{x:.1}" y="{top:.1}" width="{width:.1}" height="{:.1}" fill="{VPN_FILL}"><title>VPN active {} to {}</title></rect>"#,
+                bottom - top,
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%Y-%m-%d %H:%M"),
+            );
+        }
+        let ticks = 6;
+        for tick in 0..=ticks {
+            let timestamp = self.start + (self.end - self.start) * tick / ticks;
+            let x = self.x(timestamp);
+            let anchor = match tick {
+                0 => "start",
+                tick if tick == ticks => "end",
+                _ => "middle",
+            };
+            let _ = writeln!(
+                out,
+                r##"<line x1="{x:.1}" y1="{top:.1}" x2="{x:.1}" y2="{bottom:.1}" stroke="#e5e7eb"/><text x="{x:.1}" y="{:.1}" text-anchor="{anchor}">{}</text>"##,
+                bottom + 14.0,
+                timestamp.format("%m-%d %H:%M"),
+            );
+        }
+    }
+}
+
+/// Shade periods without data in grey between `top` and `bottom`.
+fn draw_gaps<'a>(
+    out: &mut String,
+    axis: &TimeAxis,
+    gaps: impl Iterator<Item = &'a DataGap>,
+    top: f64,
+    bottom: f64,
+) {
+    for gap in gaps {
+        let x = axis.x(gap.start);
+        let width = (axis.x(gap.end) - x).max(1.0);
+        let _ = writeln!(
+            out,
+            r#"<rect x="{x:.1}" y="{top:.1}" width="{width:.1}" height="{:.1}" fill="{GAP_FILL}"><title>No data {} to {}</title></rect>"#,
+            bottom - top,
+            gap.start.format("%Y-%m-%d %H:%M"),
+            gap.end.format("%Y-%m-%d %H:%M"),
+        );
+    }
+}
+
+/// Sum of latency, successful pings and failures in one time slot.
+type Slot = (f64, u64, u64);
+
+/// Failed and total pings per weekday and hour.
+type Heatmap = [[(usize, usize); 24]; 7];
+
+/// What the HTML report charts from the pings: per target the mean latency
+/// and failures in each time slot across the span of all pings, and the
+/// failures by weekday and hour.
+#[derive(Debug, Clone)]
+pub struct LatencyCharts {
+    /// The first and last ping of all targets, as found by a first pass.
+    span: Option<(DateTime<Local>, DateTime<Local>)>,
+    targets: BTreeMap<TargetKey, Vec<Slot>>,
+    heatmap: Heatmap,
+}
+
+impl LatencyCharts {
+    /// Chart pings across the span of the targets in `histories`.
+    #[must_use]
+    pub fn new(histories: &Histories) -> Self {
+        let first = histories.iter().filter_map(TargetHistory::first).min();
+        let last = histories.iter().filter_map(TargetHistory::last).max();
+        Self {
+            span: first.zip(last),
+            targets: BTreeMap::new(),
+            heatmap: [[(0, 0); 24]; 7],
+        }
+    }
+
+    fn add(&mut self, target: TargetKey, slots: Vec<Slot>) {
+        match self.targets.get_mut(&target) {
+            Some(earlier) => {
+                for (earlier, later) in earlier.iter_mut().zip(slots) {
+                    earlier.0 += later.0;
+                    earlier.1 += later.1;
+                    earlier.2 += later.2;
+                }
+            }
+            None => {
+                self.targets.insert(target, slots);
+            }
+        }
+    }
+}
+
+impl Accumulator for LatencyCharts {
+    fn record(&mut self, event: &PingEvent) {
+        let cell = &mut self.heatmap[event.timestamp.weekday().num_days_from_monday() as usize]
+            [event.timestamp.hour() as usize];
+        cell.1 += 1;
+        if !event.success {
+            cell.0 += 1;
+        }
+
+        let Some((start, end)) = self.span else {
+            return;
+        };
+        if !self.targets.contains_key(&event.target) {
+            let slots = vec![(0.0, 0, 0); LATENCY_SLOTS];
+            self.targets.insert(event.target.clone(), slots);
+        }
+        let Some(slots) = self.targets.get_mut(&event.target) else {
+            return;
+        };
+        let axis = TimeAxis {
+            start,
+            end,
+            left: LATENCY_LEFT,
+        };
+        let slot = &mut slots[axis.slot(event.timestamp, LATENCY_SLOTS)];
+        match (event.success, event.latency_ms) {
+            (true, Some(latency_ms)) => {
+                slot.0 += latency_ms;
+                slot.1 += 1;
+            }
+            (true, None) => {}
+            (false, _) => slot.2 += 1,
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (day, hours) in self.heatmap.iter_mut().zip(later.heatmap) {
+            for (cell, later) in day.iter_mut().zip(hours) {
+                cell.0 += later.0;
+                cell.1 += later.1;
+            }
+        }
+        for (target, slots) in later.targets {
+            self.add(target, slots);
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for (mut target, slots) in std::mem::take(&mut self.targets) {
+            rekey(&mut target);
+            self.add(target, slots);
+        }
+    }
+}
+
+/// Render a self-contained HTML report of the overview and the charts of the
+/// pings it was built from, with their histories: latency over time per
+/// target, a day-of-week by hour failure heatmap and incident timelines,
+/// with periods of active VPN and data gaps shaded. Charts are inline SVG,
+/// so the file needs no external assets.
+#[must_use]
+pub fn render_html(
+    report: &AuditReport<OverviewReport>,
+    charts: &LatencyCharts,
+    histories: &Histories,
+) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Piing audit</title>\n<style>");
+    out.push_str(STYLE);
+    out.push_str("</style>\n</head>\n<body>\n<h1>Piing audit</h1>\n<ul>\n");
+    let filter = &report.filter;
+    let _ = writeln!(
+        out,
+        "<li>Generated: {}</li>\n<li>Range: {}</li>",
+        report.generated_at.format("%Y-%m-%d %H:%M:%S"),
+        html_escape(&filter.range.to_string())
+    );
+    for (label, values) in [
+        ("Tags", &filter.tags),
+        ("Targets", &filter.targets),
+        ("Hosts", &filter.hosts),
+    ] {
+        if !values.is_empty() {
+            let _ = writeln!(out, "<li>{label}: {}</li>", html_escape(&values.join(", ")));
+        }
+    }
+    if let Some(database) = &report.source.database {
+        let _ = writeln!(
+            out,
+            "<li>Ping database: {}</li>",
+            html_escape(&database.display().to_string())
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "<li>Log files: {} found, {} read</li>",
+            report.source.files_found, report.source.files_read
+        );
+    }
+    let _ = writeln!(out, "<li>Ping events: {}</li>\n</ul>", report.source.events);
+
+    let Some((start, end)) = charts.span.filter(|_| !charts.targets.is_empty()) else {
+        out.push_str("<p>No ping events to chart.</p>\n</body>\n</html>\n");
+        return out;
+    };
+    let vpn_spans = vpn_active_spans(histories);
+    let legend = format!(
+        r#"<p class="legend"><span style="background:{VPN_FILL}"></span>VPN active<span style="background:{GAP_FILL}"></span>No data<span style="background:{LINE_COLOUR}"></span>Mean latency<span style="background:{FAILURE_COLOUR}"></span>Failures</p>"#
+    );
+
+    out.push_str("<h2>Latency over time</h2>\n");
+    out.push_str(&legend);
+    let axis = TimeAxis {
+        start,
+        end,
+        left: LATENCY_LEFT,
+    };
+    let labelled: BTreeMap<String, (&TargetKey, &[Slot])> = charts
+        .targets
+        .iter()
+        .map(|(target, slots)| (histories.label(target), (target, slots.as_slice())))
+        .collect();
+    for (label, (target, slots)) in labelled {
+        let _ = writeln!(out, "<h3>{}</h3>", html_escape(&label));
+        latency_chart(
+            &mut out,
+            &axis,
+            slots,
+            spans_of(&vpn_spans, target),
+            report.coverage.gaps_of(&label),
+        );
+    }
+
+    out.push_str("<h2>Failures by day of week and hour</h2>\n");
+    failure_heatmap(&mut out, &charts.heatmap);
+
+    out.push_str("<h2>Incidents</h2>\n");
+    out.push_str(&legend);
+    let timeline_axis = TimeAxis {
+        start,
+        end,
+        left: 260.0,
+    };
+    let options = IncidentOptions {
+        gap_factor: report.coverage.gap_factor,
+        ..IncidentOptions::default()
+    };
+    incident_timeline(
+        &mut out,
+        &timeline_axis,
+        histories,
+        &options,
+        &vpn_spans,
+        &report.coverage,
+    );
+
+    out.push_str("<h2>Tables</h2>\n");
+    let incidents = IncidentReport::build(histories, &options, ReportPeriod::All);
+    let tables = report
+        .body
+        .tables()
+        .into_iter()
+        .chain(incidents.tables())
+        .chain(report.coverage.tables());
+    for table in tables {
+        table.render_html(&mut out);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "latency sums and slot indexes stay far below 2^52"
+)]
+fn latency_chart<'a>(
+    out: &mut String,
+    axis: &TimeAxis,
+    slots: &[Slot],
+    vpn_spans: &[(DateTime<Local>, DateTime<Local>)],
+    gaps: impl Iterator<Item = &'a DataGap>,
+) {
+    let means: Vec<Option<f64>> = slots
+        .iter()
+        .map(|(sum, count, _)| (*count > 0).then(|| *sum / *count as f64))
+        .collect();
+    let scale = nice_ceiling(means.iter().flatten().copied().fold(0.0, f64::max));
+
+    let top = 10.0;
+    let bottom = top + LATENCY_HEIGHT;
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{:.0}" viewBox="0 0 {WIDTH} {:.0}">"#,
+        bottom + 24.0,
+        bottom + 24.0
+    );
+    axis.draw_background(out, vpn_spans, top, bottom);
+    draw_gaps(out, axis, gaps, top, bottom);
+    for fraction in [0.0, 0.5, 1.0] {
+        let y = bottom - fraction * LATENCY_HEIGHT;
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{:.0} ms</text>"#,
+            axis.left - 6.0,
+            y + 4.0,
+            fraction * scale
+        );
+    }
+
+    let slot_width = (WIDTH - axis.left - RIGHT_MARGIN) / LATENCY_SLOTS as f64;
+    let mut path = String::new();
+    let mut drawing = false;
+    for (index, mean) in means.iter().enumerate() {
+        let x = axis.left + (index as f64 + 0.5) * slot_width;
+        match mean {
+            Some(mean) => {
+                let y = bottom - (mean / scale) * LATENCY_HEIGHT;
+                let command = if drawing { 'L' } else { 'M' };
+                let _ = write!(path, "{command}{x:.1},{y:.1} ");
+                drawing = true;
+            }
+            None => drawing = false,
+        }
+    }
+    let _ = writeln!(
+        out,
+        r#"<path d="{}" fill="none" stroke="{LINE_COLOUR}" stroke-width="1.5"/>"#,
+        path.trim_end()
+    );
+    for (index, (_, _, failures)) in slots.iter().enumerate() {
+        if *failures > 0 {
+            let x = axis.left + index as f64 * slot_width;
+            let _ = writeln!(
+                out,
+                r#"<rect x="{x:.1}" y="{top:.1}" width="{:.1}" height="6" fill="{FAILURE_COLOUR}"><title>{failures} failed ping(s)</title></rect>"#,
+                slot_width.max(1.0)
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+}
+
+fn failure_heatmap(out: &mut String, cells: &Heatmap) {
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "event counts stay far below 2^52"
+    )]
+    let rate = |(failures, total): (usize, usize)| failures as f64 / total.max(1) as f64;
+    let max_rate = cells
+        .iter()
+        .flatten()
+        .map(|cell| rate(*cell))
+        .fold(0.0, f64::max);
+
+    let top = 20.0;
+    let height = top + 7.0 * HEATMAP_CELL_HEIGHT + 4.0;
+    let width = HEATMAP_LEFT + 24.0 * HEATMAP_CELL_WIDTH + RIGHT_MARGIN;
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}">"#
+    );
+    for hour in 0..24 {
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="14" text-anchor="middle">{hour}</text>"#,
+            HEATMAP_LEFT + (f64::from(hour) + 0.5) * HEATMAP_CELL_WIDTH
+        );
+    }
+    for (day, (name, hours)) in (0..).zip(DAY_NAMES.iter().zip(cells)) {
+        let y = top + f64::from(day) * HEATMAP_CELL_HEIGHT;
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{name}</text>"#,
+            HEATMAP_LEFT - 6.0,
+            y + HEATMAP_CELL_HEIGHT / 2.0 + 4.0
+        );
+        for (hour, cell) in (0..).zip(hours) {
+            let (failures, total) = *cell;
+            let fill = if total == 0 {
+                "#f3f4f6".to_string()
+            } else if failures == 0 {
+                "#ecfdf5".to_string()
+            } else {
+                let alpha = 0.15 + 0.85 * rate(*cell) / max_rate;
+                format!("rgba(220,38,38,{alpha:.2})")
+            };
+            let _ = writeln!(
+                out,
+                r##"<rect x="{:.1}" y="{y:.1}" width="{:.1}" height="{:.1}" fill="{fill}" stroke="#ffffff"><title>{name} {hour:02}:00 - {failures} of {total} pings failed ({:.2}%)</title></rect>"##,
+                HEATMAP_LEFT + f64::from(hour) * HEATMAP_CELL_WIDTH,
+                HEATMAP_CELL_WIDTH,
+                HEATMAP_CELL_HEIGHT,
+                rate(*cell) * 100.0
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+}
+
+/// The VPN spans of the machine that probed a target.
+fn spans_of<'a>(
+    vpn_spans: &'a VpnSpans,
+    target: &TargetKey,
+) -> &'a [(DateTime<Local>, DateTime<Local>)] {
+    vpn_spans.get(&target.machine).map_or(&[], Vec::as_slice)
+}
+
+fn incident_timeline(
+    out: &mut String,
+    axis: &TimeAxis,
+    histories: &Histories,
+    options: &IncidentOptions,
+    vpn_spans: &VpnSpans,
+    coverage: &CoverageReport,
+) {
+    let groups = histories.groups(IncidentGrouping::Target);
+    let incidents = extract_incidents(histories, options);
+
+    let top = 10.0;
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "there are only ever a handful of targets"
+    )]
+    let bottom = top + groups.len() as f64 * TIMELINE_ROW;
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{:.0}" viewBox="0 0 {WIDTH} {:.0}">"#,
+        bottom + 24.0,
+        bottom + 24.0
+    );
+    for (row, (group, members)) in (0..).zip(&groups) {
+        let y = top + f64::from(row) * TIMELINE_ROW;
+        // Each row is shaded with the VPN state of its own machine.
+        axis.draw_background(
+            out,
+            spans_of(vpn_spans, &members[0].target),
+            y,
+            y + TIMELINE_ROW,
+        );
+        let _ = writeln!(
+            out,
+            r##"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text><line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#9ca3af"/>"##,
+            axis.left - 6.0,
+            y + TIMELINE_ROW / 2.0 + 4.0,
+            html_escape(group),
+            axis.left,
+            y + TIMELINE_ROW / 2.0,
+            WIDTH - RIGHT_MARGIN,
+            y + TIMELINE_ROW / 2.0,
+        );
+        draw_gaps(
+            out,
+            axis,
+            coverage.gaps_of(group),
+            y + 4.0,
+            y + TIMELINE_ROW - 4.0,
+        );
+        for incident in incidents.iter().filter(|incident| &incident.group == group) {
+            let x = axis.x(incident.start);
+            let width = (axis.x(incident.end) - x).max(1.5);
+            let _ = writeln!(
+                out,
+                r#"<rect x="{x:.1}" y="{:.1}" width="{width:.1}" height="{:.1}" fill="{FAILURE_COLOUR}"><title>{} for {}, {} failed probe(s){}</title></rect>"#,
+                y + 4.0,
+                TIMELINE_ROW - 8.0,
+                incident.start.format("%Y-%m-%d %H:%M:%S"),
+                format_duration(incident.duration()),
+                incident.failed_probes,
+                if incident.recovered {
+                    ""
+                } else {
+                    ", not recovered"
+                },
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+}
+
+/// Round a chart maximum up to 1, 2 or 5 times a power of ten.
+fn nice_ceiling(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|candidate| *candidate >= value)
+        .unwrap_or(10.0 * magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chart_maximum_rounds_up_to_a_round_number() {
+        assert!((nice_ceiling(0.0) - 1.0).abs() < f64::EPSILON);
+        assert!((nice_ceiling(34.2) - 50.0).abs() < f64::EPSILON);
+        assert!((nice_ceiling(150.0) - 200.0).abs() < f64::EPSILON);
+        assert!((nice_ceiling(1_000.0) - 1_000.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::audit::Cell;
+use crate::audit::Histories;
+use crate::audit::Incident;
+use crate::audit::IncidentOptions;
+use crate::audit::ReportBody;
+use crate::audit::ReportPeriod;
+use crate::audit::Table;
+use crate::audit::extract_incidents;
+use crate::audit::incident_stats;
+use crate::audit::serialize_optional_seconds;
+use crate::audit::serialize_seconds;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use serde::Serialize;
+
+/// One incident as reported.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentRow {
+    pub group: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    #[serde(rename = "duration_seconds", serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+    pub failed_probes: usize,
+    pub recovered: bool,
+}
+
+/// Incident statistics of one group and period as reported.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentStatsRow {
+    pub group: String,
+    pub period: String,
+    pub incidents: usize,
+    #[serde(rename = "downtime_seconds", serialize_with = "serialize_seconds")]
+    pub downtime: Duration,
+    #[serde(rename = "mttr_seconds", serialize_with = "serialize_optional_seconds")]
+    pub mttr: Option<Duration>,
+    #[serde(rename = "mtbf_seconds", serialize_with = "serialize_optional_seconds")]
+    pub mtbf: Option<Duration>,
+    #[serde(
+        rename = "longest_seconds",
+        serialize_with = "serialize_optional_seconds"
+    )]
+    pub longest: Option<Duration>,
+}
+
+/// The `audit incidents` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentReport {
+    pub incidents: Vec<IncidentRow>,
+    pub stats: Vec<IncidentStatsRow>,
+}
+
+impl IncidentReport {
+    #[must_use]
+    pub fn build(histories: &Histories, options: &IncidentOptions, period: ReportPeriod) -> Self {
+        Self::from_incidents(
+            histories,
+            extract_incidents(histories, options),
+            options,
+            period,
+        )
+    }
+
+    /// The view of incidents found elsewhere, such as those kept in the ping
+    /// database, ordered by group and start time.
+    #[must_use]
+    pub fn from_incidents(
+        histories: &Histories,
+        incidents: Vec<Incident>,
+        options: &IncidentOptions,
+        period: ReportPeriod,
+    ) -> Self {
+        let stats = incident_stats(histories, &incidents, options, period)
+            .into_iter()
+            .map(|stats| IncidentStatsRow {
+                mttr: stats.mttr(),
+                mtbf: stats.mtbf(),
+                group: stats.group,
+                period: stats.period,
+                incidents: stats.incidents,
+                downtime: stats.downtime,
+                longest: stats.longest,
+            })
+            .collect();
+        let incidents = incidents
+            .into_iter()
+            .map(|incident| IncidentRow {
+                duration: incident.duration(),
+                group: incident.group,
+                start: incident.start,
+                end: incident.end,
+                failed_probes: incident.failed_probes,
+                recovered: incident.recovered,
+            })
+            .collect();
+        Self { incidents, stats }
+    }
+}
+
+impl ReportBody for IncidentReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut incidents = Table::new(
+            "Incidents",
+            &["Group", "Start", "End", "Duration", "Failed probes"],
+        );
+        if self.incidents.is_empty() {
+            incidents.push_note("No incidents found.");
+            return vec![incidents];
+        }
+        for incident in &self.incidents {
+            incidents.push_row(vec![
+                incident.group.as_str().into(),
+                Cell::Time(incident.start),
+                if incident.recovered {
+                    Cell::Time(incident.end)
+                } else {
+                    "(not recovered)".into()
+                },
+                incident.duration.into(),
+                incident.failed_probes.into(),
+            ]);
+        }
+        incidents.push_note(format!("Total incidents: {}", self.incidents.len()));
+
+        let mut stats = Table::new(
+            "Incident Statistics",
+            &[
+                "Group",
+                "Period",
+                "Incidents",
+                "Downtime",
+                "MTTR",
+                "MTBF",
+                "Longest",
+            ],
+        );
+        for row in &self.stats {
+            stats.push_row(vec![
+                row.group.as_str().into(),
+                row.period.as_str().into(),
+                row.incidents.into(),
+                row.downtime.into(),
+                row.mttr.into(),
+                row.mtbf.into(),
+                row.longest.into(),
+            ]);
+        }
+        vec![incidents, stats]
+    }
+}
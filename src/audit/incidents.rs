@@ -0,0 +1,396 @@
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::PingEvent;
+use crate::audit::ReportPeriod;
+use crate::audit::TargetHistory;
+use crate::audit::TargetKey;
+use crate::audit::gap_limit;
+use crate::audit::interleaved_runs;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// How ping events are grouped before extracting incidents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum IncidentGrouping {
+    /// Every target has its own incidents.
+    #[default]
+    Target,
+    /// Targets sharing a tag form a group.
+    Tag,
+    /// All selected targets form a single group.
+    All,
+}
+
+/// Settings for turning failed pings into incidents.
+#[derive(Debug, Clone)]
+pub struct IncidentOptions {
+    pub grouping: IncidentGrouping,
+    /// Incidents starting within this long after the previous one ended are merged into it.
+    pub merge_gap: Duration,
+    /// Incidents shorter than this are dropped.
+    pub min_duration: Duration,
+    /// Silences longer than this many times the usual interval end an
+    /// incident instead of extending it.
+    pub gap_factor: i32,
+}
+
+impl Default for IncidentOptions {
+    fn default() -> Self {
+        Self {
+            grouping: IncidentGrouping::Target,
+            merge_gap: Duration::zero(),
+            min_duration: Duration::zero(),
+            gap_factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
+/// A period during which a target (or any member of a group) was failing.
+#[derive(Debug, Clone)]
+pub struct Incident {
+    pub group: String,
+    /// The machine whose failed ping opened the incident, when the audited
+    /// logs come from more than one machine.
+    pub machine: Option<String>,
+    /// Time of the first failed ping.
+    pub start: DateTime<Local>,
+    /// Time of the first successful ping afterwards, or of the last failure
+    /// when the logs end or a data gap starts before recovery.
+    pub end: DateTime<Local>,
+    pub failed_probes: usize,
+    pub recovered: bool,
+}
+
+impl Incident {
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Incident statistics of one group over one period.
+#[derive(Debug, Clone)]
+pub struct IncidentStats {
+    pub group: String,
+    pub period: String,
+    pub incidents: usize,
+    pub downtime: Duration,
+    /// Time spanned by the group's events in the period, less data gaps.
+    pub observed: Duration,
+    pub longest: Option<Duration>,
+}
+
+impl IncidentStats {
+    /// Mean time to recovery: the average incident duration.
+    #[must_use]
+    pub fn mttr(&self) -> Option<Duration> {
+        let incidents = i32::try_from(self.incidents).ok()?;
+        (incidents > 0).then(|| self.downtime / incidents)
+    }
+
+    /// Mean time between failures: observed time without an incident,
+    /// divided by the number of incidents.
+    #[must_use]
+    pub fn mtbf(&self) -> Option<Duration> {
+        let incidents = i32::try_from(self.incidents).ok()?;
+        (incidents > 0).then(|| (self.observed - self.downtime).max(Duration::zero()) / incidents)
+    }
+}
+
+/// Split events into named groups, each sorted by time.
+#[must_use]
+pub fn group_events(
+    events: &[PingEvent],
+    grouping: IncidentGrouping,
+) -> BTreeMap<String, Vec<&PingEvent>> {
+    // Label targets by the last name they were logged with.
+    let mut names: HashMap<&TargetKey, &str> = HashMap::new();
+    for event in events {
+        if let Some(name) = &event.target_name {
+            names.insert(&event.target, name);
+        }
+    }
+    let mut groups: BTreeMap<String, Vec<&PingEvent>> = BTreeMap::new();
+    for event in events {
+        match grouping {
+            IncidentGrouping::Target => {
+                let label = match names.get(&event.target) {
+                    Some(name) => format!("{name} ({})", event.target),
+                    None => event.target.to_string(),
+                };
+                groups.entry(label).or_default().push(event);
+            }
+            IncidentGrouping::Tag => {
+                for tag in &event.tags {
+                    groups
+                        .entry(format!("tag:{}", tag.to_lowercase()))
+                        .or_default()
+                        .push(event);
+                }
+            }
+            IncidentGrouping::All => {
+                groups
+                    .entry("all targets".to_string())
+                    .or_default()
+                    .push(event);
+            }
+        }
+    }
+    for members in groups.values_mut() {
+        members.sort_by_key(|event| event.timestamp);
+    }
+    groups
+}
+
+/// Group consecutive failures into incidents, ordered by group and start time.
+///
+/// A group is failing from the first failed ping of any member until every
+/// member that failed has succeeded again. A data gap ends the incident at its
+/// last failure, since nothing is known about the silence.
+#[must_use]
+pub fn extract_incidents(histories: &Histories, options: &IncidentOptions) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+    for (group, members) in histories.groups(options.grouping) {
+        incidents.extend(extract_group_incidents(&group, &members, options));
+    }
+    incidents
+}
+
+fn extract_group_incidents(
+    group: &str,
+    members: &[&TargetHistory],
+    options: &IncidentOptions,
+) -> Vec<Incident> {
+    let mut closed: Vec<Incident> = Vec::new();
+    let mut open: Option<Incident> = None;
+    let mut failing: HashSet<usize> = HashSet::new();
+    let limit = gap_limit(members, options.gap_factor);
+    // The latest ping so far; the pings of a run follow each other closely.
+    let mut previous_time: Option<DateTime<Local>> = None;
+    let mut after_gap = false;
+
+    for (member, run) in interleaved_runs(members) {
+        if previous_time.is_some_and(|previous| run.first - previous > limit) {
+            closed.extend(open.take());
+            failing.clear();
+            after_gap = true;
+        }
+        previous_time = Some(previous_time.map_or(run.last, |previous| previous.max(run.last)));
+
+        if run.success {
+            failing.remove(&member);
+            if failing.is_empty()
+                && let Some(mut incident) = open.take()
+            {
+                incident.end = run.first;
+                incident.recovered = true;
+                closed.push(incident);
+                after_gap = false;
+            }
+            continue;
+        }
+
+        failing.insert(member);
+        let incident = open.get_or_insert_with(|| {
+            // Reopen the previous incident when this one starts within the merge gap.
+            if let Some(previous) = closed.last()
+                && !after_gap
+                && run.first - previous.end <= options.merge_gap
+            {
+                let mut previous = closed.pop().expect("checked above");
+                previous.recovered = false;
+                return previous;
+            }
+            Incident {
+                group: group.to_string(),
+                machine: members[member].target.machine.clone(),
+                start: run.first,
+                end: run.first,
+                failed_probes: 0,
+                recovered: false,
+            }
+        });
+        incident.failed_probes += run.pings;
+        incident.end = incident.end.max(run.last);
+    }
+    closed.extend(open);
+    closed.retain(|incident| incident.duration() >= options.min_duration);
+    closed
+}
+
+/// Summarise incidents per group and period. Incidents count towards the
+/// period they started in, and data gaps are left out of the observed time.
+#[must_use]
+pub fn incident_stats(
+    histories: &Histories,
+    incidents: &[Incident],
+    options: &IncidentOptions,
+    period: ReportPeriod,
+) -> Vec<IncidentStats> {
+    let mut stats: BTreeMap<(String, String), IncidentStats> = BTreeMap::new();
+    for (group, members) in histories.groups(options.grouping) {
+        let limit = gap_limit(&members, options.gap_factor);
+        let mut observed: BTreeMap<String, Duration> = BTreeMap::new();
+        // The latest ping so far and its period.
+        let mut previous: Option<(DateTime<Local>, String)> = None;
+        for (_, run) in interleaved_runs(&members) {
+            let label = period.label(run.first);
+            let total = observed.entry(label.clone()).or_insert_with(Duration::zero);
+            // The time before a run counts unless it is a data gap or spans
+            // periods, and the time within it counts once however the runs
+            // of the group overlap.
+            if let Some((before, before_label)) = &previous
+                && *before < run.first
+                && *before_label == label
+                && run.first - *before <= limit
+            {
+                *total += run.first - *before;
+            }
+            let covered = previous
+                .as_ref()
+                .map_or(run.first, |(before, _)| run.first.max(*before));
+            if run.last > covered {
+                *total += run.last - covered;
+            }
+            if previous
+                .as_ref()
+                .is_none_or(|(before, _)| run.last > *before)
+            {
+                previous = Some((run.last, label));
+            }
+        }
+        for (label, observed) in observed {
+            stats.insert(
+                (group.clone(), label.clone()),
+                IncidentStats {
+                    group: group.clone(),
+                    period: label,
+                    incidents: 0,
+                    downtime: Duration::zero(),
+                    observed,
+                    longest: None,
+                },
+            );
+        }
+    }
+    for incident in incidents {
+        let key = (incident.group.clone(), period.label(incident.start));
+        let Some(entry) = stats.get_mut(&key) else {
+            continue;
+        };
+        let duration = incident.duration();
+        entry.incidents += 1;
+        entry.downtime += duration;
+        entry.longest = Some(
+            entry
+                .longest
+                .map_or(duration, |longest| longest.max(duration)),
+        );
+    }
+    stats.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(host: &str, second: u32, success: bool) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, 0, second).unwrap();
+        PingEvent::test(host, at, success).with_tags(&["dns"])
+    }
+
+    #[test]
+    fn consecutive_failures_form_one_incident() {
+        let events = [
+            event("8.8.8.8", 0, true),
+            event("8.8.8.8", 10, false),
+            event("8.8.8.8", 20, false),
+            event("8.8.8.8", 30, true),
+            event("8.8.8.8", 40, false),
+            event("8.8.8.8", 45, true),
+        ];
+        let histories = Histories::of(&events);
+        let incidents = extract_incidents(&histories, &IncidentOptions::default());
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].failed_probes, 2);
+        assert_eq!(incidents[0].duration(), Duration::seconds(20));
+        assert!(incidents[0].recovered);
+
+        let merged = extract_incidents(
+            &histories,
+            &IncidentOptions {
+                merge_gap: Duration::seconds(10),
+                ..IncidentOptions::default()
+            },
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].failed_probes, 3);
+        assert_eq!(merged[0].duration(), Duration::seconds(35));
+
+        let long_only = extract_incidents(
+            &histories,
+            &IncidentOptions {
+                min_duration: Duration::seconds(10),
+                ..IncidentOptions::default()
+            },
+        );
+        assert_eq!(long_only.len(), 1);
+    }
+
+    #[test]
+    fn group_stays_down_until_every_member_recovers() {
+        let events = [
+            event("8.8.8.8", 0, false),
+            event("1.1.1.1", 1, false),
+            event("8.8.8.8", 10, true),
+            event("1.1.1.1", 11, false),
+            event("1.1.1.1", 21, true),
+        ];
+        let histories = Histories::of(&events);
+        let incidents = extract_incidents(
+            &histories,
+            &IncidentOptions {
+                grouping: IncidentGrouping::All,
+                ..IncidentOptions::default()
+            },
+        );
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].duration(), Duration::seconds(21));
+
+        let stats = incident_stats(
+            &histories,
+            &incidents,
+            &IncidentOptions {
+                grouping: IncidentGrouping::All,
+                ..IncidentOptions::default()
+            },
+            ReportPeriod::All,
+        );
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].mttr(), Some(Duration::seconds(21)));
+    }
+
+    #[test]
+    fn data_gaps_end_incidents_without_recovery() {
+        let events = [
+            event("8.8.8.8", 0, true),
+            event("8.8.8.8", 1, false),
+            event("8.8.8.8", 2, false),
+            event("8.8.8.8", 30, false),
+            event("8.8.8.8", 31, true),
+        ];
+        let histories = Histories::of(&events);
+        let incidents = extract_incidents(&histories, &IncidentOptions::default());
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].duration(), Duration::seconds(1));
+        assert!(!incidents[0].recovered);
+        assert_eq!(incidents[1].duration(), Duration::seconds(1));
+        assert!(incidents[1].recovered);
+    }
+}
@@ -0,0 +1,277 @@
+use hdrhistogram::Histogram;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Latencies above this are clamped; probes time out long before it.
+const MAX_TRACKED_LATENCY_US: u64 = 60_000_000;
+
+/// Upper bounds (exclusive) of the buckets used for ASCII latency histograms.
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000];
+
+/// A serialisable snapshot of [`LatencyStats`].
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub min_ms: Option<f64>,
+    pub mean_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+/// Streaming latency statistics of successful pings.
+///
+/// Values go into an HDR histogram of microseconds with two significant
+/// digits, so percentiles stay within 1% of the true value while memory
+/// stays constant no matter how many pings are recorded. The exact minimum
+/// and maximum are tracked alongside.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "LatencySketch", from = "LatencySketch")]
+pub struct LatencyStats {
+    histogram: Histogram<u64>,
+    sum_ms: f64,
+    min_ms: Option<f64>,
+    max_ms: Option<f64>,
+}
+
+impl Default for LatencyStats {
+    fn default() -> Self {
+        Self {
+            histogram: Histogram::new_with_bounds(1, MAX_TRACKED_LATENCY_US, 2)
+                .expect("latency histogram bounds are valid"),
+            sum_ms: 0.0,
+            min_ms: None,
+            max_ms: None,
+        }
+    }
+}
+
+/// The stored form of [`LatencyStats`]: the count of every non-empty
+/// histogram bucket, keyed by the highest latency in microseconds the
+/// bucket holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LatencySketch {
+    buckets_us: Vec<(u64, u64)>,
+    sum_ms: f64,
+    min_ms: Option<f64>,
+    max_ms: Option<f64>,
+}
+
+impl From<LatencyStats> for LatencySketch {
+    fn from(stats: LatencyStats) -> Self {
+        Self {
+            buckets_us: stats
+                .histogram
+                .iter_recorded()
+                .map(|bucket| (bucket.value_iterated_to(), bucket.count_at_value()))
+                .collect(),
+            sum_ms: stats.sum_ms,
+            min_ms: stats.min_ms,
+            max_ms: stats.max_ms,
+        }
+    }
+}
+
+impl From<LatencySketch> for LatencyStats {
+    fn from(sketch: LatencySketch) -> Self {
+        let mut stats = LatencyStats::default();
+        for (latency_us, count) in sketch.buckets_us {
+            stats.histogram.saturating_record_n(latency_us, count);
+        }
+        stats.sum_ms = sketch.sum_ms;
+        stats.min_ms = sketch.min_ms;
+        stats.max_ms = sketch.max_ms;
+        stats
+    }
+}
+
+impl LatencyStats {
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "the histogram clamps latencies to its bounds"
+    )]
+    pub fn record(&mut self, latency_ms: f64) {
+        let latency_ms = latency_ms.max(0.0);
+        self.histogram
+            .saturating_record((latency_ms * 1000.0).round() as u64);
+        self.sum_ms += latency_ms;
+        self.min_ms = Some(self.min_ms.map_or(latency_ms, |min| min.min(latency_ms)));
+        self.max_ms = Some(self.max_ms.map_or(latency_ms, |max| max.max(latency_ms)));
+    }
+
+    /// Fold another set of statistics into this one.
+    ///
+    /// # Panics
+    /// Never in practice: every instance shares the same histogram bounds
+    pub fn merge(&mut self, other: &LatencyStats) {
+        self.histogram
+            .add(&other.histogram)
+            .expect("latency histograms share the same bounds");
+        self.sum_ms += other.sum_ms;
+        self.min_ms = match (self.min_ms, other.min_ms) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_ms = match (self.max_ms, other.max_ms) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    #[must_use]
+    pub fn min_ms(&self) -> Option<f64> {
+        self.min_ms
+    }
+
+    #[must_use]
+    pub fn max_ms(&self) -> Option<f64> {
+        self.max_ms
+    }
+
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "ping counts stay far below 2^52"
+    )]
+    pub fn mean_ms(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.sum_ms / count as f64)
+    }
+
+    /// The latency at the given percentile (0-100), or `None` when nothing was recorded.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "the histogram holds at most a minute of microseconds"
+    )]
+    pub fn percentile_ms(&self, percentile: f64) -> Option<f64> {
+        if self.histogram.is_empty() {
+            return None;
+        }
+        let value = self.histogram.value_at_quantile(percentile / 100.0) as f64 / 1000.0;
+        // Report the exact maximum rather than the top of its histogram bucket.
+        Some(self.max_ms.map_or(value, |max| value.min(max)))
+    }
+
+    #[must_use]
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count(),
+            min_ms: self.min_ms,
+            mean_ms: self.mean_ms(),
+            p50_ms: self.percentile_ms(50.0),
+            p90_ms: self.percentile_ms(90.0),
+            p95_ms: self.percentile_ms(95.0),
+            p99_ms: self.percentile_ms(99.0),
+            max_ms: self.max_ms,
+        }
+    }
+
+    /// Count latencies per [`LATENCY_BUCKETS_MS`] bucket, plus a final
+    /// bucket for everything at or above the last bound.
+    #[must_use]
+    pub fn bucket_counts(&self) -> Vec<(String, u64)> {
+        let mut lower = 0;
+        let mut buckets = Vec::with_capacity(LATENCY_BUCKETS_MS.len() + 1);
+        for upper in LATENCY_BUCKETS_MS {
+            buckets.push((format!("{lower}-{upper} ms"), self.count_in(lower, upper)));
+            lower = upper;
+        }
+        buckets.push((
+            format!(">= {lower} ms"),
+            self.count_in(lower, MAX_TRACKED_LATENCY_US / 1000 + 1),
+        ));
+        buckets
+    }
+
+    /// Count the latencies from `lower` up to `upper` milliseconds.
+    fn count_in(&self, lower: u64, upper: u64) -> u64 {
+        if self.histogram.is_empty() {
+            return 0;
+        }
+        // `count_between` is inclusive at both ends and works on histogram
+        // buckets, so count everything below `upper` and subtract.
+        let below = |bound: u64| {
+            if bound == 0 {
+                0
+            } else {
+                self.histogram.count_between(0, bound * 1000 - 1)
+            }
+        };
+        below(upper) - below(lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_are_close_to_exact() {
+        let mut stats = LatencyStats::default();
+        for latency_ms in 1..=1_000 {
+            stats.record(f64::from(latency_ms));
+        }
+        let p50 = stats.percentile_ms(50.0).unwrap();
+        let p99 = stats.percentile_ms(99.0).unwrap();
+        assert!((p50 - 500.0).abs() <= 5.0, "p50 was {p50}");
+        assert!((p99 - 990.0).abs() <= 10.0, "p99 was {p99}");
+        assert_eq!(stats.percentile_ms(100.0), Some(1_000.0));
+        assert_eq!(
+            stats.bucket_counts().iter().map(|(_, n)| n).sum::<u64>(),
+            1_000
+        );
+    }
+
+    #[test]
+    fn merge_combines_counts_and_bounds() {
+        let mut a = LatencyStats::default();
+        let mut b = LatencyStats::default();
+        a.record(10.0);
+        b.record(3.0);
+        b.record(40.0);
+        a.merge(&b);
+        assert_eq!(a.count(), 3);
+        assert_eq!(a.min_ms(), Some(3.0));
+        assert_eq!(a.max_ms(), Some(40.0));
+    }
+
+    #[test]
+    fn fractional_latencies_keep_their_precision() {
+        let mut stats = LatencyStats::default();
+        for latency_ms in [0.42, 0.45, 0.47] {
+            stats.record(latency_ms);
+        }
+        let p50 = stats.percentile_ms(50.0).unwrap();
+        assert!((p50 - 0.45).abs() < 0.005, "p50 was {p50}");
+        assert_eq!(stats.min_ms(), Some(0.42));
+        assert!((stats.mean_ms().unwrap() - 0.4466).abs() < 0.001);
+        assert_eq!(stats.bucket_counts()[0].1, 3);
+    }
+
+    #[test]
+    fn sketch_round_trips_percentiles() {
+        let mut stats = LatencyStats::default();
+        for latency_ms in [3.0, 12.4, 12.4, 250.0, 1_480.0] {
+            stats.record(latency_ms);
+        }
+        let json = serde_json::to_string(&stats).unwrap();
+        let restored: LatencyStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.count(), 5);
+        for percentile in [10.0, 50.0, 90.0, 100.0] {
+            assert_eq!(
+                restored.percentile_ms(percentile),
+                stats.percentile_ms(percentile)
+            );
+        }
+        assert_eq!(restored.mean_ms(), stats.mean_ms());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::audit::Accumulator;
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use crate::config::LatencyAnomalySettings;
+use crate::latency_baseline::AnomalyChange;
+use crate::latency_baseline::LatencyAnomaly;
+use crate::latency_baseline::LatencyBaseline;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Settings for finding latency degradations in the logs.
+#[derive(Debug, Clone)]
+pub struct AnomalyOptions {
+    pub settings: LatencyAnomalySettings,
+    /// Silences longer than this many times a target's usual interval end a
+    /// degradation in progress without recovery.
+    pub gap_factor: i32,
+}
+
+impl Default for AnomalyOptions {
+    fn default() -> Self {
+        Self {
+            settings: LatencyAnomalySettings::default(),
+            gap_factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
+/// A period during which a target's latency stayed above its rolling baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyDegradation {
+    pub target: String,
+    /// Time of the first ping above the threshold.
+    pub start: DateTime<Local>,
+    /// Time of the first ping back under the threshold, or of the last slow
+    /// ping when the logs end or a data gap starts before recovery.
+    pub end: DateTime<Local>,
+    pub recovered: bool,
+    /// Pings above the threshold.
+    pub pings: u64,
+    pub peak_ms: f64,
+    pub baseline_ms: f64,
+    pub threshold_ms: f64,
+}
+
+impl LatencyDegradation {
+    fn new(target: &str, anomaly: LatencyAnomaly, end: DateTime<Local>, recovered: bool) -> Self {
+        Self {
+            target: target.to_string(),
+            start: anomaly.since,
+            end,
+            recovered,
+            pings: anomaly.pings,
+            peak_ms: anomaly.peak_ms,
+            baseline_ms: anomaly.baseline_ms,
+            threshold_ms: anomaly.threshold_ms,
+        }
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// What finding latency degradations gathers: every target's pings replayed
+/// through the same rolling baseline the runtime keeps, and the periods it
+/// would have reported as `latency_anomaly`.
+///
+/// Pings inside maintenance windows or resume grace periods are skipped, as
+/// they are at runtime.
+#[derive(Debug, Clone)]
+pub struct LatencyReplay {
+    options: AnomalyOptions,
+    /// The usual interval of every target over all its pings, found by a
+    /// first pass, as data gaps are told by it.
+    intervals: Arc<HashMap<TargetKey, Duration>>,
+    targets: BTreeMap<TargetKey, TargetReplay>,
+}
+
+impl LatencyReplay {
+    /// Replay pings with the usual intervals of the targets in `histories`.
+    #[must_use]
+    pub fn new(options: AnomalyOptions, histories: &Histories) -> Self {
+        let intervals = histories
+            .iter()
+            .map(|history| (history.target.clone(), history.usual_interval()))
+            .collect();
+        Self {
+            options,
+            intervals: Arc::new(intervals),
+            targets: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn options(&self) -> &AnomalyOptions {
+        &self.options
+    }
+
+    /// Silences longer than this end a degradation of `target` in progress.
+    fn gap_limit(&self, target: &TargetKey) -> Duration {
+        // The first pass settled the machines, so a target may be known
+        // there without the machine its pings are replayed under.
+        let interval = self.intervals.get(target).copied().or_else(|| {
+            self.intervals
+                .iter()
+                .filter(|(known, _)| known.host == target.host && known.mode == target.mode)
+                .map(|(_, interval)| *interval)
+                .max()
+        });
+        interval.unwrap_or(Duration::seconds(1)) * self.options.gap_factor
+    }
+
+    /// Every degradation found, including those still in progress when the
+    /// logs end, by start time, labelled as in `histories`.
+    #[must_use]
+    pub fn degradations(&self, histories: &Histories) -> Vec<LatencyDegradation> {
+        let mut degradations = Vec::new();
+        for (target, replay) in &self.targets {
+            let label = histories.label(target);
+            let mut baseline = replay.baseline.clone();
+            let ended = baseline
+                .interrupt()
+                .map(|anomaly| (anomaly, anomaly.last, false));
+            for &(anomaly, end, recovered) in replay.degradations.iter().chain(&ended) {
+                degradations.push(LatencyDegradation::new(&label, anomaly, end, recovered));
+            }
+        }
+        degradations.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.target.cmp(&b.target)));
+        degradations
+    }
+}
+
+impl Accumulator for LatencyReplay {
+    const ORDERED: bool = true;
+
+    fn record(&mut self, event: &PingEvent) {
+        if !self.targets.contains_key(&event.target) {
+            let replay = TargetReplay::new(self.gap_limit(&event.target));
+            self.targets.insert(event.target.clone(), replay);
+        }
+        if let Some(replay) = self.targets.get_mut(&event.target) {
+            replay.record(event, &self.options.settings);
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (target, replay) in later.targets {
+            match self.targets.get_mut(&target) {
+                Some(earlier) => earlier.follow(replay),
+                None => {
+                    self.targets.insert(target, replay);
+                }
+            }
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        let targets = std::mem::take(&mut self.targets);
+        for (mut target, replay) in targets {
+            rekey(&mut target);
+            match self.targets.get_mut(&target) {
+                Some(earlier) => earlier.follow(replay),
+                None => {
+                    self.targets.insert(target, replay);
+                }
+            }
+        }
+    }
+}
+
+/// The baseline of one target and the degradations it ended.
+#[derive(Debug, Clone)]
+struct TargetReplay {
+    baseline: LatencyBaseline,
+    gap_limit: Duration,
+    previous: Option<DateTime<Local>>,
+    /// Each degradation, with its end and whether latency recovered.
+    degradations: Vec<(LatencyAnomaly, DateTime<Local>, bool)>,
+}
+
+impl TargetReplay {
+    fn new(gap_limit: Duration) -> Self {
+        Self {
+            baseline: LatencyBaseline::new(),
+            gap_limit,
+            previous: None,
+            degradations: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, event: &PingEvent, settings: &LatencyAnomalySettings) {
+        if self
+            .previous
+            .is_some_and(|previous| event.timestamp - previous > self.gap_limit)
+        {
+            self.interrupt();
+        }
+        self.previous = Some(event.timestamp);
+        if event.maintenance || event.resume_grace {
+            return;
+        }
+        let Some(latency_ms) = event.latency_ms.filter(|_| event.success) else {
+            return;
+        };
+        if let Some(AnomalyChange::Ended(anomaly, end)) =
+            self.baseline.observe(event.timestamp, latency_ms, settings)
+        {
+            self.degradations.push((anomaly, end, true));
+        }
+    }
+
+    /// End the degradation in progress without recovery.
+    fn interrupt(&mut self) {
+        if let Some(anomaly) = self.baseline.interrupt() {
+            self.degradations.push((anomaly, anomaly.last, false));
+        }
+    }
+
+    /// Carry on with the replay of the same target's pings from another
+    /// source, which started from an empty baseline.
+    fn follow(&mut self, later: Self) {
+        self.interrupt();
+        self.degradations.extend(later.degradations);
+        self.baseline = later.baseline;
+        self.previous = later.previous.max(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(seconds: i64, latency_ms: f64) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, 0, 0).unwrap() + Duration::seconds(seconds);
+        PingEvent::test("8.8.8.8", at, true).with_latency(latency_ms)
+    }
+
+    #[test]
+    fn slow_periods_are_listed_and_cut_by_data_gaps() {
+        let mut events: Vec<PingEvent> = (0..60).map(|ping| event(ping * 10, 20.0)).collect();
+        // Slow for 5 minutes, then back to normal.
+        events.extend((60..90).map(|ping| event(ping * 10, 80.0)));
+        events.extend((90..120).map(|ping| event(ping * 10, 20.0)));
+        // Slow again until the logs stop for an hour.
+        events.extend((120..150).map(|ping| event(ping * 10, 80.0)));
+        events.extend((0..30).map(|ping| event(5000 + ping * 10, 20.0)));
+
+        let histories = Histories::of(&events);
+        let mut replay = LatencyReplay::new(AnomalyOptions::default(), &histories);
+        for event in &events {
+            replay.record(event);
+        }
+        let degradations = replay.degradations(&histories);
+        assert_eq!(degradations.len(), 2);
+        assert!(degradations[0].recovered);
+        assert_eq!(degradations[0].duration(), Duration::minutes(5));
+        assert_eq!(degradations[0].pings, 30);
+        assert!((degradations[0].peak_ms - 80.0).abs() < 1e-9);
+        assert!(!degradations[1].recovered);
+        assert_eq!(degradations[1].end, event(1490, 80.0).timestamp);
+    }
+}
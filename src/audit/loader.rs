@@ -0,0 +1,489 @@
+use crate::audit::Accumulator;
+use crate::audit::EventFilter;
+use crate::audit::FilterVerdict;
+use crate::audit::Histories;
+use crate::audit::LogFile;
+use crate::audit::LogLine;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use crate::audit::TimeRange;
+use crate::audit::discover_log_files;
+use crate::audit::parse_log_line;
+use chrono::Duration;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::TimeZone;
+use eyre::Context;
+use eyre::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::num::NonZero;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// What was read to produce a report.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceSummary {
+    /// The logs directories and zip archives that were read.
+    pub sources: Vec<PathBuf>,
+    /// The ping database that was queried instead of log files.
+    pub database: Option<PathBuf>,
+    pub files_found: usize,
+    /// Files whose lifetime may overlap the time range, and so were read.
+    pub files_read: usize,
+    /// Files read from the audit index instead of being parsed again.
+    pub files_indexed: usize,
+    /// Ping events kept by the filter.
+    pub events: usize,
+    pub excluded_maintenance: usize,
+    pub excluded_resume_grace: usize,
+    /// Lines that could not be read, such as lines damaged by a crash.
+    pub malformed_lines: usize,
+    /// Unfinished last lines, cut off by a crash or still being written.
+    pub truncated_lines: usize,
+    /// The machines whose pings were kept.
+    pub machines: Vec<String>,
+}
+
+impl SourceSummary {
+    /// Returns true if there was anything to read: a log file or a database.
+    #[must_use]
+    pub fn found_any(&self) -> bool {
+        self.files_found > 0 || self.database.is_some()
+    }
+
+    /// Add the file, exclusion and damaged line counts of another summary.
+    pub fn add_counts(&mut self, other: &SourceSummary) {
+        self.files_found += other.files_found;
+        self.files_read += other.files_read;
+        self.files_indexed += other.files_indexed;
+        self.excluded_maintenance += other.excluded_maintenance;
+        self.excluded_resume_grace += other.excluded_resume_grace;
+        self.malformed_lines += other.malformed_lines;
+        self.truncated_lines += other.truncated_lines;
+    }
+}
+
+/// What a view gathered from the ping events of logs directories, with the
+/// history of every target and a summary of the reading.
+#[derive(Debug, Clone, Default)]
+pub struct Loaded<A> {
+    pub view: A,
+    pub histories: Histories,
+    /// The filter the events were selected with.
+    pub filter: EventFilter,
+    pub source: SourceSummary,
+}
+
+impl<A: Accumulator> Loaded<A> {
+    /// An empty load that gathers into `view` the events selected by `filter`.
+    pub(crate) fn new(view: A, filter: EventFilter) -> Self {
+        Self {
+            view,
+            histories: Histories::default(),
+            filter,
+            source: SourceSummary::default(),
+        }
+    }
+
+    /// An empty load with the same view settings and filter, for a worker
+    /// to read into.
+    pub(crate) fn part(&self, view: &A) -> Self {
+        Self::new(view.clone(), self.filter.clone())
+    }
+
+    /// Parse the lines of one log file, keeping the events selected by the
+    /// filter and counting the machines named along the way.
+    pub(crate) fn read_log(
+        &mut self,
+        mut reader: impl BufRead,
+        machines: &mut HashMap<String, usize>,
+    ) -> std::io::Result<()> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let event = match std::str::from_utf8(&line).map_or(LogLine::Malformed, parse_log_line)
+            {
+                LogLine::Ping(event) => event,
+                LogLine::Other => continue,
+                LogLine::Malformed if line.ends_with(b"\n") => {
+                    self.source.malformed_lines += 1;
+                    continue;
+                }
+                LogLine::Malformed => {
+                    self.source.truncated_lines += 1;
+                    continue;
+                }
+            };
+            if let Some(machine) = &event.target.machine {
+                *machines.entry(machine.clone()).or_default() += 1;
+            }
+            match self.filter.check(&event) {
+                FilterVerdict::Keep => self.keep(event),
+                FilterVerdict::OutOfScope => {}
+                FilterVerdict::Maintenance => self.source.excluded_maintenance += 1,
+                FilterVerdict::ResumeGrace => self.source.excluded_resume_grace += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// Gather an event selected by the filter.
+    pub(crate) fn keep(&mut self, event: PingEvent) {
+        if let Some(machine) = &event.target.machine
+            && !self.source.machines.contains(machine)
+        {
+            self.source.machines.push(machine.clone());
+        }
+        self.histories.record(&event);
+        self.view.record_owned(event);
+        self.source.events += 1;
+    }
+
+    /// Credit the events that name no machine to `fallback`.
+    pub(crate) fn credit_machine(&mut self, fallback: &str) {
+        let mut credited = false;
+        let mut rekey = |target: &mut TargetKey| {
+            if target.machine.is_none() {
+                target.machine = Some(fallback.to_string());
+                credited = true;
+            }
+        };
+        self.view.retarget(&mut rekey);
+        self.histories.retarget(&mut rekey);
+        if credited
+            && !self
+                .source
+                .machines
+                .iter()
+                .any(|machine| machine == fallback)
+        {
+            self.source.machines.push(fallback.to_string());
+        }
+    }
+
+    /// Sort the machines of the kept events, dropping the machine tags when
+    /// only one remains so that reports on a single machine are unchanged.
+    pub(crate) fn settle_machines(&mut self) {
+        self.source.machines.sort();
+        self.source.machines.dedup();
+        if self.source.machines.len() <= 1 {
+            let clear = |target: &mut TargetKey| target.machine = None;
+            self.view.retarget(clear);
+            self.histories.retarget(clear);
+        }
+    }
+
+    /// Add what another worker read from later logs.
+    pub(crate) fn merge(&mut self, other: Self) {
+        self.view.merge(other.view);
+        self.histories.merge(other.histories);
+        self.source.add_counts(&other.source);
+        self.source.events += other.source.events;
+        for machine in other.source.machines {
+            if !self.source.machines.contains(&machine) {
+                self.source.machines.push(machine);
+            }
+        }
+    }
+}
+
+/// Add the machine counts of another worker.
+fn add_machines(machines: &mut HashMap<String, usize>, other: HashMap<String, usize>) {
+    for (machine, count) in other {
+        *machines.entry(machine).or_default() += count;
+    }
+}
+
+/// Run `work` on every item, spreading the items over one worker thread per
+/// core, and return the results in the order of the items.
+///
+/// # Panics
+/// If `work` panics
+pub(crate) fn parallel_map<T: Sync, R: Send>(items: &[T], work: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, NonZero::get)
+        .min(items.len());
+    if workers <= 1 {
+        return items.iter().map(work).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = std::iter::repeat_with(|| None).take(items.len()).collect();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+                        done.push((index, work(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (index, result) in handle.join().expect("log worker panicked") {
+                results[index] = Some(result);
+            }
+        }
+    });
+    results.into_iter().flatten().collect()
+}
+
+/// Whether the path names a zip archive of log files rather than a directory.
+pub(crate) fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Read the ping events selected by the filter from every log file in the
+/// given logs directories and zip archives into `view`, with each log file
+/// gathered on a worker thread. A missing directory yields no events.
+///
+/// Every event is tagged with the machine that logged it. Events logged
+/// before machines were recorded take the machine named most often in the
+/// same directory or archive, or else the directory path or archive name.
+/// When only one machine remains the tags are dropped, so that reports on a
+/// single machine's logs are unchanged.
+///
+/// # Errors
+/// Returns an error if a directory, archive or log file cannot be read
+pub fn load_into<A: Accumulator>(
+    sources: &[PathBuf],
+    filter: &EventFilter,
+    view: A,
+) -> Result<Loaded<A>> {
+    // Every source and worker starts from an empty copy of the view.
+    let empty = view.clone();
+    let mut loaded = Loaded::new(view, filter.clone());
+    loaded.source.sources = sources.to_vec();
+    for source in sources {
+        let mut part = loaded.part(&empty);
+        let mut machines = HashMap::new();
+        if is_archive(source) {
+            read_archive(source, &mut part, &mut machines)?;
+        } else {
+            read_directory(source, &mut part, &empty, &mut machines)?;
+        }
+        part.credit_machine(&fallback_machine(machines, source));
+        loaded.merge(part);
+    }
+
+    loaded.settle_machines();
+    Ok(loaded)
+}
+
+/// Read the ping events selected by the filter from one logs directory or
+/// zip archive a log file at a time, handing each file's events to `each`
+/// in the order the files were written, so that no more than one file is
+/// held in memory. Events keep the machine they name, if any. Returns how
+/// many events named each machine, to find the machine to credit the others
+/// to with [`fallback_machine`].
+///
+/// # Errors
+/// Returns an error if the source or a log file cannot be read, or `each`
+/// fails
+pub fn load_by_file<A: Accumulator>(
+    source: &Path,
+    filter: &EventFilter,
+    view: &A,
+    mut each: impl FnMut(Loaded<A>) -> Result<()>,
+) -> Result<HashMap<String, usize>> {
+    let mut machines = HashMap::new();
+    let mut read = |log_file: &LogFile, reader: Box<dyn BufRead + '_>| -> Result<()> {
+        let mut part = Loaded::new(view.clone(), filter.clone());
+        part.source.files_found = 1;
+        part.source.files_read = 1;
+        part.read_log(reader, &mut machines)
+            .wrap_err_with(|| format!("Failed to read log file: {}", log_file.path.display()))?;
+        each(part)
+    };
+    if is_archive(source) {
+        read_archived_logs(source, &filter.range, read)?;
+    } else if source.exists() {
+        for log_file in discover_log_files(source)? {
+            if !log_file.may_overlap(&filter.range) {
+                continue;
+            }
+            let reader = log_file.open().wrap_err_with(|| {
+                format!("Failed to open log file: {}", log_file.path.display())
+            })?;
+            read(&log_file, reader)?;
+        }
+    }
+    Ok(machines)
+}
+
+/// The machine to credit with events of a source that do not name one: the
+/// machine the source names most often, or else the source itself.
+pub(crate) fn fallback_machine(machines: HashMap<String, usize>, source: &Path) -> String {
+    machines
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map_or_else(|| source_label(source), |(machine, _)| machine)
+}
+
+/// The name of a directory or zip archive of logs, as credited with the
+/// events of logs that name no machine.
+pub(crate) fn source_label(source: &Path) -> String {
+    if is_archive(source)
+        && let Some(stem) = source.file_stem()
+    {
+        return stem.to_string_lossy().into_owned();
+    }
+    source.display().to_string()
+}
+
+fn read_directory<A: Accumulator>(
+    logs_dir: &Path,
+    loaded: &mut Loaded<A>,
+    view: &A,
+    machines: &mut HashMap<String, usize>,
+) -> Result<()> {
+    if !logs_dir.exists() {
+        return Ok(());
+    }
+    // Skip whole files whose lifetime (launch time from the file name to
+    // last write) lies outside the requested window.
+    let log_files = discover_log_files(logs_dir)?;
+    loaded.source.files_found += log_files.len();
+    let log_files: Vec<_> = log_files
+        .into_iter()
+        .filter(|log_file| log_file.may_overlap(&loaded.filter.range))
+        .collect();
+    loaded.source.files_read += log_files.len();
+
+    if A::ORDERED {
+        for log_file in &log_files {
+            let reader = log_file.open().wrap_err_with(|| {
+                format!("Failed to open log file: {}", log_file.path.display())
+            })?;
+            loaded.read_log(reader, machines).wrap_err_with(|| {
+                format!("Failed to read log file: {}", log_file.path.display())
+            })?;
+        }
+        return Ok(());
+    }
+
+    // Each worker folds whole files into its own accumulators; merging
+    // them in file order keeps the events in the order they were logged.
+    let parts = parallel_map(&log_files, |log_file| -> Result<_> {
+        let mut part = loaded.part(view);
+        let mut part_machines = HashMap::new();
+        let reader = log_file
+            .open()
+            .wrap_err_with(|| format!("Failed to open log file: {}", log_file.path.display()))?;
+        part.read_log(reader, &mut part_machines)
+            .wrap_err_with(|| format!("Failed to read log file: {}", log_file.path.display()))?;
+        Ok((part, part_machines))
+    });
+    for part in parts {
+        let (part, part_machines) = part?;
+        loaded.merge(part);
+        add_machines(machines, part_machines);
+    }
+    Ok(())
+}
+
+/// Read the ping events selected by the filter from a zip archive of log
+/// files, counting the machines named along the way.
+pub(crate) fn read_archive<A: Accumulator>(
+    path: &Path,
+    loaded: &mut Loaded<A>,
+    machines: &mut HashMap<String, usize>,
+) -> Result<()> {
+    let range = loaded.filter.range;
+    loaded.source.files_found += read_archived_logs(path, &range, |log_file, reader| {
+        loaded.source.files_read += 1;
+        loaded.read_log(reader, machines).wrap_err_with(|| {
+            format!(
+                "Failed to read {} in archive: {}",
+                log_file.path.display(),
+                path.display()
+            )
+        })
+    })?;
+    Ok(())
+}
+
+/// Hand the log files of a zip archive whose lifetime may overlap `range`
+/// to `read`, one at a time and by file name, and therefore by start time,
+/// as in a directory. Returns how many log files the archive holds.
+fn read_archived_logs(
+    path: &Path,
+    range: &TimeRange,
+    mut read: impl FnMut(&LogFile, Box<dyn BufRead + '_>) -> Result<()>,
+) -> Result<usize> {
+    let file =
+        File::open(path).wrap_err_with(|| format!("Failed to open archive: {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .wrap_err_with(|| format!("Failed to read archive: {}", path.display()))?;
+    // Archives list their entries in any order.
+    let mut log_files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .wrap_err_with(|| format!("Failed to read archive: {}", path.display()))?;
+        if !entry.is_file() {
+            continue;
+        }
+        // Zip entries carry MS-DOS timestamps: local time rounded down to
+        // two seconds, so allow for the rounding when bounding the file.
+        let modified = entry.last_modified().and_then(|modified| {
+            let date = NaiveDate::from_ymd_opt(
+                modified.year().into(),
+                modified.month().into(),
+                modified.day().into(),
+            )?;
+            let naive = date.and_hms_opt(
+                modified.hour().into(),
+                modified.minute().into(),
+                modified.second().into(),
+            )?;
+            Local
+                .from_local_datetime(&naive)
+                .latest()
+                .map(|modified| modified + Duration::seconds(2))
+        });
+        if let Some(log_file) = LogFile::new(PathBuf::from(entry.name()), modified) {
+            log_files.push((index, log_file));
+        }
+    }
+    log_files.sort_by(|(_, a), (_, b)| {
+        a.path
+            .file_name()
+            .cmp(&b.path.file_name())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let found = log_files.len();
+    for (index, log_file) in log_files {
+        if !log_file.may_overlap(range) {
+            continue;
+        }
+        let entry = archive
+            .by_index(index)
+            .wrap_err_with(|| format!("Failed to read archive: {}", path.display()))?;
+        let reader = log_file.compression.decode(entry).wrap_err_with(|| {
+            format!(
+                "Failed to read {} in archive: {}",
+                log_file.path.display(),
+                path.display()
+            )
+        })?;
+        read(&log_file, reader)?;
+    }
+    Ok(found)
+}
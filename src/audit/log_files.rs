@@ -0,0 +1,250 @@
+use crate::audit::TimeRange;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use eyre::Context;
+use eyre::Result;
+use flate2::read::MultiGzDecoder;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// How a log file is compressed, going by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    /// `.gz`, as left by gzip or log rotation.
+    Gzip,
+    /// `.zst`, as left by zstd.
+    Zstd,
+}
+
+impl Compression {
+    /// Wrap a reader of the file's bytes into a reader of its lines.
+    ///
+    /// # Errors
+    /// Returns an error if the decompressor cannot be set up
+    pub fn decode<'a>(self, reader: impl Read + 'a) -> std::io::Result<Box<dyn BufRead + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(BufReader::new(reader)),
+            Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+            Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(reader)?)),
+        })
+    }
+}
+
+/// A log file discovered in a logs directory, with the span of time it may cover.
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    pub path: PathBuf,
+    /// When the writing process started, taken from a `piing_<timestamp>` file name.
+    pub started: Option<DateTime<Local>>,
+    /// When the file was last written to.
+    pub modified: Option<DateTime<Local>>,
+    pub compression: Compression,
+}
+
+impl LogFile {
+    /// Describe the log file at `path`, or return `None` when its name is not
+    /// that of an ndjson log, plain or compressed.
+    #[must_use]
+    pub fn new(path: PathBuf, modified: Option<DateTime<Local>>) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let (name, compression) = if let Some(name) = name.strip_suffix(".gz") {
+            (name, Compression::Gzip)
+        } else if let Some(name) = name.strip_suffix(".zst") {
+            (name, Compression::Zstd)
+        } else {
+            (name, Compression::None)
+        };
+        let ext = Path::new(name).extension()?;
+        if ext != "ndjson" && ext != "log" {
+            return None;
+        }
+        Some(Self {
+            started: parse_started(&path),
+            modified,
+            compression,
+            path,
+        })
+    }
+
+    /// Open the file to read its lines.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened
+    pub fn open(&self) -> std::io::Result<Box<dyn BufRead>> {
+        self.open_at(0)
+    }
+
+    /// Open the file to read its lines from byte `offset` of its content.
+    /// Compressed files are decompressed up to the offset and skipped over.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or read up to the offset
+    pub fn open_at(&self, offset: u64) -> std::io::Result<Box<dyn BufRead>> {
+        let mut file = File::open(&self.path)?;
+        if self.compression == Compression::None {
+            file.seek(SeekFrom::Start(offset))?;
+            return self.compression.decode(file);
+        }
+        let mut reader = self.compression.decode(file)?;
+        std::io::copy(&mut (&mut reader).take(offset), &mut std::io::sink())?;
+        Ok(reader)
+    }
+
+    /// Returns true unless the file provably holds no events inside the window.
+    #[must_use]
+    pub fn may_overlap(&self, range: &TimeRange) -> bool {
+        range.overlaps(self.started, self.modified)
+    }
+}
+
+/// Find the ndjson log files in a directory and its subdirectories, such as
+/// the dated directories older logs are archived into, sorted by file name
+/// (and therefore by start time).
+///
+/// A compressed file whose plain original is still there is left out: log
+/// rotation removes the original only after the compressed copy is in
+/// place, so the pair is what an interrupted rotation leaves behind.
+///
+/// # Errors
+/// Returns an error if the directory or one of its subdirectories cannot be read
+pub fn discover_log_files(dir: &Path) -> Result<Vec<LogFile>> {
+    let mut log_files = Vec::new();
+    collect_log_files(dir, &mut log_files)?;
+    let plain: HashSet<PathBuf> = log_files
+        .iter()
+        .filter(|log_file| log_file.compression == Compression::None)
+        .map(|log_file| log_file.path.clone())
+        .collect();
+    log_files.retain(|log_file| {
+        log_file.compression == Compression::None
+            || !plain.contains(&log_file.path.with_extension(""))
+    });
+    log_files.sort_by(|a, b| {
+        a.path
+            .file_name()
+            .cmp(&b.path.file_name())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(log_files)
+}
+
+fn collect_log_files(dir: &Path, log_files: &mut Vec<LogFile>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .wrap_err_with(|| format!("Failed to read logs directory: {}", dir.display()))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_log_files(&path, log_files)?;
+            continue;
+        }
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified().ok().map(DateTime::<Local>::from);
+        log_files.extend(LogFile::new(path, modified));
+    }
+    Ok(())
+}
+
+/// Parse the launch time out of names like `piing_2025-12-01_08-30-00.log.ndjson`.
+fn parse_started(path: &Path) -> Option<DateTime<Local>> {
+    let name = path.file_name()?.to_str()?;
+    let stamp = name.strip_prefix("piing_")?.split('.').next()?;
+    let naive = NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d_%H-%M-%S").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_time_comes_from_file_name() {
+        assert_eq!(
+            parse_started(Path::new("logs/piing_2025-12-01_08-30-00.log.ndjson")),
+            Local.with_ymd_and_hms(2025, 12, 1, 8, 30, 0).earliest()
+        );
+        assert_eq!(parse_started(Path::new("logs/custom.ndjson")), None);
+    }
+
+    #[test]
+    fn compressed_logs_read_like_plain_ones() {
+        use std::io::Write;
+
+        let lines = "{\"line\":1}\n{\"line\":2}\n";
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(lines.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(lines.as_bytes(), 0).unwrap();
+
+        for (name, compression, bytes) in [
+            (
+                "piing_2025-12-01_08-30-00.log.ndjson",
+                Compression::None,
+                lines.as_bytes(),
+            ),
+            (
+                "piing_2025-12-01_08-30-00.log.ndjson.gz",
+                Compression::Gzip,
+                &gzip[..],
+            ),
+            (
+                "piing_2025-12-01_08-30-00.log.ndjson.zst",
+                Compression::Zstd,
+                &zstd[..],
+            ),
+        ] {
+            let log_file = LogFile::new(PathBuf::from("logs/2025-12").join(name), None).unwrap();
+            assert_eq!(log_file.compression, compression);
+            assert!(log_file.started.is_some());
+            let mut read = String::new();
+            compression
+                .decode(bytes)
+                .unwrap()
+                .read_to_string(&mut read)
+                .unwrap();
+            assert_eq!(read, lines);
+        }
+        assert!(LogFile::new(PathBuf::from("logs/piing.ndjson.tmp"), None).is_none());
+    }
+
+    #[test]
+    fn interrupted_compression_is_read_once() {
+        let dir = std::env::temp_dir().join(format!("piing_log_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "piing_2025-12-01_08-30-00.log.ndjson",
+            "piing_2025-12-01_08-30-00.log.ndjson.gz",
+            "piing_2025-12-02_08-30-00.log.ndjson.gz",
+        ] {
+            File::create(dir.join(name)).unwrap();
+        }
+        let log_files = discover_log_files(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = log_files
+            .iter()
+            .map(|log_file| log_file.path.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "piing_2025-12-01_08-30-00.log.ndjson",
+                "piing_2025-12-02_08-30-00.log.ndjson.gz"
+            ]
+        );
+    }
+}
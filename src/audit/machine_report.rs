@@ -0,0 +1,236 @@
+use crate::audit::Cell;
+use crate::audit::CorrelatedIncident;
+use crate::audit::CorrelationOptions;
+use crate::audit::Histories;
+use crate::audit::LatencyStats;
+use crate::audit::LatencySummary;
+use crate::audit::ReportBody;
+use crate::audit::Table;
+use crate::audit::TargetKey;
+use crate::audit::TargetSummaries;
+use crate::audit::correlate_failures;
+use crate::audit::format_duration;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Label of the machine when the logs of a single machine are audited.
+const SINGLE_MACHINE: &str = "this machine";
+
+/// Pings, failures and latency of one machine across all its targets.
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineSummary {
+    pub machine: String,
+    pub targets: usize,
+    pub events: usize,
+    pub failures: usize,
+    pub failure_rate: Option<f64>,
+    pub latency: LatencySummary,
+}
+
+/// One target as probed from one machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineTarget {
+    pub machine: String,
+    pub events: usize,
+    pub failure_rate: f64,
+    pub p50_ms: Option<f64>,
+}
+
+/// One target as probed from each machine that probed it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetAcrossMachines {
+    /// A label such as `google_dns (icmp 8.8.8.8)`.
+    pub target: String,
+    pub machines: Vec<MachineTarget>,
+}
+
+/// The `audit machines` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineReport {
+    pub machines: Vec<MachineSummary>,
+    pub targets: Vec<TargetAcrossMachines>,
+    /// Correlated incidents during which a target failed on every machine.
+    pub shared_incidents: Vec<CorrelatedIncident>,
+}
+
+impl MachineReport {
+    #[must_use]
+    pub fn build(
+        summaries: &TargetSummaries,
+        histories: &Histories,
+        options: &CorrelationOptions,
+    ) -> Self {
+        let mut machines: BTreeMap<String, (MachineSummary, LatencyStats)> = BTreeMap::new();
+        let mut targets: BTreeMap<String, Vec<MachineTarget>> = BTreeMap::new();
+        for summary in summaries.iter() {
+            let machine = summary
+                .target
+                .machine
+                .clone()
+                .unwrap_or_else(|| SINGLE_MACHINE.to_string());
+            let (totals, latency) = machines.entry(machine.clone()).or_insert_with(|| {
+                (
+                    MachineSummary {
+                        machine: machine.clone(),
+                        targets: 0,
+                        events: 0,
+                        failures: 0,
+                        failure_rate: None,
+                        latency: LatencySummary::default(),
+                    },
+                    LatencyStats::default(),
+                )
+            });
+            totals.targets += 1;
+            totals.events += summary.events;
+            totals.failures += summary.failures;
+            latency.merge(&summary.latency);
+
+            let key = TargetKey {
+                machine: None,
+                ..summary.target.clone()
+            };
+            let label = match &summary.name {
+                Some(name) => format!("{name} ({key})"),
+                None => key.to_string(),
+            };
+            targets.entry(label).or_default().push(MachineTarget {
+                machine,
+                events: summary.events,
+                failure_rate: summary.failure_rate(),
+                p50_ms: summary.latency.percentile_ms(50.0),
+            });
+        }
+        let machines = machines
+            .into_values()
+            .map(|(mut totals, latency)| {
+                #[expect(
+                    clippy::cast_precision_loss,
+                    reason = "event counts stay far below 2^52"
+                )]
+                let failure_rate = (totals.events > 0)
+                    .then(|| totals.failures as f64 / totals.events as f64 * 100.0);
+                totals.failure_rate = failure_rate;
+                totals.latency = latency.summary();
+                totals
+            })
+            .collect();
+
+        let shared_incidents = correlate_failures(histories, options)
+            .into_iter()
+            .filter(CorrelatedIncident::hit_every_machine)
+            .collect();
+        Self {
+            machines,
+            targets: targets
+                .into_iter()
+                .map(|(target, machines)| TargetAcrossMachines { target, machines })
+                .collect(),
+            shared_incidents,
+        }
+    }
+}
+
+/// The targets of an incident with the machine left out of their labels, so
+/// that a target failing on every machine is listed once.
+fn without_machines(incident: &CorrelatedIncident) -> Vec<String> {
+    let mut targets: Vec<String> = incident
+        .targets
+        .iter()
+        .map(|target| {
+            incident
+                .machines
+                .iter()
+                .fold(target.clone(), |target, machine| {
+                    target.replace(&format!(" @ {machine}"), "")
+                })
+        })
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+impl ReportBody for MachineReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut machines = Table::new(
+            "Machines",
+            &[
+                "Machine",
+                "Targets",
+                "Events",
+                "Failures",
+                "Failure rate",
+                "p50 ms",
+                "p95 ms",
+            ],
+        );
+        for summary in &self.machines {
+            machines.push_row(vec![
+                summary.machine.as_str().into(),
+                summary.targets.into(),
+                summary.events.into(),
+                summary.failures.into(),
+                summary.failure_rate.map_or(Cell::Missing, Cell::Percent),
+                summary.latency.p50_ms.into(),
+                summary.latency.p95_ms.into(),
+            ]);
+        }
+        if self.machines.len() < 2 {
+            machines.push_note(
+                "Only one machine's logs were read; pass --logs-dir once per machine to compare several.",
+            );
+            return vec![machines];
+        }
+
+        let names: Vec<&str> = self
+            .machines
+            .iter()
+            .map(|summary| summary.machine.as_str())
+            .collect();
+        let mut headers = vec!["Target"];
+        headers.extend(&names);
+        let mut failures = Table::new("Failure Rate by Machine", &headers);
+        let mut latency = Table::new("Median Latency by Machine", &headers);
+        for target in &self.targets {
+            let on = |name: &str| target.machines.iter().find(|entry| entry.machine == name);
+            let mut failure_row: Vec<Cell> = vec![target.target.as_str().into()];
+            let mut latency_row: Vec<Cell> = vec![target.target.as_str().into()];
+            for name in &names {
+                let entry = on(name);
+                failure_row
+                    .push(entry.map_or(Cell::Missing, |entry| Cell::Percent(entry.failure_rate)));
+                latency_row.push(entry.and_then(|entry| entry.p50_ms).into());
+            }
+            failures.push_row(failure_row);
+            latency.push_row(latency_row);
+        }
+        latency.push_note("Latencies are in milliseconds.");
+
+        let mut shared = Table::new(
+            "Incidents on Every Machine",
+            &["Start", "Duration", "Machines", "Targets", "Failed probes"],
+        );
+        for incident in &self.shared_incidents {
+            let duration: Cell = if incident.recovered {
+                incident.duration().into()
+            } else {
+                format!("{} (not recovered)", format_duration(incident.duration())).into()
+            };
+            shared.push_row(vec![
+                Cell::Time(incident.start),
+                duration,
+                incident.machines.len().into(),
+                without_machines(incident).join(", ").into(),
+                incident.failed_probes.into(),
+            ]);
+        }
+        shared.push_note(
+            "Periods during which a target failed on every machine with data at the same time, which points at an outage shared by the whole site rather than one computer.",
+        );
+        if self.shared_incidents.is_empty() {
+            shared.push_note("No failures hit every machine at once.");
+        }
+        vec![machines, failures, latency, shared]
+    }
+}
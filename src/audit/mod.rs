@@ -0,0 +1,63 @@
+mod accumulator;
+mod anomaly_report;
+mod audit_index;
+mod compare_report;
+mod comparison;
+mod correlated_failures;
+mod correlated_report;
+mod error_categories;
+mod error_report;
+mod event;
+mod filter;
+mod gaps;
+mod history;
+mod hourly;
+mod html_report;
+mod incident_report;
+mod incidents;
+mod latency;
+mod latency_anomalies;
+mod loader;
+mod log_files;
+mod machine_report;
+mod overview;
+mod period;
+mod report;
+mod sla;
+mod sla_report;
+mod target_summary;
+mod time_range;
+mod vpn_correlation;
+mod vpn_report;
+
+pub use accumulator::*;
+pub use anomaly_report::*;
+pub use audit_index::*;
+pub use compare_report::*;
+pub use comparison::*;
+pub use correlated_failures::*;
+pub use correlated_report::*;
+pub use error_categories::*;
+pub use error_report::*;
+pub use event::*;
+pub use filter::*;
+pub use gaps::*;
+pub use history::*;
+pub use hourly::*;
+pub use html_report::*;
+pub use incident_report::*;
+pub use incidents::*;
+pub use latency::*;
+pub use latency_anomalies::*;
+pub use loader::*;
+pub use log_files::*;
+pub use machine_report::*;
+pub use overview::*;
+pub use period::*;
+pub use report::*;
+pub use sla::*;
+pub use sla_report::*;
+pub use target_summary::*;
+pub use time_range::*;
+pub use vpn_correlation::*;
+pub use vpn_report::*;
@@ -0,0 +1,398 @@
+use crate::audit::Cell;
+use crate::audit::HourlyTally;
+use crate::audit::LatencyStats;
+use crate::audit::LatencySummary;
+use crate::audit::PingEvent;
+use crate::audit::ReportBody;
+use crate::audit::Table;
+use crate::audit::TargetKey;
+use crate::audit::summarise_hours;
+use crate::audit::tally_hours;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::Local;
+use chrono::Timelike;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Failures among the events falling in one hour of day or day of week.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureBucket {
+    pub label: String,
+    pub failures: usize,
+    pub events: usize,
+    /// Failure rate as a percentage of events, or `None` when there is no
+    /// data for the bucket, which is not the same as no failures.
+    pub failure_rate: Option<f64>,
+}
+
+/// Events, failures and latency of one target.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetOverview {
+    /// A label such as `google_dns (icmp 8.8.8.8)`.
+    pub label: String,
+    pub name: Option<String>,
+    pub host: String,
+    pub mode: String,
+    /// The machine that probed the target, when several machines are audited.
+    pub machine: Option<String>,
+    pub events: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub latency: LatencySummary,
+}
+
+/// Latency of the pings made to one target during one hour of day.
+#[derive(Debug, Clone, Serialize)]
+pub struct HourLatency {
+    /// The label of the target, as in [`TargetOverview::label`].
+    pub label: String,
+    pub hour: u32,
+    pub latency: LatencySummary,
+}
+
+/// Successful pings whose latency fell in one histogram bucket.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    pub label: String,
+    pub count: u64,
+    /// Share of successful pings, as a percentage.
+    pub share: f64,
+}
+
+/// The default audit view: per-target breakdown, failure and latency
+/// distributions.
+#[derive(Debug, Clone, Serialize)]
+pub struct OverviewReport {
+    pub targets: Vec<TargetOverview>,
+    pub failures_by_hour: Vec<FailureBucket>,
+    pub failures_by_weekday: Vec<FailureBucket>,
+    /// Latency per target and hour of day, leaving out hours without a
+    /// successful ping.
+    pub latency_by_hour: Vec<HourLatency>,
+    pub latency_distribution: Vec<LatencyBucket>,
+    /// Failures by hour over the 24 hours before the report was built; only
+    /// present when no explicit time window was requested.
+    pub last_24_hours: Option<Vec<FailureBucket>>,
+}
+
+impl OverviewReport {
+    /// Build the overview of the events. Passing `now` adds the last 24 hours
+    /// section.
+    #[must_use]
+    pub fn build(events: &[PingEvent], now: Option<DateTime<Local>>) -> Self {
+        let cuts: Vec<DateTime<Local>> = now.map(last_day_start).into_iter().collect();
+        Self::from_hours(&tally_hours(events, &cuts), now)
+    }
+
+    /// Build the overview from hourly tallies. Passing `now` adds the last 24
+    /// hours section, which is exact only when the tallies were split at
+    /// [`last_day_start`].
+    #[must_use]
+    pub fn from_hours(hours: &[HourlyTally], now: Option<DateTime<Local>>) -> Self {
+        let summaries = summarise_hours(hours);
+        let targets = summaries
+            .iter()
+            .map(|summary| TargetOverview {
+                label: summary.label(),
+                name: summary.name.clone(),
+                host: summary.target.host.clone(),
+                mode: summary.target.mode.clone(),
+                machine: summary.target.machine.clone(),
+                events: summary.events,
+                failures: summary.failures,
+                failure_rate: summary.failure_rate(),
+                latency: summary.latency.summary(),
+            })
+            .collect();
+
+        let hour_labels: Vec<String> = (0..24).map(|hour: u32| hour.to_string()).collect();
+        let failures_by_hour = failure_buckets(hours.iter(), &hour_labels, |hour| {
+            hour.start.hour() as usize
+        });
+        let day_labels: Vec<String> = DAY_NAMES.iter().map(ToString::to_string).collect();
+        let failures_by_weekday = failure_buckets(hours.iter(), &day_labels, |hour| {
+            hour.start.weekday().num_days_from_monday() as usize
+        });
+
+        let mut by_target_hour: BTreeMap<(&TargetKey, u32), LatencyStats> = BTreeMap::new();
+        for hour in hours {
+            by_target_hour
+                .entry((&hour.target, hour.start.hour()))
+                .or_default()
+                .merge(&hour.tally.latency);
+        }
+        let latency_by_hour = summaries
+            .iter()
+            .flat_map(|summary| {
+                let label = summary.label();
+                by_target_hour
+                    .range((&summary.target, 0)..=(&summary.target, 23))
+                    .filter(|(_, stats)| stats.count() > 0)
+                    .map(move |(&(_, hour), stats)| HourLatency {
+                        label: label.clone(),
+                        hour,
+                        latency: stats.summary(),
+                    })
+            })
+            .collect();
+
+        let mut combined = LatencyStats::default();
+        for summary in &summaries {
+            combined.merge(&summary.latency);
+        }
+        let latency_distribution = combined
+            .bucket_counts()
+            .into_iter()
+            .map(|(label, count)| {
+                #[expect(
+                    clippy::cast_precision_loss,
+                    reason = "ping counts stay far below 2^52"
+                )]
+                let share = (count as f64 / combined.count().max(1) as f64) * 100.0;
+                LatencyBucket {
+                    label,
+                    count,
+                    share,
+                }
+            })
+            .collect();
+
+        let last_24_hours = now.map(|now| {
+            let day_ago = last_day_start(now);
+            failure_buckets(
+                hours.iter().filter(|hour| hour.start >= day_ago),
+                &hour_labels,
+                |hour| hour.start.hour() as usize,
+            )
+        });
+
+        Self {
+            targets,
+            failures_by_hour,
+            failures_by_weekday,
+            latency_by_hour,
+            latency_distribution,
+            last_24_hours,
+        }
+    }
+}
+
+/// The start of the last 24 hours section of a report built at `now`.
+#[must_use]
+pub fn last_day_start(now: DateTime<Local>) -> DateTime<Local> {
+    now - Duration::hours(24)
+}
+
+fn failure_buckets<'a>(
+    hours: impl Iterator<Item = &'a HourlyTally>,
+    labels: &[String],
+    bucket: impl Fn(&HourlyTally) -> usize,
+) -> Vec<FailureBucket> {
+    let mut counts = vec![(0usize, 0usize); labels.len()];
+    for hour in hours {
+        let (failures, total) = &mut counts[bucket(hour)];
+        *total += hour.tally.events;
+        *failures += hour.tally.failures;
+    }
+    labels
+        .iter()
+        .zip(counts)
+        .map(|(label, (failures, events))| {
+            #[expect(
+                clippy::cast_precision_loss,
+                reason = "event counts stay far below 2^52"
+            )]
+            let failure_rate = (events > 0).then(|| (failures as f64 / events as f64) * 100.0);
+            FailureBucket {
+                label: label.clone(),
+                failures,
+                events,
+                failure_rate,
+            }
+        })
+        .collect()
+}
+
+fn failure_table(title: &str, label: &str, buckets: &[FailureBucket], suffix: &str) -> Table {
+    let mut table = Table::new(title, &[label, "Failures", "Total", "Failure Rate"]).with_bar(1);
+    for bucket in buckets {
+        table.push_row(vec![
+            bucket.label.as_str().into(),
+            bucket.failures.into(),
+            bucket.events.into(),
+            bucket.failure_rate.map_or(Cell::Missing, Cell::Percent),
+        ]);
+    }
+    let failures: usize = buckets.iter().map(|bucket| bucket.failures).sum();
+    let events: usize = buckets.iter().map(|bucket| bucket.events).sum();
+    table.push_note(format!("Total failures{suffix}: {failures}"));
+    table.push_note(format!("Total events{suffix}: {events}"));
+    table
+}
+
+fn latency_row(label: String, latency: &LatencySummary) -> Vec<Cell> {
+    vec![
+        label.into(),
+        latency.count.into(),
+        latency.p50_ms.into(),
+        latency.p90_ms.into(),
+        latency.p95_ms.into(),
+        latency.p99_ms.into(),
+        latency.max_ms.into(),
+    ]
+}
+
+const LATENCY_COLUMNS: [&str; 6] = ["Count", "p50 ms", "p90 ms", "p95 ms", "p99 ms", "Max ms"];
+
+impl ReportBody for OverviewReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut tables = Vec::new();
+
+        if !self.targets.is_empty() {
+            let mut breakdown = Table::new(
+                "Per-Target Breakdown",
+                &[
+                    "Target",
+                    "Events",
+                    "Failures",
+                    "Failure Rate",
+                    "Min ms",
+                    "Avg ms",
+                    "Max ms",
+                ],
+            );
+            for target in &self.targets {
+                breakdown.push_row(vec![
+                    target.label.as_str().into(),
+                    target.events.into(),
+                    target.failures.into(),
+                    Cell::Percent(target.failure_rate),
+                    target.latency.min_ms.into(),
+                    target.latency.mean_ms.map_or(Cell::Missing, Cell::Number),
+                    target.latency.max_ms.into(),
+                ]);
+            }
+            tables.push(breakdown);
+        }
+
+        tables.push(failure_table(
+            "Failure Distribution by Hour of Day",
+            "Hour",
+            &self.failures_by_hour,
+            "",
+        ));
+        tables.push(failure_table(
+            "Failure Distribution by Day of Week",
+            "Day",
+            &self.failures_by_weekday,
+            "",
+        ));
+
+        if self.targets.iter().any(|target| target.latency.count > 0) {
+            let mut by_target = Table::new(
+                "Latency Percentiles by Target",
+                &[&["Target"][..], &LATENCY_COLUMNS].concat(),
+            );
+            for target in &self.targets {
+                by_target.push_row(latency_row(target.label.clone(), &target.latency));
+            }
+            tables.push(by_target);
+
+            let mut by_hour = Table::new(
+                "Latency Percentiles by Target and Hour of Day",
+                &[&["Target", "Hour"][..], &LATENCY_COLUMNS].concat(),
+            );
+            for hour in &self.latency_by_hour {
+                let mut row = latency_row(hour.label.clone(), &hour.latency);
+                row.insert(1, hour.hour.to_string().into());
+                by_hour.push_row(row);
+            }
+            tables.push(by_hour);
+
+            let mut distribution = Table::new(
+                "Latency Distribution (all selected targets)",
+                &["Latency", "Count", "Share"],
+            )
+            .with_bar(1);
+            for bucket in &self.latency_distribution {
+                distribution.push_row(vec![
+                    bucket.label.as_str().into(),
+                    bucket.count.into(),
+                    Cell::Percent(bucket.share),
+                ]);
+            }
+            tables.push(distribution);
+        }
+
+        if let Some(last_24_hours) = &self.last_24_hours {
+            const TITLE: &str = "Failure Distribution by Hour of Day (Last 24 Hours)";
+            if last_24_hours.iter().all(|bucket| bucket.events == 0) {
+                let mut empty = Table::new(TITLE, &[]);
+                empty.push_note("No events found in the last 24 hours.");
+                tables.push(empty);
+            } else {
+                tables.push(failure_table(TITLE, "Hour", last_24_hours, " (last 24h)"));
+            }
+        }
+        tables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(hour: u32, success: bool) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, hour, 0, 0).unwrap();
+        PingEvent::test("8.8.8.8", at, success).named("google_dns")
+    }
+
+    #[test]
+    fn buckets_failures_by_hour_and_weekday() {
+        let events = [event(8, true), event(8, false), event(9, false)];
+        let report = OverviewReport::build(&events, None);
+        assert_eq!(report.failures_by_hour[8].failures, 1);
+        assert_eq!(report.failures_by_hour[8].events, 2);
+        assert!((report.failures_by_hour[8].failure_rate.unwrap() - 50.0).abs() < f64::EPSILON);
+        assert!(report.failures_by_hour[10].failure_rate.is_none());
+        // 2025-12-01 was a Monday.
+        assert_eq!(report.failures_by_weekday[0].failures, 2);
+        assert_eq!(report.targets[0].label, "google_dns (icmp 8.8.8.8)");
+        assert!(report.last_24_hours.is_none());
+    }
+
+    #[test]
+    fn latency_by_hour_is_kept_per_target() {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, 0, 0).unwrap();
+        let slow = PingEvent::test("1.1.1.1", at, true)
+            .named("slow")
+            .with_latency(400.0);
+        let events = [event(8, true), event(8, true), slow, event(9, false)];
+        let report = OverviewReport::build(&events, None);
+        let rows: Vec<(&str, u32, Option<f64>)> = report
+            .latency_by_hour
+            .iter()
+            .map(|hour| (hour.label.as_str(), hour.hour, hour.latency.p50_ms))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("slow (icmp 1.1.1.1)", 8, Some(400.0)),
+                ("google_dns (icmp 8.8.8.8)", 8, Some(10.0)),
+            ]
+        );
+    }
+}
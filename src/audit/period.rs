@@ -0,0 +1,32 @@
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use clap::ValueEnum;
+
+/// The calendar periods a report can be broken down by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportPeriod {
+    Day,
+    Week,
+    Month,
+    /// One period covering the whole audited range.
+    #[default]
+    All,
+}
+
+impl ReportPeriod {
+    /// A sortable label for the period containing the timestamp, such as
+    /// `2025-12-01`, `2025-W49` or `2025-12`.
+    #[must_use]
+    pub fn label(self, timestamp: DateTime<Local>) -> String {
+        match self {
+            ReportPeriod::Day => timestamp.format("%Y-%m-%d").to_string(),
+            ReportPeriod::Week => {
+                let week = timestamp.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            ReportPeriod::Month => timestamp.format("%Y-%m").to_string(),
+            ReportPeriod::All => "all".to_string(),
+        }
+    }
+}
@@ -0,0 +1,637 @@
+use crate::audit::CoverageReport;
+use crate::audit::EventFilter;
+use crate::audit::SourceSummary;
+use chrono::DateTime;
+use chrono::Local;
+use clap::ValueEnum;
+use eyre::Result;
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt::Write;
+
+/// Width of the bars drawn in text histograms.
+const BAR_WIDTH: u64 = 50;
+
+/// How an audit report is written out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Aligned tables with histogram bars, for reading in a terminal.
+    #[default]
+    Text,
+    /// The full report model as pretty-printed JSON.
+    Json,
+    /// Every table as CSV rows, the first column naming the table.
+    Csv,
+    /// GitHub-flavoured Markdown tables, for pasting into tickets.
+    Markdown,
+}
+
+/// A single value in a report table.
+#[derive(Debug, Clone)]
+pub enum Cell {
+    Text(String),
+    Count(u64),
+    /// A number shown with one decimal.
+    Number(f64),
+    /// A change shown with one decimal and an explicit sign.
+    Delta(f64),
+    /// A percentage (0-100) shown with two decimals.
+    Percent(f64),
+    /// A percentage shown with three decimals, so that availabilities such
+    /// as 99.95% and 99.99% can be told apart.
+    FinePercent(f64),
+    Duration(chrono::Duration),
+    Time(DateTime<Local>),
+    Missing,
+}
+
+impl Cell {
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Cell::Text(_) | Cell::Time(_))
+    }
+
+    fn count(&self) -> u64 {
+        match self {
+            Cell::Count(count) => *count,
+            _ => 0,
+        }
+    }
+
+    /// The value as shown in text and Markdown tables.
+    fn display(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Count(count) => count.to_string(),
+            Cell::Number(value) => format!("{value:.1}"),
+            Cell::Delta(value) => format!("{value:+.1}"),
+            Cell::Percent(value) => format!("{value:.2}%"),
+            Cell::FinePercent(value) => format!("{value:.3}%"),
+            Cell::Duration(duration) => format_duration(*duration),
+            Cell::Time(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Cell::Missing => "-".to_string(),
+        }
+    }
+
+    /// The value as written to CSV: plain numbers, seconds and RFC 3339 times.
+    fn csv(&self) -> String {
+        match self {
+            Cell::Text(text) => csv_escape(text),
+            Cell::Count(count) => count.to_string(),
+            Cell::Number(value) | Cell::Delta(value) => format!("{value:.1}"),
+            Cell::Percent(value) => format!("{value:.2}"),
+            Cell::FinePercent(value) => format!("{value:.3}"),
+            Cell::Duration(duration) => duration.num_seconds().to_string(),
+            Cell::Time(time) => time.to_rfc3339(),
+            Cell::Missing => String::new(),
+        }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<u64> for Cell {
+    fn from(value: u64) -> Self {
+        Cell::Count(value)
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(value: usize) -> Self {
+        Cell::Count(value as u64)
+    }
+}
+
+impl From<Option<u64>> for Cell {
+    fn from(value: Option<u64>) -> Self {
+        value.map_or(Cell::Missing, Cell::Count)
+    }
+}
+
+impl From<Option<f64>> for Cell {
+    fn from(value: Option<f64>) -> Self {
+        value.map_or(Cell::Missing, Cell::Number)
+    }
+}
+
+impl From<chrono::Duration> for Cell {
+    fn from(value: chrono::Duration) -> Self {
+        Cell::Duration(value)
+    }
+}
+
+impl From<Option<chrono::Duration>> for Cell {
+    fn from(value: Option<chrono::Duration>) -> Self {
+        value.map_or(Cell::Missing, Cell::Duration)
+    }
+}
+
+/// A titled table, the unit every output format is rendered from.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub title: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+    /// A count column drawn as a bar in text output.
+    pub bar_column: Option<usize>,
+    /// Lines shown under the table in text and Markdown output.
+    pub notes: Vec<String>,
+}
+
+impl Table {
+    #[must_use]
+    pub fn new(title: impl Into<String>, columns: &[&str]) -> Self {
+        Self {
+            title: title.into(),
+            columns: columns.iter().map(ToString::to_string).collect(),
+            rows: Vec::new(),
+            bar_column: None,
+            notes: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_bar(mut self, column: usize) -> Self {
+        self.bar_column = Some(column);
+        self
+    }
+
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    pub fn push_note(&mut self, note: impl Into<String>) {
+        self.notes.push(note.into());
+    }
+
+    fn render_text(&self, out: &mut String) {
+        let _ = writeln!(out, "{}:\n", self.title);
+        if !self.rows.is_empty() {
+            let cells: Vec<Vec<String>> = self
+                .rows
+                .iter()
+                .map(|row| row.iter().map(Cell::display).collect())
+                .collect();
+            let widths: Vec<usize> = self
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    cells
+                        .iter()
+                        .filter_map(|row| row.get(index))
+                        .map(|cell| cell.chars().count())
+                        .chain([column.chars().count()])
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let bar_max = self.bar_column.map(|column| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(Cell::count)
+                    .max()
+                    .unwrap_or_default()
+            });
+
+            let mut header: Vec<String> = self
+                .columns
+                .iter()
+                .zip(&widths)
+                .map(|(column, width)| format!("{column:width$}"))
+                .collect();
+            let mut rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            if bar_max.is_some() {
+                header.push("Bar".to_string());
+                // The separator already pads the bar column by one dash.
+                rule.push("-".repeat(49));
+            }
+            let _ = writeln!(out, "{}", header.join(" | ").trim_end());
+            let _ = writeln!(out, "{}", rule.join("-|-"));
+
+            for (row, texts) in self.rows.iter().zip(&cells) {
+                let mut line: Vec<String> = row
+                    .iter()
+                    .zip(texts)
+                    .zip(&widths)
+                    .map(|((cell, text), width)| {
+                        if cell.is_numeric() {
+                            format!("{text:>width$}")
+                        } else {
+                            format!("{text:width$}")
+                        }
+                    })
+                    .collect();
+                if let (Some(column), Some(max)) = (self.bar_column, bar_max) {
+                    let value = row.get(column).map(Cell::count).unwrap_or_default();
+                    let length = (value * BAR_WIDTH).checked_div(max).unwrap_or_default();
+                    line.push("█".repeat(usize::try_from(length).unwrap_or_default()));
+                }
+                let _ = writeln!(out, "{}", line.join(" | ").trim_end());
+            }
+            if !self.notes.is_empty() {
+                out.push('\n');
+            }
+        }
+        for note in &self.notes {
+            let _ = writeln!(out, "{note}");
+        }
+        out.push_str("\n\n");
+    }
+
+    fn render_markdown(&self, out: &mut String) {
+        let _ = writeln!(out, "### {}\n", self.title);
+        if !self.rows.is_empty() {
+            let _ = writeln!(
+                out,
+                "| {} |",
+                self.columns
+                    .iter()
+                    .map(|column| markdown_escape(column))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            );
+            let alignments: Vec<&str> = (0..self.columns.len())
+                .map(|index| {
+                    let numeric = self
+                        .rows
+                        .iter()
+                        .filter_map(|row| row.get(index))
+                        .any(Cell::is_numeric);
+                    if numeric { "---:" } else { "---" }
+                })
+                .collect();
+            let _ = writeln!(out, "| {} |", alignments.join(" | "));
+            for row in &self.rows {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|cell| markdown_escape(&cell.display()))
+                    .collect();
+                let _ = writeln!(out, "| {} |", cells.join(" | "));
+            }
+            out.push('\n');
+        }
+        for note in &self.notes {
+            let _ = writeln!(out, "{}\n", markdown_escape(note));
+        }
+    }
+
+    /// Write the table as an HTML `<table>` under an `<h3>` heading.
+    pub(crate) fn render_html(&self, out: &mut String) {
+        let _ = writeln!(out, "<h3>{}</h3>", html_escape(&self.title));
+        if !self.rows.is_empty() {
+            out.push_str("<table>\n<tr>");
+            for column in &self.columns {
+                let _ = write!(out, "<th>{}</th>", html_escape(column));
+            }
+            out.push_str("</tr>\n");
+            for row in &self.rows {
+                out.push_str("<tr>");
+                for cell in row {
+                    let class = if cell.is_numeric() {
+                        " class=\"num\""
+                    } else {
+                        ""
+                    };
+                    let _ = write!(out, "<td{class}>{}</td>", html_escape(&cell.display()));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        }
+        for note in &self.notes {
+            let _ = writeln!(out, "<p>{}</p>", html_escape(note));
+        }
+    }
+
+    fn render_csv(&self, out: &mut String) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let header: Vec<String> = std::iter::once("table")
+            .chain(self.columns.iter().map(String::as_str))
+            .map(csv_escape)
+            .collect();
+        let _ = writeln!(out, "{}", header.join(","));
+        let title = csv_escape(&self.title);
+        for row in &self.rows {
+            let cells: Vec<String> = std::iter::once(title.clone())
+                .chain(row.iter().map(Cell::csv))
+                .collect();
+            let _ = writeln!(out, "{}", cells.join(","));
+        }
+        out.push('\n');
+    }
+}
+
+/// The part of a report specific to one audit view.
+pub trait ReportBody: Serialize {
+    /// The tables that text, CSV and Markdown output are rendered from.
+    fn tables(&self) -> Vec<Table>;
+}
+
+/// An audit report: what was read and how it was filtered, plus the view's data.
+///
+/// The JSON output is this structure as is; the other formats are rendered
+/// from [`ReportBody::tables`], followed by the monitoring coverage.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport<T> {
+    pub generated_at: DateTime<Local>,
+    pub filter: EventFilter,
+    pub source: SourceSummary,
+    pub coverage: CoverageReport,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T: ReportBody> AuditReport<T> {
+    #[must_use]
+    pub fn new(
+        filter: &EventFilter,
+        source: SourceSummary,
+        coverage: CoverageReport,
+        body: T,
+    ) -> Self {
+        Self {
+            generated_at: Local::now(),
+            filter: filter.clone(),
+            source,
+            coverage,
+            body,
+        }
+    }
+
+    /// The view's tables followed by the coverage tables.
+    pub(crate) fn tables(&self) -> Vec<Table> {
+        let mut tables = self.body.tables();
+        tables.extend(self.coverage.tables());
+        tables
+    }
+
+    /// Render the report in the given format.
+    ///
+    /// # Errors
+    /// Returns an error if the report cannot be serialised to JSON
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        let mut out = String::new();
+        match format {
+            ReportFormat::Json => {
+                out = serde_json::to_string_pretty(self)?;
+                out.push('\n');
+            }
+            ReportFormat::Csv => {
+                for table in self.tables() {
+                    table.render_csv(&mut out);
+                }
+            }
+            ReportFormat::Text => {
+                self.render_text_header(&mut out);
+                if self.source.found_any() {
+                    for table in self.tables() {
+                        table.render_text(&mut out);
+                    }
+                }
+            }
+            ReportFormat::Markdown => {
+                self.render_markdown_header(&mut out);
+                if self.source.found_any() {
+                    for table in self.tables() {
+                        table.render_markdown(&mut out);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn render_text_header(&self, out: &mut String) {
+        let source = &self.source;
+        if let Some(database) = &source.database {
+            let _ = writeln!(out, "Queried ping database {}\n", database.display());
+        } else if source.files_found == 0 {
+            let sources: Vec<String> = source
+                .sources
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            let _ = writeln!(out, "No log files found in: {}", sources.join(", "));
+            return;
+        } else if self.filter.range.is_unbounded() {
+            let _ = writeln!(out, "Found {} log file(s)\n", source.files_found);
+        } else {
+            let _ = writeln!(
+                out,
+                "Found {} log file(s), {} of which may cover {}\n",
+                source.files_found, source.files_read, self.filter.range
+            );
+        }
+        if source.files_indexed > 0 {
+            let _ = writeln!(
+                out,
+                "Read {} unchanged log file(s) from the audit index\n",
+                source.files_indexed
+            );
+        }
+        if source.machines.len() > 1 {
+            let _ = writeln!(
+                out,
+                "Merged the logs of {} machines: {}\n",
+                source.machines.len(),
+                source.machines.join(", ")
+            );
+        }
+        if self.filter.tags.is_empty() {
+            let _ = writeln!(out, "Parsed {} ping events\n", source.events);
+        } else {
+            let _ = writeln!(
+                out,
+                "Parsed {} ping events tagged with {}\n",
+                source.events,
+                self.filter.tags.join(", ")
+            );
+        }
+        if source.malformed_lines > 0 || source.truncated_lines > 0 {
+            let _ = writeln!(
+                out,
+                "Skipped {} malformed and {} truncated log line(s)\n",
+                source.malformed_lines, source.truncated_lines
+            );
+        }
+        if source.excluded_maintenance > 0 {
+            let _ = writeln!(
+                out,
+                "Excluded {} ping events recorded during maintenance windows (use --include-maintenance to count them)\n",
+                source.excluded_maintenance
+            );
+        }
+        if source.excluded_resume_grace > 0 {
+            let _ = writeln!(
+                out,
+                "Excluded {} failures logged while the system was resuming from sleep (use --include-resume-grace to count them)\n",
+                source.excluded_resume_grace
+            );
+        }
+    }
+
+    fn render_markdown_header(&self, out: &mut String) {
+        let source = &self.source;
+        let _ = writeln!(out, "## Piing audit\n");
+        let _ = writeln!(
+            out,
+            "- Generated: {}",
+            self.generated_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        let _ = writeln!(out, "- Range: {}", self.filter.range);
+        for (label, values) in [
+            ("Tags", &self.filter.tags),
+            ("Targets", &self.filter.targets),
+            ("Hosts", &self.filter.hosts),
+        ] {
+            if !values.is_empty() {
+                let _ = writeln!(out, "- {label}: {}", markdown_escape(&values.join(", ")));
+            }
+        }
+        if let Some(database) = &source.database {
+            let _ = writeln!(
+                out,
+                "- Ping database: {}",
+                markdown_escape(&database.display().to_string())
+            );
+        } else {
+            let _ = write!(
+                out,
+                "- Log files: {} found, {} read",
+                source.files_found, source.files_read
+            );
+            if source.files_indexed > 0 {
+                let _ = write!(out, ", {} from the audit index", source.files_indexed);
+            }
+            out.push('\n');
+        }
+        if source.machines.len() > 1 {
+            let _ = writeln!(
+                out,
+                "- Machines: {}",
+                markdown_escape(&source.machines.join(", "))
+            );
+        }
+        let _ = writeln!(out, "- Ping events: {}", source.events);
+        if source.malformed_lines > 0 || source.truncated_lines > 0 {
+            let _ = writeln!(
+                out,
+                "- Skipped lines: {} malformed, {} truncated",
+                source.malformed_lines, source.truncated_lines
+            );
+        }
+        if source.excluded_maintenance > 0 || source.excluded_resume_grace > 0 {
+            let _ = writeln!(
+                out,
+                "- Excluded: {} during maintenance, {} during resume grace",
+                source.excluded_maintenance, source.excluded_resume_grace
+            );
+        }
+        out.push('\n');
+    }
+}
+
+/// Format a duration to whole seconds, such as `1h 2m 5s`.
+#[must_use]
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = u64::try_from(duration.num_seconds()).unwrap_or_default();
+    humantime::format_duration(std::time::Duration::from_secs(seconds)).to_string()
+}
+
+/// Serialise a duration as whole seconds.
+///
+/// # Errors
+/// Returns the serializer's error
+pub fn serialize_seconds<S: Serializer>(
+    duration: &chrono::Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(duration.num_seconds())
+}
+
+/// Serialise an optional duration as whole seconds.
+///
+/// # Errors
+/// Returns the serializer's error
+pub fn serialize_optional_seconds<S: Serializer>(
+    duration: &Option<chrono::Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.num_seconds()),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn markdown_escape(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+/// Escape text for use in HTML and SVG content or attribute values.
+#[must_use]
+pub fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::new("Failures", &["Target", "Failures", "Rate"]).with_bar(1);
+        table.push_row(vec!["a, b".into(), 4usize.into(), Cell::Percent(12.5)]);
+        table.push_row(vec!["c|d".into(), 2usize.into(), Cell::Missing]);
+        table
+    }
+
+    #[test]
+    fn csv_rows_are_escaped_and_prefixed_with_the_table() {
+        let mut out = String::new();
+        table().render_csv(&mut out);
+        assert_eq!(
+            out,
+            "table,Target,Failures,Rate\nFailures,\"a, b\",4,12.50\nFailures,c|d,2,\n\n"
+        );
+    }
+
+    #[test]
+    fn markdown_tables_escape_pipes_and_align_numbers() {
+        let mut out = String::new();
+        table().render_markdown(&mut out);
+        assert!(out.contains("| Target | Failures | Rate |\n| --- | ---: | ---: |\n"));
+        assert!(out.contains("| c\\|d | 2 | - |"));
+    }
+
+    #[test]
+    fn text_bars_scale_to_the_largest_count() {
+        let mut out = String::new();
+        table().render_text(&mut out);
+        assert!(out.contains(&format!("a, b   |        4 | 12.50% | {}", "█".repeat(50))));
+        assert!(out.contains(&format!("c|d    |        2 |      - | {}", "█".repeat(25))));
+    }
+}
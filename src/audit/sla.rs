@@ -0,0 +1,364 @@
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::IncidentGrouping;
+use crate::audit::ReportPeriod;
+use crate::audit::TargetHistory;
+use crate::audit::serialize_seconds;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// How availability weighs the pings it is computed from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AvailabilityMethod {
+    /// Each outcome lasts until the next ping; availability is uptime over observed time.
+    #[default]
+    Time,
+    /// Every ping counts once; availability is successful pings over all pings.
+    Probe,
+}
+
+/// How periods without pings count towards availability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingData {
+    /// Leave them out, as neither up nor down.
+    #[default]
+    Unknown,
+    /// Count them as downtime.
+    Down,
+}
+
+/// Settings for computing availability.
+#[derive(Debug, Clone)]
+pub struct SlaOptions {
+    pub grouping: IncidentGrouping,
+    pub period: ReportPeriod,
+    pub method: AvailabilityMethod,
+    pub missing: MissingData,
+    /// Silences longer than this many times a target's usual interval are missing data.
+    pub gap_factor: i32,
+    /// Availability objectives keyed by lower-case target name.
+    pub objectives: HashMap<String, f64>,
+    /// Objective for groups whose targets have none of their own.
+    pub default_objective: Option<f64>,
+}
+
+impl Default for SlaOptions {
+    fn default() -> Self {
+        Self {
+            grouping: IncidentGrouping::default(),
+            period: ReportPeriod::default(),
+            method: AvailabilityMethod::default(),
+            missing: MissingData::default(),
+            gap_factor: DEFAULT_GAP_FACTOR,
+            objectives: HashMap::new(),
+            default_objective: None,
+        }
+    }
+}
+
+/// Availability of one group over one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct Availability {
+    pub group: String,
+    pub period: String,
+    pub probes: usize,
+    pub failed_probes: usize,
+    /// Pings that would have been made during gaps without data.
+    pub missing_probes: usize,
+    #[serde(rename = "up_seconds", serialize_with = "serialize_seconds")]
+    pub up: Duration,
+    #[serde(rename = "down_seconds", serialize_with = "serialize_seconds")]
+    pub down: Duration,
+    #[serde(rename = "missing_seconds", serialize_with = "serialize_seconds")]
+    pub missing: Duration,
+    /// Time counted as down: failures, plus missing data when it counts as down.
+    #[serde(rename = "downtime_seconds", serialize_with = "serialize_seconds")]
+    pub downtime: Duration,
+    /// Availability as a percentage, or `None` when nothing was counted.
+    pub availability: Option<f64>,
+    /// `-log10` of the unavailability, such as 3 for 99.9%; `None` at 100%.
+    pub nines: Option<f64>,
+    pub objective: Option<f64>,
+    pub meets_objective: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Up,
+    Down,
+    Missing,
+}
+
+/// A stretch of time during which one target was in one state.
+type Span = (DateTime<Local>, DateTime<Local>, State);
+
+#[derive(Debug, Default)]
+struct Tally {
+    probes: usize,
+    failed_probes: usize,
+    missing_probes: usize,
+    up: Duration,
+    down: Duration,
+    missing: Duration,
+}
+
+impl Tally {
+    fn add(&mut self, state: State, duration: Duration) {
+        match state {
+            State::Up => self.up += duration,
+            State::Down => self.down += duration,
+            State::Missing => self.missing += duration,
+        }
+    }
+}
+
+/// Split one target's history into spans of up, down and missing time,
+/// returning the spans and the number of pings missing per gap start.
+fn target_spans(
+    history: &TargetHistory,
+    gap_factor: i32,
+) -> (Vec<Span>, Vec<(DateTime<Local>, usize)>) {
+    let interval = history.usual_interval();
+    let limit = interval * gap_factor;
+    let mut spans = Vec::new();
+    let mut missing = Vec::new();
+    for (index, run) in history.runs.iter().enumerate() {
+        let state = if run.success { State::Up } else { State::Down };
+        if run.last > run.first {
+            spans.push((run.first, run.last, state));
+        }
+        let Some(next) = history.runs.get(index + 1) else {
+            break;
+        };
+        let gap = next.first - run.last;
+        if gap <= limit {
+            spans.push((run.last, next.first, state));
+        } else {
+            let covered = run.last + interval;
+            spans.push((run.last, covered, state));
+            spans.push((covered, next.first, State::Missing));
+            let expected = gap.num_milliseconds() / interval.num_milliseconds().max(1);
+            missing.push((run.last, usize::try_from(expected - 1).unwrap_or_default()));
+        }
+    }
+    (spans, missing)
+}
+
+/// Compute availability per group and period. Spans of time are credited to
+/// the period they start in. A group is down while any of its targets is
+/// down, and missing only while none of its targets has data.
+#[must_use]
+pub fn compute_availability(histories: &Histories, options: &SlaOptions) -> Vec<Availability> {
+    let mut results = Vec::new();
+    for (group, members) in histories.groups(options.grouping) {
+        let mut tallies: BTreeMap<String, Tally> = BTreeMap::new();
+        let mut objective: Option<f64> = None;
+        let mut all_spans = Vec::new();
+        for history in members {
+            for run in &history.runs {
+                let tally = tallies.entry(options.period.label(run.first)).or_default();
+                tally.probes += run.pings;
+                if !run.success {
+                    tally.failed_probes += run.pings;
+                }
+            }
+            if let Some(target_objective) = history
+                .name
+                .as_ref()
+                .and_then(|name| options.objectives.get(&name.to_lowercase()))
+            {
+                objective = Some(objective.map_or(*target_objective, |o| o.max(*target_objective)));
+            }
+
+            let (spans, missing) = target_spans(history, options.gap_factor);
+            for (start, count) in missing {
+                tallies
+                    .entry(options.period.label(start))
+                    .or_default()
+                    .missing_probes += count;
+            }
+            all_spans.push(spans);
+        }
+
+        // Sweep over every span boundary, combining the targets' states.
+        let mut boundaries: Vec<DateTime<Local>> = all_spans
+            .iter()
+            .flatten()
+            .flat_map(|(start, end, _)| [*start, *end])
+            .collect();
+        boundaries.sort();
+        boundaries.dedup();
+        let mut cursors = vec![0usize; all_spans.len()];
+        for pair in boundaries.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let mut combined: Option<State> = None;
+            for (spans, cursor) in all_spans.iter().zip(cursors.iter_mut()) {
+                while spans.get(*cursor).is_some_and(|(_, end, _)| *end <= from) {
+                    *cursor += 1;
+                }
+                if let Some((start, _, state)) = spans.get(*cursor)
+                    && *start <= from
+                {
+                    combined = Some(match (combined, *state) {
+                        (Some(State::Down), _) | (_, State::Down) => State::Down,
+                        (Some(State::Up), _) | (_, State::Up) => State::Up,
+                        _ => State::Missing,
+                    });
+                }
+            }
+            if let Some(state) = combined {
+                tallies
+                    .entry(options.period.label(from))
+                    .or_default()
+                    .add(state, to - from);
+            }
+        }
+
+        let objective = objective.or(options.default_objective);
+        for (period, tally) in tallies {
+            results.push(availability(
+                group.clone(),
+                period,
+                &tally,
+                options,
+                objective,
+            ));
+        }
+    }
+    results
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "ping counts and millisecond spans stay far below 2^52"
+)]
+fn availability(
+    group: String,
+    period: String,
+    tally: &Tally,
+    options: &SlaOptions,
+    objective: Option<f64>,
+) -> Availability {
+    let missing_is_down = options.missing == MissingData::Down;
+    let downtime = if missing_is_down {
+        tally.down + tally.missing
+    } else {
+        tally.down
+    };
+    let (good, counted) = match options.method {
+        AvailabilityMethod::Time => {
+            let counted = tally.up + downtime;
+            (
+                tally.up.num_milliseconds() as f64,
+                counted.num_milliseconds() as f64,
+            )
+        }
+        AvailabilityMethod::Probe => {
+            let missing = if missing_is_down {
+                tally.missing_probes
+            } else {
+                0
+            };
+            (
+                (tally.probes - tally.failed_probes) as f64,
+                (tally.probes + missing) as f64,
+            )
+        }
+    };
+    let availability = (counted > 0.0).then(|| good / counted * 100.0);
+    let nines = availability
+        .filter(|availability| *availability < 100.0)
+        .map(|availability| -(1.0 - availability / 100.0).log10());
+    Availability {
+        group,
+        period,
+        probes: tally.probes,
+        failed_probes: tally.failed_probes,
+        missing_probes: tally.missing_probes,
+        up: tally.up,
+        down: tally.down,
+        missing: tally.missing,
+        downtime,
+        availability,
+        nines,
+        objective,
+        meets_objective: objective
+            .zip(availability)
+            .map(|(objective, availability)| availability >= objective),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::PingEvent;
+    use chrono::TimeZone;
+
+    fn event(host: &str, minute: u32, success: bool) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, minute, 0).unwrap();
+        PingEvent::test(host, at, success).named(host)
+    }
+
+    #[test]
+    fn time_and_probe_weighting_differ_around_gaps() {
+        // One ping a minute, a failure at minute 2 and no data from 4 to 10.
+        let events: Vec<PingEvent> = [0, 1, 2, 3, 4, 10, 11]
+            .into_iter()
+            .map(|minute| event("a", minute, minute != 2))
+            .collect();
+        let options = SlaOptions {
+            objectives: HashMap::from([("a".to_string(), 99.0)]),
+            ..SlaOptions::default()
+        };
+        let histories = Histories::of(&events);
+        let time = &compute_availability(&histories, &options)[0];
+        assert_eq!(time.down, Duration::minutes(1));
+        assert_eq!(time.missing, Duration::minutes(5));
+        assert_eq!(time.missing_probes, 5);
+        // Up 5 minutes (0-2, 3-5, 10-11) out of 6 counted.
+        assert!((time.availability.unwrap() - 500.0 / 6.0).abs() < 1e-9);
+        assert_eq!(time.meets_objective, Some(false));
+
+        let probe = &compute_availability(
+            &histories,
+            &SlaOptions {
+                method: AvailabilityMethod::Probe,
+                missing: MissingData::Down,
+                ..options
+            },
+        )[0];
+        // 6 of 7 pings succeeded, plus 5 missing pings counted as down.
+        assert!((probe.availability.unwrap() - 600.0 / 12.0).abs() < 1e-9);
+        assert_eq!(probe.downtime, Duration::minutes(6));
+    }
+
+    #[test]
+    fn groups_are_down_while_any_target_is_down() {
+        let events = [
+            event("a", 0, true),
+            event("b", 0, false),
+            event("a", 1, true),
+            event("b", 1, true),
+            event("a", 2, true),
+            event("b", 2, true),
+        ];
+        let results = compute_availability(
+            &Histories::of(&events),
+            &SlaOptions {
+                grouping: IncidentGrouping::All,
+                ..SlaOptions::default()
+            },
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].down, Duration::minutes(1));
+        assert_eq!(results[0].up, Duration::minutes(1));
+        assert!((results[0].nines.unwrap() - 2f64.log10()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::audit::Availability;
+use crate::audit::AvailabilityMethod;
+use crate::audit::Cell;
+use crate::audit::Histories;
+use crate::audit::MissingData;
+use crate::audit::ReportBody;
+use crate::audit::SlaOptions;
+use crate::audit::Table;
+use crate::audit::compute_availability;
+use serde::Serialize;
+
+/// The `audit sla` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlaReport {
+    pub method: AvailabilityMethod,
+    pub missing: MissingData,
+    pub availability: Vec<Availability>,
+}
+
+impl SlaReport {
+    #[must_use]
+    pub fn build(histories: &Histories, options: &SlaOptions) -> Self {
+        Self {
+            method: options.method,
+            missing: options.missing,
+            availability: compute_availability(histories, options),
+        }
+    }
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "downtime in seconds stays far below 2^52"
+)]
+fn minutes(duration: chrono::Duration) -> Cell {
+    Cell::Number(duration.num_seconds() as f64 / 60.0)
+}
+
+impl ReportBody for SlaReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "Availability",
+            &[
+                "Group",
+                "Period",
+                "Availability",
+                "Nines",
+                "Downtime min",
+                "Missing min",
+                "Probes",
+                "SLO",
+                "SLO met",
+            ],
+        );
+        if self.availability.is_empty() {
+            table.push_note("No ping events to compute availability from.");
+            return vec![table];
+        }
+        for row in &self.availability {
+            let nines = match (row.availability, row.nines) {
+                (Some(_), Some(nines)) => Cell::Number(nines),
+                (Some(_), None) => "∞".into(),
+                (None, _) => Cell::Missing,
+            };
+            let met = row
+                .meets_objective
+                .map_or(Cell::Missing, |met| if met { "yes" } else { "NO" }.into());
+            table.push_row(vec![
+                row.group.as_str().into(),
+                row.period.as_str().into(),
+                row.availability.map_or(Cell::Missing, Cell::FinePercent),
+                nines,
+                minutes(row.downtime),
+                minutes(row.missing),
+                row.probes.into(),
+                row.objective.map_or(Cell::Missing, Cell::Percent),
+                met,
+            ]);
+        }
+        table.push_note(match self.method {
+            AvailabilityMethod::Time => {
+                "Availability is time-weighted: each ping's outcome lasts until the next ping."
+            }
+            AvailabilityMethod::Probe => "Availability is probe-weighted: every ping counts once.",
+        });
+        table.push_note(match self.missing {
+            MissingData::Unknown => {
+                "Periods without data are left out (use --missing down to count them as downtime)."
+            }
+            MissingData::Down => "Periods without data count as downtime.",
+        });
+        vec![table]
+    }
+}
@@ -0,0 +1,142 @@
+use crate::audit::Accumulator;
+use crate::audit::HourlyTally;
+use crate::audit::LatencyStats;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use std::collections::BTreeMap;
+
+/// Event counts and latency for one target.
+#[derive(Debug, Clone)]
+pub struct TargetSummary {
+    pub target: TargetKey,
+    /// The most recently logged name of the target, if any.
+    pub name: Option<String>,
+    pub events: usize,
+    pub failures: usize,
+    pub latency: LatencyStats,
+}
+
+impl TargetSummary {
+    fn new(target: TargetKey) -> Self {
+        Self {
+            target,
+            name: None,
+            events: 0,
+            failures: 0,
+            latency: LatencyStats::default(),
+        }
+    }
+
+    /// Failure rate as a percentage of events.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "event counts stay far below 2^52"
+    )]
+    pub fn failure_rate(&self) -> f64 {
+        if self.events == 0 {
+            0.0
+        } else {
+            (self.failures as f64 / self.events as f64) * 100.0
+        }
+    }
+
+    fn record(&mut self, event: &PingEvent) {
+        self.events += 1;
+        if event.target_name.is_some() {
+            self.name.clone_from(&event.target_name);
+        }
+        if event.success {
+            if let Some(latency_ms) = event.latency_ms {
+                self.latency.record(latency_ms);
+            }
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Add the events of the same target from later logs.
+    fn merge(&mut self, later: &TargetSummary) {
+        self.events += later.events;
+        self.failures += later.failures;
+        self.latency.merge(&later.latency);
+        if later.name.is_some() {
+            self.name.clone_from(&later.name);
+        }
+    }
+
+    /// A label such as `google_dns (icmp 8.8.8.8)`.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({})", self.target),
+            None => self.target.to_string(),
+        }
+    }
+}
+
+/// Event counts and latency of every target, ordered by host and mode.
+#[derive(Debug, Clone, Default)]
+pub struct TargetSummaries {
+    targets: BTreeMap<TargetKey, TargetSummary>,
+}
+
+impl TargetSummaries {
+    pub fn iter(&self) -> impl Iterator<Item = &TargetSummary> {
+        self.targets.values()
+    }
+}
+
+impl Accumulator for TargetSummaries {
+    fn record(&mut self, event: &PingEvent) {
+        if let Some(summary) = self.targets.get_mut(&event.target) {
+            summary.record(event);
+        } else {
+            let mut summary = TargetSummary::new(event.target.clone());
+            summary.record(event);
+            self.targets.insert(event.target.clone(), summary);
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (target, summary) in later.targets {
+            match self.targets.get_mut(&target) {
+                Some(earlier) => earlier.merge(&summary),
+                None => {
+                    self.targets.insert(target, summary);
+                }
+            }
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for (mut target, mut summary) in std::mem::take(&mut self.targets) {
+            rekey(&mut target);
+            summary.target = target.clone();
+            self.merge(Self {
+                targets: BTreeMap::from([(target, summary)]),
+            });
+        }
+    }
+}
+
+/// Summarise hourly tallies per target, ordered by host and mode. The
+/// tallies are expected in time order, as returned by [`tally_hours`].
+///
+/// [`tally_hours`]: crate::audit::tally_hours
+#[must_use]
+pub fn summarise_hours<'a>(hours: impl IntoIterator<Item = &'a HourlyTally>) -> Vec<TargetSummary> {
+    let mut summaries: BTreeMap<&TargetKey, TargetSummary> = BTreeMap::new();
+    for hour in hours {
+        let summary = summaries
+            .entry(&hour.target)
+            .or_insert_with(|| TargetSummary::new(hour.target.clone()));
+        summary.events += hour.tally.events;
+        summary.failures += hour.tally.failures;
+        summary.latency.merge(&hour.tally.latency);
+        if hour.name.is_some() {
+            summary.name.clone_from(&hour.name);
+        }
+    }
+    summaries.into_values().collect()
+}
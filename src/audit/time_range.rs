@@ -0,0 +1,194 @@
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::TimeZone;
+use eyre::Result;
+use serde::Serialize;
+
+/// A window of local time that audit events must fall in. Either bound may be
+/// left open; `since` is inclusive and `until` is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TimeRange {
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+}
+
+impl TimeRange {
+    #[must_use]
+    pub fn new(since: Option<DateTime<Local>>, until: Option<DateTime<Local>>) -> Self {
+        Self { since, until }
+    }
+
+    /// Returns true if neither bound is set.
+    #[must_use]
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// Returns true if the timestamp falls inside the window.
+    #[must_use]
+    pub fn contains(&self, timestamp: DateTime<Local>) -> bool {
+        self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp < until)
+    }
+
+    /// Returns true if the span `[start, end]` shares any instant with the
+    /// window. Unknown ends are treated as unbounded.
+    #[must_use]
+    pub fn overlaps(&self, start: Option<DateTime<Local>>, end: Option<DateTime<Local>>) -> bool {
+        let starts_too_late =
+            matches!((start, self.until), (Some(start), Some(until)) if start >= until);
+        let ends_too_early = matches!((end, self.since), (Some(end), Some(since)) if end < since);
+        !starts_too_late && !ends_too_early
+    }
+
+    /// Returns true if some timestamp falls inside both windows.
+    #[must_use]
+    pub fn intersects(&self, other: &TimeRange) -> bool {
+        let ends_before = |earlier: &TimeRange, later: &TimeRange| {
+            earlier
+                .until
+                .zip(later.since)
+                .is_some_and(|(until, since)| until <= since)
+        };
+        !ends_before(self, other) && !ends_before(other, self)
+    }
+}
+
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+        match (self.since, self.until) {
+            (None, None) => write!(f, "all time"),
+            (Some(since), None) => write!(f, "since {}", since.format(FORMAT)),
+            (None, Some(until)) => write!(f, "until {}", until.format(FORMAT)),
+            (Some(since), Some(until)) => write!(
+                f,
+                "from {} until {}",
+                since.format(FORMAT),
+                until.format(FORMAT)
+            ),
+        }
+    }
+}
+
+/// Parse a time bound given on the command line.
+///
+/// Accepts an RFC 3339 timestamp (`2025-12-01T08:00:00-05:00`), a local date
+/// (`2025-12-01`, meaning local midnight) or a humantime duration (`7d`,
+/// `12h 30m`) measured back from now.
+///
+/// # Errors
+/// Returns an error if the value matches none of the accepted forms
+pub fn parse_time_bound(raw: &str) -> Result<DateTime<Local>> {
+    parse_time_bound_at(raw, Local::now())
+}
+
+fn parse_time_bound_at(raw: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    let raw = raw.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(timestamp.with_timezone(&Local));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return Local
+            .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+            .earliest()
+            .ok_or_else(|| eyre::eyre!("Local midnight does not exist on {date}"));
+    }
+    if let Ok(ago) = humantime::parse_duration(raw) {
+        let ago = chrono::Duration::from_std(ago)
+            .map_err(|_| eyre::eyre!("Duration '{raw}' is too large"))?;
+        return Ok(now - ago);
+    }
+    Err(eyre::eyre!(
+        "Invalid time '{raw}'; expected an RFC 3339 timestamp, a YYYY-MM-DD date or a duration such as '7d'"
+    ))
+}
+
+/// Parse a time range given on the command line as `START..END`, where each
+/// side is a time bound accepted by [`parse_time_bound`] and may be left empty
+/// to keep that end open.
+///
+/// # Errors
+/// Returns an error if the separator is missing, a bound is invalid or the
+/// range is empty
+pub fn parse_time_range(raw: &str) -> Result<TimeRange> {
+    parse_time_range_at(raw, Local::now())
+}
+
+fn parse_time_range_at(raw: &str, now: DateTime<Local>) -> Result<TimeRange> {
+    let Some((since, until)) = raw.split_once("..") else {
+        eyre::bail!("Invalid range '{raw}'; expected START..END, such as '14d..7d'");
+    };
+    let bound = |raw: &str| {
+        let raw = raw.trim();
+        (!raw.is_empty())
+            .then(|| parse_time_bound_at(raw, now))
+            .transpose()
+    };
+    let range = TimeRange::new(bound(since)?, bound(until)?);
+    if let (Some(since), Some(until)) = (range.since, range.until)
+        && since >= until
+    {
+        eyre::bail!("Invalid range '{raw}'; its start must be earlier than its end");
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_accepted_form() {
+        let now = Local.with_ymd_and_hms(2025, 12, 8, 12, 0, 0).unwrap();
+        assert_eq!(
+            parse_time_bound_at("7d", now).unwrap(),
+            Local.with_ymd_and_hms(2025, 12, 1, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time_bound_at("2025-12-01", now).unwrap(),
+            Local.with_ymd_and_hms(2025, 12, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time_bound_at("2025-12-01T00:00:00Z", now).unwrap(),
+            DateTime::parse_from_rfc3339("2025-12-01T00:00:00Z").unwrap()
+        );
+        assert!(parse_time_bound_at("last tuesday", now).is_err());
+    }
+
+    #[test]
+    fn parses_ranges_with_open_ends() {
+        let now = Local.with_ymd_and_hms(2025, 12, 8, 12, 0, 0).unwrap();
+        let range = parse_time_range_at("14d..7d", now).unwrap();
+        assert_eq!(
+            range.since,
+            Some(Local.with_ymd_and_hms(2025, 11, 24, 12, 0, 0).unwrap())
+        );
+        assert_eq!(range.until, Some(now - chrono::Duration::days(7)));
+        let open = parse_time_range_at("2025-12-01..", now).unwrap();
+        assert!(open.since.is_some() && open.until.is_none());
+        assert!(parse_time_range_at("7d..14d", now).is_err());
+        assert!(parse_time_range_at("7d", now).is_err());
+    }
+
+    #[test]
+    fn overlap_treats_unknown_ends_as_open() {
+        let day = |d| Local.with_ymd_and_hms(2025, 12, d, 0, 0, 0).unwrap();
+        let range = TimeRange::new(Some(day(5)), Some(day(10)));
+        assert!(range.overlaps(Some(day(1)), None));
+        assert!(range.overlaps(Some(day(1)), Some(day(6))));
+        assert!(!range.overlaps(Some(day(1)), Some(day(4))));
+        assert!(!range.overlaps(Some(day(10)), None));
+    }
+
+    #[test]
+    fn windows_meeting_end_to_start_do_not_intersect() {
+        let day = |d| Some(Local.with_ymd_and_hms(2025, 12, d, 0, 0, 0).unwrap());
+        let range = TimeRange::new(day(5), day(10));
+        assert!(!range.intersects(&TimeRange::new(day(10), None)));
+        assert!(!range.intersects(&TimeRange::new(None, day(5))));
+        assert!(range.intersects(&TimeRange::new(day(9), day(12))));
+        assert!(range.intersects(&TimeRange::new(None, None)));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::audit::Accumulator;
+use crate::audit::Histories;
+use crate::audit::Incident;
+use crate::audit::LatencyStats;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use chrono::Timelike;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Counts and latency of the pings made in one VPN state.
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeStats {
+    pub events: usize,
+    pub failures: usize,
+    pub latency: LatencyStats,
+}
+
+impl OutcomeStats {
+    pub fn record(&mut self, event: &PingEvent) {
+        self.events += 1;
+        if event.success {
+            if let Some(latency_ms) = event.latency_ms {
+                self.latency.record(latency_ms);
+            }
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Add the counts and latency of other pings.
+    pub fn merge(&mut self, other: &OutcomeStats) {
+        self.events += other.events;
+        self.failures += other.failures;
+        self.latency.merge(&other.latency);
+    }
+
+    /// Failure rate as a percentage of events, or `None` without events.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "event counts stay far below 2^52"
+    )]
+    pub fn failure_rate(&self) -> Option<f64> {
+        (self.events > 0).then(|| (self.failures as f64 / self.events as f64) * 100.0)
+    }
+}
+
+/// Pings split by whether a VPN was active when they were made.
+#[derive(Debug, Clone, Default)]
+pub struct VpnSplit {
+    pub vpn_on: OutcomeStats,
+    pub vpn_off: OutcomeStats,
+}
+
+impl VpnSplit {
+    pub fn record(&mut self, event: &PingEvent) {
+        if event.vpn_active {
+            self.vpn_on.record(event);
+        } else {
+            self.vpn_off.record(event);
+        }
+    }
+
+    /// Add the pings of another split.
+    pub fn merge(&mut self, other: &VpnSplit) {
+        self.vpn_on.merge(&other.vpn_on);
+        self.vpn_off.merge(&other.vpn_off);
+    }
+}
+
+/// Pings split by VPN state per target and per hour of day (0-23).
+#[derive(Debug, Clone, Default)]
+pub struct VpnSplits {
+    targets: BTreeMap<TargetKey, VpnSplit>,
+    hours: [VpnSplit; 24],
+}
+
+impl VpnSplits {
+    /// Every target's split, labelled like incident groups and ordered by label.
+    #[must_use]
+    pub fn by_target(&self, histories: &Histories) -> Vec<(String, &VpnSplit)> {
+        let mut targets: Vec<(String, &VpnSplit)> = self
+            .targets
+            .iter()
+            .map(|(target, split)| (histories.label(target), split))
+            .collect();
+        targets.sort_by(|a, b| a.0.cmp(&b.0));
+        targets
+    }
+
+    /// The split of every hour of day.
+    #[must_use]
+    pub fn by_hour(&self) -> &[VpnSplit] {
+        &self.hours
+    }
+}
+
+impl Accumulator for VpnSplits {
+    fn record(&mut self, event: &PingEvent) {
+        if let Some(split) = self.targets.get_mut(&event.target) {
+            split.record(event);
+        } else {
+            let mut split = VpnSplit::default();
+            split.record(event);
+            self.targets.insert(event.target.clone(), split);
+        }
+        self.hours[event.timestamp.hour() as usize].record(event);
+    }
+
+    fn merge(&mut self, later: Self) {
+        for (target, split) in later.targets {
+            self.targets.entry(target).or_default().merge(&split);
+        }
+        for (hour, split) in self.hours.iter_mut().zip(&later.hours) {
+            hour.merge(split);
+        }
+    }
+
+    fn retarget(&mut self, mut rekey: impl FnMut(&mut TargetKey)) {
+        for (mut target, split) in std::mem::take(&mut self.targets) {
+            rekey(&mut target);
+            self.targets.entry(target).or_default().merge(&split);
+        }
+    }
+}
+
+/// Periods of active VPN of each machine, keyed like
+/// [`TargetKey::machine`](crate::audit::TargetKey::machine).
+pub type VpnSpans = BTreeMap<Option<String>, Vec<(DateTime<Local>, DateTime<Local>)>>;
+
+/// The first and last ping of every run of each machine with its VPN state,
+/// in chronological order. Every machine has its own VPN, so their states
+/// are only compared within a machine; within a run the state holds.
+fn states_by_machine(
+    histories: &Histories,
+) -> BTreeMap<Option<String>, Vec<(DateTime<Local>, bool)>> {
+    let mut machines: BTreeMap<Option<String>, Vec<(DateTime<Local>, bool)>> = BTreeMap::new();
+    for history in histories.iter() {
+        let states = machines.entry(history.target.machine.clone()).or_default();
+        for run in &history.runs {
+            states.push((run.first, run.vpn_active));
+            if run.last > run.first {
+                states.push((run.last, run.vpn_active));
+            }
+        }
+    }
+    for states in machines.values_mut() {
+        states.sort_by_key(|(timestamp, _)| *timestamp);
+    }
+    machines
+}
+
+/// Times at which the logged VPN state of each machine changed, in
+/// chronological order.
+#[must_use]
+pub fn vpn_transitions(histories: &Histories) -> BTreeMap<Option<String>, Vec<DateTime<Local>>> {
+    states_by_machine(histories)
+        .into_iter()
+        .map(|(machine, states)| {
+            let changes = states
+                .windows(2)
+                .filter(|pair| pair[0].1 != pair[1].1)
+                .map(|pair| pair[1].0)
+                .collect();
+            (machine, changes)
+        })
+        .collect()
+}
+
+/// Periods during which the logged VPN state of each machine was active, in
+/// chronological order.
+#[must_use]
+pub fn vpn_active_spans(histories: &Histories) -> VpnSpans {
+    states_by_machine(histories)
+        .into_iter()
+        .map(|(machine, states)| {
+            let mut spans = Vec::new();
+            let mut open: Option<DateTime<Local>> = None;
+            for (timestamp, vpn_active) in &states {
+                match (open, vpn_active) {
+                    (None, true) => open = Some(*timestamp),
+                    (Some(start), false) => {
+                        spans.push((start, *timestamp));
+                        open = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let (Some(start), Some((last, _))) = (open, states.last()) {
+                spans.push((start, *last));
+            }
+            (machine, spans)
+        })
+        .collect()
+}
+
+/// How incident starts line up with VPN state changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransitionCorrelation {
+    pub transitions: usize,
+    pub incidents: usize,
+    /// Incidents that started within the window of a VPN state change.
+    pub near_transition: usize,
+    /// Share (0-100) of the observed time lying within the window of a
+    /// change; the share of incidents expected near a change by chance.
+    pub expected_share: f64,
+}
+
+impl TransitionCorrelation {
+    /// Share (0-100) of incidents that started near a VPN state change.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "incident counts stay far below 2^52"
+    )]
+    pub fn observed_share(&self) -> Option<f64> {
+        (self.incidents > 0).then(|| (self.near_transition as f64 / self.incidents as f64) * 100.0)
+    }
+}
+
+/// Count incidents that started within `window` (before or after) of a VPN
+/// state change of the machine they were seen from.
+#[must_use]
+pub fn correlate_transitions(
+    histories: &Histories,
+    incidents: &[Incident],
+    window: Duration,
+) -> TransitionCorrelation {
+    let transitions = vpn_transitions(histories);
+    let near_transition = incidents
+        .iter()
+        .filter(|incident| {
+            transitions.get(&incident.machine).is_some_and(|changes| {
+                changes
+                    .iter()
+                    .any(|change| (incident.start - *change).abs() <= window)
+            })
+        })
+        .count();
+
+    // The share of each machine's observed span lying near one of its
+    // changes, weighted by the length of the span.
+    let mut covered = Duration::zero();
+    let mut observed = Duration::zero();
+    for (machine, states) in states_by_machine(histories) {
+        let (Some((first, _)), Some((last, _))) = (states.first(), states.last()) else {
+            continue;
+        };
+        let (first, last) = (*first, *last);
+        observed += last - first;
+        let changes = transitions.get(&machine).map_or(&[][..], Vec::as_slice);
+        covered += time_near_changes(changes, window, first, last);
+    }
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "millisecond spans stay far below 2^52"
+    )]
+    let expected_share = if observed > Duration::zero() {
+        covered.num_milliseconds() as f64 / observed.num_milliseconds() as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    TransitionCorrelation {
+        transitions: transitions.values().map(Vec::len).sum(),
+        incidents: incidents.len(),
+        near_transition,
+        expected_share,
+    }
+}
+
+/// The time between `first` and `last` lying within `window` of a change,
+/// merging the windows of changes close to each other.
+fn time_near_changes(
+    changes: &[DateTime<Local>],
+    window: Duration,
+    first: DateTime<Local>,
+    last: DateTime<Local>,
+) -> Duration {
+    let mut covered = Duration::zero();
+    let mut current: Option<(DateTime<Local>, DateTime<Local>)> = None;
+    for change in changes {
+        let start = (*change - window).max(first);
+        let end = (*change + window).min(last);
+        current = match current {
+            Some((open_start, open_end)) if start <= open_end => {
+                Some((open_start, open_end.max(end)))
+            }
+            Some((open_start, open_end)) => {
+                covered += open_end - open_start;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    if let Some((open_start, open_end)) = current {
+        covered += open_end - open_start;
+    }
+    covered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::IncidentOptions;
+    use crate::audit::extract_incidents;
+    use chrono::TimeZone;
+
+    fn event(minute: u32, success: bool, vpn_active: bool) -> PingEvent {
+        let at = Local.with_ymd_and_hms(2025, 12, 1, 8, minute, 0).unwrap();
+        PingEvent::test("8.8.8.8", at, success).with_vpn(vpn_active)
+    }
+
+    #[test]
+    fn incidents_right_after_a_vpn_change_are_counted() {
+        let events: Vec<PingEvent> = (0..60)
+            .map(|minute| event(minute, !matches!(minute, 31 | 50), minute >= 30))
+            .collect();
+        let histories = Histories::of(&events);
+        let incidents = extract_incidents(&histories, &IncidentOptions::default());
+        let correlation = correlate_transitions(&histories, &incidents, Duration::minutes(2));
+        assert_eq!(correlation.transitions, 1);
+        assert_eq!(correlation.incidents, 2);
+        assert_eq!(correlation.near_transition, 1);
+        assert!((correlation.expected_share - 400.0 / 59.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn vpn_states_are_followed_per_machine() {
+        // desk-1 is always on the VPN and desk-2 never; desk-2 switches it
+        // on at minute 30 and fails right after.
+        let mut events = Vec::new();
+        for minute in 0..60 {
+            events.push(event(minute, true, true).on_machine("desk-1"));
+            events.push(event(minute, minute != 31, minute >= 30).on_machine("desk-2"));
+        }
+        let histories = Histories::of(&events);
+        let spans = vpn_active_spans(&histories);
+        let at = |minute| Local.with_ymd_and_hms(2025, 12, 1, 8, minute, 0).unwrap();
+        assert_eq!(spans[&Some("desk-1".to_string())], [(at(0), at(59))]);
+        assert_eq!(spans[&Some("desk-2".to_string())], [(at(30), at(59))]);
+
+        let incidents = extract_incidents(&histories, &IncidentOptions::default());
+        let correlation = correlate_transitions(&histories, &incidents, Duration::minutes(2));
+        assert_eq!(correlation.transitions, 1);
+        assert_eq!(correlation.near_transition, 1);
+        assert!((correlation.expected_share - 200.0 / 59.0).abs() < 0.01);
+    }
+}
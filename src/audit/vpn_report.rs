@@ -0,0 +1,177 @@
+use crate::audit::Cell;
+use crate::audit::Histories;
+use crate::audit::IncidentOptions;
+use crate::audit::LatencySummary;
+use crate::audit::OutcomeStats;
+use crate::audit::ReportBody;
+use crate::audit::Table;
+use crate::audit::TransitionCorrelation;
+use crate::audit::VpnSplits;
+use crate::audit::correlate_transitions;
+use crate::audit::extract_incidents;
+use crate::audit::format_duration;
+use crate::audit::serialize_seconds;
+use chrono::Duration;
+use serde::Serialize;
+
+/// Counts and latency of the pings made in one VPN state.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutcomeSummary {
+    pub events: usize,
+    pub failures: usize,
+    pub failure_rate: Option<f64>,
+    pub latency: LatencySummary,
+}
+
+impl From<&OutcomeStats> for OutcomeSummary {
+    fn from(stats: &OutcomeStats) -> Self {
+        Self {
+            events: stats.events,
+            failures: stats.failures,
+            failure_rate: stats.failure_rate(),
+            latency: stats.latency.summary(),
+        }
+    }
+}
+
+/// One target, or one hour of day, split by VPN state.
+#[derive(Debug, Clone, Serialize)]
+pub struct VpnSplitRow {
+    pub label: String,
+    pub vpn_off: OutcomeSummary,
+    pub vpn_on: OutcomeSummary,
+}
+
+/// The `audit vpn` view.
+#[derive(Debug, Clone, Serialize)]
+pub struct VpnReport {
+    #[serde(rename = "window_seconds", serialize_with = "serialize_seconds")]
+    pub window: Duration,
+    pub by_target: Vec<VpnSplitRow>,
+    pub by_hour: Vec<VpnSplitRow>,
+    pub transitions: TransitionCorrelation,
+    /// Share (0-100) of incidents that started near a VPN state change.
+    pub observed_share: Option<f64>,
+}
+
+impl VpnReport {
+    /// Build the VPN view, counting incidents within `window` of a VPN state change.
+    #[must_use]
+    pub fn build(
+        histories: &Histories,
+        splits: &VpnSplits,
+        options: &IncidentOptions,
+        window: Duration,
+    ) -> Self {
+        let by_target = splits
+            .by_target(histories)
+            .into_iter()
+            .map(|(label, split)| VpnSplitRow {
+                label,
+                vpn_off: (&split.vpn_off).into(),
+                vpn_on: (&split.vpn_on).into(),
+            })
+            .collect();
+        let by_hour = splits
+            .by_hour()
+            .iter()
+            .enumerate()
+            .map(|(hour, split)| VpnSplitRow {
+                label: hour.to_string(),
+                vpn_off: (&split.vpn_off).into(),
+                vpn_on: (&split.vpn_on).into(),
+            })
+            .collect();
+        let incidents = extract_incidents(histories, options);
+        let transitions = correlate_transitions(histories, &incidents, window);
+        Self {
+            window,
+            by_target,
+            by_hour,
+            observed_share: transitions.observed_share(),
+            transitions,
+        }
+    }
+}
+
+fn rate(summary: &OutcomeSummary) -> Cell {
+    summary.failure_rate.map_or(Cell::Missing, Cell::Percent)
+}
+
+impl ReportBody for VpnReport {
+    fn tables(&self) -> Vec<Table> {
+        let mut by_target = Table::new(
+            "VPN Correlation by Target",
+            &[
+                "Target",
+                "VPN",
+                "Events",
+                "Failure Rate",
+                "p50 ms",
+                "p95 ms",
+                "p99 ms",
+            ],
+        );
+        if self.by_target.is_empty() {
+            by_target.push_note("No ping events to compare.");
+            return vec![by_target];
+        }
+        for row in &self.by_target {
+            for (label, vpn, summary) in [
+                (row.label.as_str(), "off", &row.vpn_off),
+                ("", "on", &row.vpn_on),
+            ] {
+                by_target.push_row(vec![
+                    label.into(),
+                    vpn.into(),
+                    summary.events.into(),
+                    rate(summary),
+                    summary.latency.p50_ms.into(),
+                    summary.latency.p95_ms.into(),
+                    summary.latency.p99_ms.into(),
+                ]);
+            }
+        }
+
+        let mut by_hour = Table::new(
+            "VPN Correlation by Hour of Day (all selected targets)",
+            &[
+                "Hour",
+                "Fail % VPN off",
+                "Fail % VPN on",
+                "p50 ms VPN off",
+                "p50 ms VPN on",
+            ],
+        );
+        for row in &self.by_hour {
+            by_hour.push_row(vec![
+                row.label.as_str().into(),
+                rate(&row.vpn_off),
+                rate(&row.vpn_on),
+                row.vpn_off.latency.p50_ms.into(),
+                row.vpn_on.latency.p50_ms.into(),
+            ]);
+        }
+
+        let window = format_duration(self.window);
+        let mut transitions = Table::new("Incidents Near VPN State Changes", &["Measure", "Value"]);
+        transitions.push_row(vec![
+            "VPN state changes".into(),
+            self.transitions.transitions.into(),
+        ]);
+        transitions.push_row(vec!["Incidents".into(), self.transitions.incidents.into()]);
+        transitions.push_row(vec![
+            format!("Incidents starting within {window} of a change").into(),
+            self.transitions.near_transition.into(),
+        ]);
+        transitions.push_row(vec![
+            "Share of incidents near a change".into(),
+            self.observed_share.map_or(Cell::Missing, Cell::Percent),
+        ]);
+        transitions.push_row(vec![
+            "Share expected by chance".into(),
+            Cell::Percent(self.transitions.expected_share),
+        ]);
+        vec![by_target, by_hour, transitions]
+    }
+}
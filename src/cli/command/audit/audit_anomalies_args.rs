@@ -0,0 +1,50 @@
+use crate::audit::AnomalyOptions;
+use crate::audit::AnomalyReport;
+use crate::audit::LatencyReplay;
+use crate::cli::command::audit::AuditArgs;
+use crate::config::Config;
+use clap::Args;
+use eyre::Result;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct AuditAnomaliesArgs {
+    /// Standard deviations above the baseline that count as slow; defaults to `latency_anomaly_z`
+    #[arg(long)]
+    pub z: Option<f64>,
+    /// How long latency must stay high; defaults to `latency_anomaly_duration`
+    #[arg(long = "for", value_parser = humantime::parse_duration)]
+    pub duration: Option<Duration>,
+    /// Weight of each ping in the baseline; defaults to `latency_baseline_alpha`
+    #[arg(long)]
+    pub alpha: Option<f64>,
+}
+
+impl AuditAnomaliesArgs {
+    /// # Errors
+    /// Returns an error if reading the logs or the configuration fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        if self.z.is_some_and(|z| z <= 0.0) {
+            eyre::bail!("--z must be greater than zero");
+        }
+        if self.alpha.is_some_and(|alpha| alpha <= 0.0 || alpha > 1.0) {
+            eyre::bail!("--alpha must be greater than zero and at most 1");
+        }
+        let mut settings = Config::current()?.runtime.latency_anomaly;
+        settings.z = self.z.unwrap_or(settings.z);
+        settings.duration = self.duration.unwrap_or(settings.duration);
+        settings.alpha = self.alpha.unwrap_or(settings.alpha);
+        let options = AnomalyOptions {
+            settings,
+            gap_factor: audit.gap_factor(),
+        };
+        // Data gaps are told by each target's usual interval over all its
+        // pings, so a first pass finds those before the pings are replayed.
+        let histories = audit.load(())?.histories;
+        let loaded = audit.load(LatencyReplay::new(options, &histories))?;
+        audit.emit(
+            &loaded,
+            AnomalyReport::build(&loaded.view, &loaded.histories),
+        )
+    }
+}
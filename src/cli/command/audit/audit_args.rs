@@ -1,273 +1,245 @@
+use crate::audit::AUDIT_INDEX_FILE;
+use crate::audit::Accumulator;
+use crate::audit::AuditReport;
+use crate::audit::CoverageReport;
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::EventFilter;
+use crate::audit::HourlyTallies;
+use crate::audit::LatencyCharts;
+use crate::audit::Loaded;
+use crate::audit::OverviewReport;
+use crate::audit::ReportBody;
+use crate::audit::ReportFormat;
+use crate::audit::TimeRange;
+use crate::audit::last_day_start;
+use crate::audit::load_hourly_events;
+use crate::audit::load_into;
+use crate::audit::parse_time_bound;
+use crate::audit::render_html;
+use crate::cli::command::audit::audit_command::AuditCommand;
 use crate::home::PIING_HOME;
+use crate::ping_database::PingDatabase;
 use chrono::DateTime;
-use chrono::Datelike;
-use chrono::Duration;
 use chrono::Local;
-use chrono::Timelike;
 use clap::Args;
 use eyre::Context;
 use eyre::Result;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
-
-#[derive(Debug, Deserialize)]
-struct LogEntry {
-    timestamp: String,
-    fields: LogFields,
-}
-
-#[derive(Debug, Deserialize)]
-struct LogFields {
-    success: bool,
-}
-
-#[derive(Debug)]
-struct PingEvent {
-    timestamp: DateTime<Local>,
-    success: bool,
-}
+use std::path::PathBuf;
 
 #[derive(Debug, Default, Args)]
-pub struct AuditArgs {}
+pub struct AuditArgs {
+    /// Only include events at or after this time: an RFC 3339 timestamp, a YYYY-MM-DD date or a duration ago such as `7d`
+    #[arg(long, value_parser = parse_time_bound_arg, global = true)]
+    pub since: Option<DateTime<Local>>,
+    /// Only include events before this time, in the same forms as `--since`
+    #[arg(long, value_parser = parse_time_bound_arg, global = true)]
+    pub until: Option<DateTime<Local>>,
+    /// Only include pings of targets carrying this tag; repeat to match any of several tags
+    #[arg(long = "tag", value_name = "TAG", global = true)]
+    pub tags: Vec<String>,
+    /// Only include pings of the target with this name; repeat to select several targets
+    #[arg(long = "target", value_name = "NAME", global = true)]
+    pub targets: Vec<String>,
+    /// Only include pings of this host; repeat to select several hosts
+    #[arg(long = "host", value_name = "HOST", global = true)]
+    pub hosts: Vec<String>,
+    /// Count pings made during maintenance windows instead of excluding them
+    #[arg(long, global = true)]
+    pub include_maintenance: bool,
+    /// Count failures logged during the grace period after a system resume instead of excluding them
+    #[arg(long, global = true)]
+    pub include_resume_grace: bool,
+    /// Read logs from this directory or zip archive instead of the local logs directory; repeat to merge the logs of several machines
+    #[arg(long = "logs-dir", value_name = "PATH", global = true)]
+    pub logs_dirs: Vec<PathBuf>,
+    /// Query the ping database instead of the log files: the local one, or the one given as `--db=PATH`
+    #[arg(long = "db", value_name = "PATH", num_args = 0..=1, require_equals = true, global = true)]
+    pub database: Option<Option<PathBuf>>,
+    /// Treat a target going without records for more than this many times its usual interval as a data gap [default: 3]
+    #[arg(long, value_name = "K", value_parser = clap::value_parser!(i32).range(2..), global = true)]
+    pub gap_factor: Option<i32>,
+    /// Write a self-contained HTML report with charts to this file instead of printing the overview
+    #[arg(long, value_name = "PATH")]
+    pub html: Option<PathBuf>,
+    /// Parse every log file instead of reusing the per-hour summaries of the audit index
+    #[arg(long)]
+    pub no_index: bool,
+    /// How to write the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text, global = true)]
+    pub format: ReportFormat,
+    #[command(subcommand)]
+    pub command: Option<AuditCommand>,
+}
 
 impl AuditArgs {
     /// # Errors
     /// Returns an error if reading the logs directory fails
-    #[expect(
-        clippy::too_many_lines,
-        reason = "invoke orchestrates reporting and is intentionally verbose"
-    )]
-    pub fn invoke(self) -> Result<()> {
-        println!("Discovering log files...\n");
-
-        let logs_dir = PIING_HOME.logs_dir();
-
-        if !logs_dir.exists() {
-            println!("Logs directory does not exist: {}", logs_dir.display());
-            return Ok(());
+    pub fn invoke(mut self) -> Result<()> {
+        match self.command.take() {
+            Some(command) => command.invoke(&self),
+            None => self.report_overview(),
         }
+    }
 
-        let mut log_files = Vec::new();
-
-        if let Ok(entries) = std::fs::read_dir(&logs_dir) {
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata()
-                    && metadata.is_file()
-                    && let Some(ext) = entry.path().extension()
-                    && (ext == "ndjson" || ext == "log")
-                {
-                    log_files.push(entry.path());
-                }
-            }
-        }
+    /// The filter selected by the common audit arguments.
+    #[must_use]
+    pub fn event_filter(&self) -> EventFilter {
+        self.event_filter_in(TimeRange::new(self.since, self.until))
+    }
 
-        if log_files.is_empty() {
-            println!("No log files found in: {}", logs_dir.display());
-            return Ok(());
+    /// The filter selected by the common audit arguments, over another time range.
+    #[must_use]
+    pub fn event_filter_in(&self, range: TimeRange) -> EventFilter {
+        EventFilter {
+            range,
+            tags: self.tags.clone(),
+            targets: self.targets.clone(),
+            hosts: self.hosts.clone(),
+            include_maintenance: self.include_maintenance,
+            include_resume_grace: self.include_resume_grace,
         }
+    }
 
-        log_files.sort();
-        println!("Found {} log file(s)\n", log_files.len());
-
-        // Parse all log entries
-        let mut events: Vec<PingEvent> = Vec::new();
-
-        for log_file in &log_files {
-            let file = File::open(log_file)
-                .wrap_err_with(|| format!("Failed to open log file: {}", log_file.display()))?;
-            let reader = BufReader::new(file);
+    /// The multiple of a target's usual interval beyond which a silence is a data gap.
+    #[must_use]
+    pub fn gap_factor(&self) -> i32 {
+        self.gap_factor.unwrap_or(DEFAULT_GAP_FACTOR)
+    }
 
-            for line in reader.lines() {
-                let line = line?;
-                if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
-                    // Parse timestamp from ISO 8601 format
-                    if let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-                        events.push(PingEvent {
-                            timestamp: dt.with_timezone(&Local),
-                            success: entry.fields.success,
-                        });
-                    }
-                }
-            }
+    /// The logs directories and archives to read: those given with
+    /// `--logs-dir`, or the local logs directory.
+    #[must_use]
+    pub fn log_sources(&self) -> Vec<PathBuf> {
+        if self.logs_dirs.is_empty() {
+            vec![PIING_HOME.logs_dir()]
+        } else {
+            self.logs_dirs.clone()
         }
+    }
 
-        println!("Parsed {} ping events\n", events.len());
-
-        // Count failures by hour of day (0-23)
-        let mut failures_by_hour: HashMap<u32, usize> = HashMap::new();
-        let mut total_by_hour: HashMap<u32, usize> = HashMap::new();
-
-        for event in &events {
-            let hour = event.timestamp.hour();
-            *total_by_hour.entry(hour).or_insert(0) += 1;
-            if !event.success {
-                *failures_by_hour.entry(hour).or_insert(0) += 1;
-            }
+    /// The ping database selected with `--db`, if any.
+    ///
+    /// # Errors
+    /// Returns an error if the database does not exist or cannot be opened
+    pub fn database(&self) -> Result<Option<PingDatabase>> {
+        let Some(path) = &self.database else {
+            return Ok(None);
+        };
+        let path = path.clone().unwrap_or_else(|| PIING_HOME.database_path());
+        if !path.is_file() {
+            eyre::bail!(
+                "No ping database at {}; turn on sqlite in a piing_logging block or fill it with `piing db import`",
+                path.display()
+            );
         }
+        PingDatabase::open(&path).map(Some)
+    }
 
-        // Print histogram
-        println!("Failure Distribution by Hour of Day:\n");
-        println!("Hour | Failures |      Total | Failure Rate | Bar");
-        println!(
-            "-----|----------|------------|--------------|{}",
-            "-".repeat(50)
-        );
-
-        let max_failures = failures_by_hour.values().max().copied().unwrap_or(0);
-        let bar_width = 50;
-
-        for hour in 0..24 {
-            let failures = failures_by_hour.get(&hour).copied().unwrap_or(0);
-            let total = total_by_hour.get(&hour).copied().unwrap_or(0);
-            #[allow(clippy::cast_precision_loss)]
-            let rate = if total > 0 {
-                (failures as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            };
-
-            let bar_length = if max_failures > 0 {
-                (failures * bar_width) / max_failures
-            } else {
-                0
-            };
 
-            let bar = "█".repeat(bar_length);
+    /// Read the ping events selected by the common audit arguments into
+    /// `view`, keeping only what the view gathers and the target histories.
+    ///
+    /// # Errors
+    /// Returns an error if the time range is empty or a log file cannot be read
+    pub fn load<A: Accumulator>(&self, view: A) -> Result<Loaded<A>> {
+        self.check_range()?;
+        self.load_in(TimeRange::new(self.since, self.until), view)
+    }
 
-            println!("{hour:4} | {failures:8} | {total:10} | {rate:11.2}% | {bar}");
+    fn check_range(&self) -> Result<()> {
+        if let (Some(since), Some(until)) = (self.since, self.until)
+            && since >= until
+        {
+            eyre::bail!("--since must be earlier than --until");
         }
+        Ok(())
+    }
 
-        println!(
-            "\nTotal failures: {}",
-            failures_by_hour.values().sum::<usize>()
-        );
-        println!("Total events: {}", events.len());
-
-        // Count failures by day of week (0=Monday, 6=Sunday)
-        let mut failures_by_day: HashMap<u32, usize> = HashMap::new();
-        let mut total_by_day: HashMap<u32, usize> = HashMap::new();
 
-        for event in &events {
-            let day = event.timestamp.weekday().num_days_from_monday();
-            *total_by_day.entry(day).or_insert(0) += 1;
-            if !event.success {
-                *failures_by_day.entry(day).or_insert(0) += 1;
-            }
+    /// Read the ping events selected by the common audit arguments into
+    /// `view`, over another time range than `--since` and `--until`.
+    ///
+    /// # Errors
+    /// Returns an error if a log file cannot be read
+    pub fn load_in<A: Accumulator>(&self, range: TimeRange, view: A) -> Result<Loaded<A>> {
+        match self.database()? {
+            Some(database) => database.load_into(&self.event_filter_in(range), view),
+            None => load_into(&self.log_sources(), &self.event_filter_in(range), view),
         }
+    }
 
-        // Print day of week histogram
-        println!("\n\nFailure Distribution by Day of Week:\n");
-        println!("Day       | Failures |      Total | Failure Rate | Bar");
-        println!(
-            "----------|----------|------------|--------------|{}",
-            "-".repeat(50)
+    /// Wrap a view built from the loaded events into a report, adding the
+    /// monitoring coverage of those events.
+    #[must_use]
+    pub fn report<A, T: ReportBody>(&self, loaded: &Loaded<A>, body: T) -> AuditReport<T> {
+        let coverage = CoverageReport::build(
+            &loaded.histories,
+            &loaded.filter.range,
+            self.gap_factor(),
+            Local::now(),
         );
+        AuditReport::new(&loaded.filter, loaded.source.clone(), coverage, body)
+    }
 
-        let max_failures_day = failures_by_day.values().max().copied().unwrap_or(0);
-        let day_names = [
-            "Monday",
-            "Tuesday",
-            "Wednesday",
-            "Thursday",
-            "Friday",
-            "Saturday",
-            "Sunday",
-        ];
-
-        for day in 0..7 {
-            let failures = failures_by_day.get(&day).copied().unwrap_or(0);
-            let total = total_by_day.get(&day).copied().unwrap_or(0);
-            #[allow(clippy::cast_precision_loss)]
-            let rate = if total > 0 {
-                (failures as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            };
-
-            let bar_length = if max_failures_day > 0 {
-                (failures * bar_width) / max_failures_day
-            } else {
-                0
-            };
-
-            let bar = "█".repeat(bar_length);
+    /// Render a report in the selected format and print it.
+    ///
+    /// # Errors
+    /// Returns an error if the report cannot be rendered
+    pub fn emit<A, T: ReportBody>(&self, loaded: &Loaded<A>, body: T) -> Result<()> {
+        print!("{}", self.report(loaded, body).render(self.format)?);
+        Ok(())
+    }
 
-            println!(
-                "{:9} | {:8} | {:10} | {:11.2}% | {}",
-                day_names[day as usize], failures, total, rate, bar
-            );
+    fn report_overview(&self) -> Result<()> {
+        // An explicit window replaces the fixed last-24-hours section.
+        let now = TimeRange::new(self.since, self.until)
+            .is_unbounded()
+            .then(Local::now);
+        // The database is queried directly rather than through the index.
+        if self.html.is_none() && self.database.is_none() {
+            return self.report_overview_indexed(now);
         }
-
-        println!(
-            "\nTotal failures: {}",
-            failures_by_day.values().sum::<usize>()
-        );
-        println!("Total events: {}", events.len());
-
-        // Filter events from last 24 hours
-        let now = Local::now();
-        let twenty_four_hours_ago = now - Duration::hours(24);
-        let recent_events: Vec<&PingEvent> = events
-            .iter()
-            .filter(|e| e.timestamp >= twenty_four_hours_ago)
-            .collect();
-
-        if recent_events.is_empty() {
-            println!("\n\nNo events found in the last 24 hours.");
-        } else {
-            // Count failures by hour for last 24 hours
-            let mut recent_failures_by_hour: HashMap<u32, usize> = HashMap::new();
-            let mut recent_total_by_hour: HashMap<u32, usize> = HashMap::new();
-
-            for event in &recent_events {
-                let hour = event.timestamp.hour();
-                *recent_total_by_hour.entry(hour).or_insert(0) += 1;
-                if !event.success {
-                    *recent_failures_by_hour.entry(hour).or_insert(0) += 1;
-                }
-            }
-
-            // Print last 24 hours histogram
-            println!("\n\nFailure Distribution by Hour of Day (Last 24 Hours):\n");
-            println!("Hour | Failures |      Total | Failure Rate | Bar");
-            println!(
-                "-----|----------|------------|--------------|{}",
-                "-".repeat(50)
-            );
-
-            let max_recent_failures = recent_failures_by_hour.values().max().copied().unwrap_or(0);
-
-            for hour in 0..24 {
-                let failures = recent_failures_by_hour.get(&hour).copied().unwrap_or(0);
-                let total = recent_total_by_hour.get(&hour).copied().unwrap_or(0);
-                #[allow(clippy::cast_precision_loss)]
-                let rate = if total > 0 {
-                    (failures as f64 / total as f64) * 100.0
-                } else {
-                    0.0
-                };
-
-                let bar_length = if max_recent_failures > 0 {
-                    (failures * bar_width) / max_recent_failures
-                } else {
-                    0
-                };
-
-                let bar = "█".repeat(bar_length);
-
-                println!("{hour:4} | {failures:8} | {total:10} | {rate:11.2}% | {bar}");
-            }
-
-            println!(
-                "\nTotal failures (last 24h): {}",
-                recent_failures_by_hour.values().sum::<usize>()
-            );
-            println!("Total events (last 24h): {}", recent_events.len());
+        let cuts: Vec<DateTime<Local>> = now.map(last_day_start).into_iter().collect();
+        let loaded = self.load(HourlyTallies::new(cuts))?;
+        let report = OverviewReport::from_hours(&loaded.view.hours(), now);
+        if let Some(path) = &self.html {
+            // The charts are spread over the span of all pings, known only
+            // once the first pass has read them.
+            let charts = self.load(LatencyCharts::new(&loaded.histories))?.view;
+            let report = self.report(&loaded, report);
+            std::fs::write(path, render_html(&report, &charts, &loaded.histories))
+                .wrap_err_with(|| format!("Failed to write HTML report: {}", path.display()))?;
+            println!("Wrote HTML report to {}", path.display());
+            return Ok(());
         }
+        self.emit(&loaded, report)
+    }
 
+    /// Print the overview from per-hour summaries, kept in the audit index
+    /// unless told not to, without holding every ping in memory.
+    fn report_overview_indexed(&self, now: Option<DateTime<Local>>) -> Result<()> {
+        self.check_range()?;
+        let cuts: Vec<DateTime<Local>> = now.map(last_day_start).into_iter().collect();
+        let index_path = PIING_HOME.cache_dir().join(AUDIT_INDEX_FILE);
+        let loaded = load_hourly_events(
+            (!self.no_index).then_some(index_path.as_path()),
+            &self.log_sources(),
+            &self.event_filter(),
+            &cuts,
+        )?;
+        let coverage = CoverageReport::from_timelines(
+            loaded.timelines,
+            &loaded.filter.range,
+            self.gap_factor(),
+            Local::now(),
+        );
+        let body = OverviewReport::from_hours(&loaded.hours, now);
+        let report = AuditReport::new(&loaded.filter, loaded.source, coverage, body);
+        print!("{}", report.render(self.format)?);
         Ok(())
     }
 }
+
+fn parse_time_bound_arg(raw: &str) -> std::result::Result<DateTime<Local>, String> {
+    parse_time_bound(raw).map_err(|error| error.to_string())
+}
@@ -0,0 +1,48 @@
+use crate::cli::command::audit::AuditArgs;
+use crate::cli::command::audit::audit_anomalies_args::AuditAnomaliesArgs;
+use crate::cli::command::audit::audit_compare_args::AuditCompareArgs;
+use crate::cli::command::audit::audit_correlated_args::AuditCorrelatedArgs;
+use crate::cli::command::audit::audit_errors_args::AuditErrorsArgs;
+use crate::cli::command::audit::audit_incidents_args::AuditIncidentsArgs;
+use crate::cli::command::audit::audit_machines_args::AuditMachinesArgs;
+use crate::cli::command::audit::audit_sla_args::AuditSlaArgs;
+use crate::cli::command::audit::audit_vpn_args::AuditVpnArgs;
+use clap::Subcommand;
+use eyre::Result;
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// List periods when latency stayed well above each target's rolling baseline
+    Anomalies(AuditAnomaliesArgs),
+    /// Compare failure rate, incidents and latency between two periods
+    Compare(AuditCompareArgs),
+    /// Classify failures by whether all targets, a subset or a single target failed together
+    Correlated(AuditCorrelatedArgs),
+    /// Break failures down by error category per target, hour of day and period
+    Errors(AuditErrorsArgs),
+    /// Group consecutive failures into outage incidents with MTTR and MTBF
+    Incidents(AuditIncidentsArgs),
+    /// Compare machines side by side and find failures that hit every machine at once
+    Machines(AuditMachinesArgs),
+    /// Compute availability per day, week or month and check it against SLOs
+    Sla(AuditSlaArgs),
+    /// Compare failures and latency with the VPN on and off
+    Vpn(AuditVpnArgs),
+}
+
+impl AuditCommand {
+    /// # Errors
+    /// Returns an error if the audit subcommand fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        match self {
+            AuditCommand::Anomalies(args) => args.invoke(audit),
+            AuditCommand::Compare(args) => args.invoke(audit),
+            AuditCommand::Correlated(args) => args.invoke(audit),
+            AuditCommand::Errors(args) => args.invoke(audit),
+            AuditCommand::Incidents(args) => args.invoke(audit),
+            AuditCommand::Machines(args) => args.invoke(audit),
+            AuditCommand::Sla(args) => args.invoke(audit),
+            AuditCommand::Vpn(args) => args.invoke(audit),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::audit::CompareOptions;
+use crate::audit::CompareReport;
+use crate::audit::ComparedPeriods;
+use crate::audit::TimeRange;
+use crate::audit::parse_time_range;
+use crate::cli::command::audit::AuditArgs;
+use clap::Args;
+use eyre::Result;
+
+#[derive(Debug, Args)]
+pub struct AuditCompareArgs {
+    /// The period before the change, as START..END with bounds in the same forms as `--since`, such as `28d..14d`
+    #[arg(long, value_name = "RANGE", value_parser = parse_time_range_arg)]
+    pub a: TimeRange,
+    /// The period after the change, such as `14d..`
+    #[arg(long, value_name = "RANGE", value_parser = parse_time_range_arg)]
+    pub b: TimeRange,
+    /// Confidence level of the intervals, as a percentage
+    #[arg(long, default_value_t = 95.0)]
+    pub confidence: f64,
+    /// Number of bootstrap resamples behind each interval, from 10 to 100000
+    #[arg(long, default_value_t = 1_000, value_parser = clap::value_parser!(u32).range(10..=100_000))]
+    pub resamples: u32,
+}
+
+impl AuditCompareArgs {
+    /// # Errors
+    /// Returns an error if the arguments are invalid or reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        if audit.since.is_some() || audit.until.is_some() {
+            eyre::bail!("--a and --b replace --since and --until for audit compare");
+        }
+        if !(self.confidence > 0.0 && self.confidence < 100.0) {
+            eyre::bail!("--confidence must be a percentage between 0 and 100");
+        }
+        if self.a.intersects(&self.b) {
+            eyre::bail!(
+                "--a ({}) and --b ({}) overlap; the periods must not share any pings",
+                self.a,
+                self.b
+            );
+        }
+        // Read both periods in one pass over the logs.
+        let union = TimeRange::new(
+            self.a.since.zip(self.b.since).map(|(a, b)| a.min(b)),
+            self.a.until.zip(self.b.until).map(|(a, b)| a.max(b)),
+        );
+        let loaded = audit.load_in(union, ComparedPeriods::new(self.a, self.b))?;
+        let options = CompareOptions {
+            resamples: self.resamples as usize,
+            confidence: self.confidence,
+            gap_factor: audit.gap_factor(),
+        };
+        let report = CompareReport::build(&loaded.view, &options);
+        audit.emit(&loaded, report)
+    }
+}
+
+fn parse_time_range_arg(raw: &str) -> std::result::Result<TimeRange, String> {
+    parse_time_range(raw).map_err(|error| error.to_string())
+}
@@ -0,0 +1,34 @@
+use crate::audit::CorrelatedReport;
+use crate::audit::CorrelationOptions;
+use crate::audit::ReportPeriod;
+use crate::cli::command::audit::AuditArgs;
+use clap::Args;
+use eyre::Result;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct AuditCorrelatedArgs {
+    /// Treat failures within the same bucket of this length as simultaneous
+    #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+    pub bucket: Duration,
+    /// Count incidents of each scope per day, week or month
+    #[arg(long, value_enum, default_value_t = ReportPeriod::Day)]
+    pub period: ReportPeriod,
+}
+
+impl AuditCorrelatedArgs {
+    /// # Errors
+    /// Returns an error if reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        if self.bucket.is_zero() {
+            eyre::bail!("--bucket must be longer than zero");
+        }
+        let loaded = audit.load(())?;
+        let options = CorrelationOptions {
+            bucket: chrono::Duration::from_std(self.bucket)?,
+            gap_factor: audit.gap_factor(),
+        };
+        let report = CorrelatedReport::build(&loaded.histories, &options, self.period);
+        audit.emit(&loaded, report)
+    }
+}
@@ -0,0 +1,23 @@
+use crate::audit::ErrorReport;
+use crate::audit::ErrorTally;
+use crate::audit::ReportPeriod;
+use crate::cli::command::audit::AuditArgs;
+use clap::Args;
+use eyre::Result;
+
+#[derive(Debug, Args)]
+pub struct AuditErrorsArgs {
+    /// Break error categories down by day, week or month
+    #[arg(long, value_enum, default_value_t = ReportPeriod::Day)]
+    pub period: ReportPeriod,
+}
+
+impl AuditErrorsArgs {
+    /// # Errors
+    /// Returns an error if reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        let mut loaded = audit.load(ErrorTally::new(self.period))?;
+        let tally = std::mem::replace(&mut loaded.view, ErrorTally::new(self.period));
+        audit.emit(&loaded, ErrorReport::build(tally, &loaded.histories))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::Histories;
+use crate::audit::Incident;
+use crate::audit::IncidentGrouping;
+use crate::audit::IncidentOptions;
+use crate::audit::IncidentReport;
+use crate::audit::ReportPeriod;
+use crate::audit::TimeRange;
+use crate::audit::extract_incidents;
+use crate::cli::command::audit::AuditArgs;
+use crate::ping_database::PingDatabase;
+use clap::Args;
+use eyre::Result;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct AuditIncidentsArgs {
+    /// Extract incidents per target, per tag, or across all selected targets
+    #[arg(long, value_enum, default_value_t = IncidentGrouping::Target)]
+    pub group_by: IncidentGrouping,
+    /// Merge incidents that start within this long after the previous one ended
+    #[arg(long, default_value = "0s", value_parser = humantime::parse_duration)]
+    pub merge_gap: Duration,
+    /// Ignore incidents shorter than this
+    #[arg(long, default_value = "0s", value_parser = humantime::parse_duration)]
+    pub min_duration: Duration,
+    /// Break MTTR, MTBF and the longest outage down by day, week or month
+    #[arg(long, value_enum, default_value_t = ReportPeriod::All)]
+    pub period: ReportPeriod,
+}
+
+impl AuditIncidentsArgs {
+    /// # Errors
+    /// Returns an error if reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        let loaded = audit.load(())?;
+        let options = IncidentOptions {
+            grouping: self.group_by,
+            merge_gap: chrono::Duration::from_std(self.merge_gap)?,
+            min_duration: chrono::Duration::from_std(self.min_duration)?,
+            gap_factor: audit.gap_factor(),
+        };
+        // The database keeps the incidents of each target as recorded, with
+        // the pings counted by default.
+        let recorded = options.grouping == IncidentGrouping::Target
+            && options.merge_gap.is_zero()
+            && options.gap_factor == DEFAULT_GAP_FACTOR
+            && !audit.include_maintenance
+            && !audit.include_resume_grace;
+        let incidents = match audit.database()? {
+            Some(database) if recorded => {
+                stored_incidents(&database, &loaded.histories, &loaded.filter.range, &options)?
+            }
+            _ => extract_incidents(&loaded.histories, &options),
+        };
+        let report =
+            IncidentReport::from_incidents(&loaded.histories, incidents, &options, self.period);
+        audit.emit(&loaded, report)
+    }
+}
+
+/// The incidents kept in the database that started within the range, for
+/// the targets of the selected pings, labelled and ordered as extracting
+/// them from the pings would.
+fn stored_incidents(
+    database: &PingDatabase,
+    histories: &Histories,
+    range: &TimeRange,
+    options: &IncidentOptions,
+) -> Result<Vec<Incident>> {
+    let mut incidents = Vec::new();
+    for stored in database.incidents(range)? {
+        // Reports on a single machine leave targets without one.
+        let Some(history) = histories.iter().find(|history| {
+            history.target.host == stored.host
+                && history.target.mode == stored.mode
+                && history
+                    .target
+                    .machine
+                    .as_ref()
+                    .is_none_or(|machine| *machine == stored.machine_id)
+        }) else {
+            continue;
+        };
+        let incident = Incident {
+            group: history.label(),
+            machine: history.target.machine.clone(),
+            start: stored.start,
+            end: stored.end,
+            failed_probes: stored.failed_probes,
+            recovered: stored.recovered,
+        };
+        if incident.duration() >= options.min_duration {
+            incidents.push(incident);
+        }
+    }
+    incidents.sort_by(|a, b| a.group.cmp(&b.group).then(a.start.cmp(&b.start)));
+    Ok(incidents)
+}
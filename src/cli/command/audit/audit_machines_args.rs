@@ -0,0 +1,33 @@
+use crate::audit::CorrelationOptions;
+use crate::audit::MachineReport;
+use crate::audit::TargetSummaries;
+use crate::cli::command::audit::AuditArgs;
+use clap::Args;
+use eyre::Result;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct AuditMachinesArgs {
+    /// Treat failures on different machines within the same bucket of this length as simultaneous
+    #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+    pub bucket: Duration,
+}
+
+impl AuditMachinesArgs {
+    /// # Errors
+    /// Returns an error if reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        if self.bucket.is_zero() {
+            eyre::bail!("--bucket must be longer than zero");
+        }
+        let loaded = audit.load(TargetSummaries::default())?;
+        let options = CorrelationOptions {
+            bucket: chrono::Duration::from_std(self.bucket)?,
+            gap_factor: audit.gap_factor(),
+        };
+        audit.emit(
+            &loaded,
+            MachineReport::build(&loaded.view, &loaded.histories, &options),
+        )
+    }
+}
@@ -0,0 +1,55 @@
+use crate::audit::AvailabilityMethod;
+use crate::audit::IncidentGrouping;
+use crate::audit::MissingData;
+use crate::audit::ReportPeriod;
+use crate::audit::SlaOptions;
+use crate::audit::SlaReport;
+use crate::cli::command::audit::AuditArgs;
+use crate::config::Config;
+use clap::Args;
+use eyre::Result;
+
+#[derive(Debug, Args)]
+pub struct AuditSlaArgs {
+    /// Report availability per target, per tag, or across all selected targets
+    #[arg(long, value_enum, default_value_t = IncidentGrouping::Target)]
+    pub group_by: IncidentGrouping,
+    /// Break availability down by day, week or month
+    #[arg(long, value_enum, default_value_t = ReportPeriod::Month)]
+    pub period: ReportPeriod,
+    /// Weight availability by time between pings or by number of pings
+    #[arg(long, value_enum, default_value_t = AvailabilityMethod::Time)]
+    pub method: AvailabilityMethod,
+    /// Whether periods without pings count as downtime or are left out
+    #[arg(long, value_enum, default_value_t = MissingData::Unknown)]
+    pub missing: MissingData,
+    /// Availability objective (percentage) for groups whose targets have no `slo` attribute
+    #[arg(long)]
+    pub slo: Option<f64>,
+}
+
+impl AuditSlaArgs {
+    /// # Errors
+    /// Returns an error if reading the logs or the configuration fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        if self.slo.is_some_and(|slo| slo <= 0.0 || slo >= 100.0) {
+            eyre::bail!("--slo must be a percentage between 0 and 100");
+        }
+        let objectives = Config::current()?
+            .targets
+            .iter()
+            .filter_map(|target| Some((target.id.name.to_lowercase(), target.slo?)))
+            .collect();
+        let loaded = audit.load(())?;
+        let options = SlaOptions {
+            grouping: self.group_by,
+            period: self.period,
+            method: self.method,
+            missing: self.missing,
+            gap_factor: audit.gap_factor(),
+            objectives,
+            default_objective: self.slo,
+        };
+        audit.emit(&loaded, SlaReport::build(&loaded.histories, &options))
+    }
+}
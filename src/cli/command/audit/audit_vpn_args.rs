@@ -0,0 +1,38 @@
+use crate::audit::IncidentGrouping;
+use crate::audit::IncidentOptions;
+use crate::audit::VpnReport;
+use crate::audit::VpnSplits;
+use crate::cli::command::audit::AuditArgs;
+use clap::Args;
+use eyre::Result;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct AuditVpnArgs {
+    /// Count incidents starting within this long before or after a VPN state change
+    #[arg(long, default_value = "60s", value_parser = humantime::parse_duration)]
+    pub window: Duration,
+    /// Extract incidents per target, per tag, or across all selected targets
+    #[arg(long, value_enum, default_value_t = IncidentGrouping::Target)]
+    pub group_by: IncidentGrouping,
+}
+
+impl AuditVpnArgs {
+    /// # Errors
+    /// Returns an error if reading the logs fails
+    pub fn invoke(self, audit: &AuditArgs) -> Result<()> {
+        let loaded = audit.load(VpnSplits::default())?;
+        let options = IncidentOptions {
+            grouping: self.group_by,
+            gap_factor: audit.gap_factor(),
+            ..IncidentOptions::default()
+        };
+        let report = VpnReport::build(
+            &loaded.histories,
+            &loaded.view,
+            &options,
+            chrono::Duration::from_std(self.window)?,
+        );
+        audit.emit(&loaded, report)
+    }
+}
@@ -1,3 +1,12 @@
+pub mod audit_anomalies_args;
 pub mod audit_args;
+pub mod audit_command;
+pub mod audit_compare_args;
+pub mod audit_correlated_args;
+pub mod audit_errors_args;
+pub mod audit_incidents_args;
+pub mod audit_machines_args;
+pub mod audit_sla_args;
+pub mod audit_vpn_args;
 
 pub use audit_args::AuditArgs;
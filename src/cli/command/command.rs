@@ -1,4 +1,5 @@
 use crate::cli::command::audit::AuditArgs;
+use crate::cli::command::db::DbArgs;
 use crate::cli::command::home::HomeArgs;
 use crate::cli::command::run::RunArgs;
 use crate::cli::command::sound::SoundArgs;
@@ -18,6 +19,8 @@ pub enum Command {
     Target(TargetArgs),
     /// Audit log files
     Audit(AuditArgs),
+    /// Manage the SQLite ping database
+    Db(DbArgs),
     /// Manage VPN related commands
     Vpn(VpnArgs),
     /// Print the piing home directory
@@ -63,6 +66,7 @@ impl Command {
             Command::Run(args) => args.invoke()?,
             Command::Target(args) => args.invoke()?,
             Command::Audit(args) => args.invoke()?,
+            Command::Db(args) => args.invoke()?,
             Command::Vpn(args) => args.invoke()?,
             Command::Home(args) => args.invoke()?,
             Command::Sound(args) => args.invoke()?,
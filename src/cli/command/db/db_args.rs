@@ -0,0 +1,17 @@
+use crate::cli::command::db::db_command::DbCommand;
+use clap::Args;
+use eyre::Result;
+
+#[derive(Debug, Args)]
+pub struct DbArgs {
+    #[command(subcommand)]
+    pub command: DbCommand,
+}
+
+impl DbArgs {
+    /// # Errors
+    /// Returns an error if the database command fails
+    pub fn invoke(self) -> Result<()> {
+        self.command.invoke()
+    }
+}
@@ -0,0 +1,19 @@
+use crate::cli::command::db::db_import_args::DbImportArgs;
+use clap::Subcommand;
+use eyre::Result;
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Backfill the ping database from ndjson log files
+    Import(DbImportArgs),
+}
+
+impl DbCommand {
+    /// # Errors
+    /// Returns an error if the database subcommand fails
+    pub fn invoke(self) -> Result<()> {
+        match self {
+            DbCommand::Import(args) => args.invoke(),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::audit::EventFilter;
+use crate::audit::fallback_machine;
+use crate::audit::load_by_file;
+use crate::config::Config;
+use crate::home::PIING_HOME;
+use crate::ping_database::ImportCounts;
+use crate::ping_database::PingDatabase;
+use crate::ping_record::PingRecord;
+use clap::Args;
+use eyre::Result;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Args)]
+pub struct DbImportArgs {
+    /// Read logs from this directory or zip archive instead of the local logs directory; repeat to import the logs of several machines
+    #[arg(long = "logs-dir", value_name = "PATH")]
+    pub logs_dirs: Vec<PathBuf>,
+    /// Import into the database at this path instead of the local ping database
+    #[arg(long = "db", value_name = "PATH")]
+    pub database: Option<PathBuf>,
+}
+
+impl DbImportArgs {
+    /// # Errors
+    /// Returns an error if the logs cannot be read or the database cannot be written
+    pub fn invoke(self) -> Result<()> {
+        // Logs in the local logs directory that name no machine were written
+        // by this machine; other directories and archives keep their name.
+        let local_source = self.logs_dirs.is_empty();
+        let sources = if local_source {
+            vec![PIING_HOME.logs_dir()]
+        } else {
+            self.logs_dirs
+        };
+        // Pings in maintenance windows and resume grace periods are stored
+        // with their flags, for the audit to count or exclude later.
+        let filter = EventFilter {
+            include_maintenance: true,
+            include_resume_grace: true,
+            ..EventFilter::default()
+        };
+        let path = self.database.unwrap_or_else(|| PIING_HOME.database_path());
+        let mut database = PingDatabase::open(&path)?;
+
+        // Each log file is imported in a transaction of its own, so that
+        // archives larger than memory can be imported.
+        let mut counts = ImportCounts::default();
+        let mut files = 0;
+        let mut records = 0;
+        let mut unreadable = 0;
+        for source in &sources {
+            let machine = if local_source {
+                Config::current().map_or_else(|_| "unknown".to_string(), |c| c.runtime.machine())
+            } else {
+                // Credit pings that name no machine as audits do, which
+                // takes a first pass over the source.
+                fallback_machine(load_by_file(source, &filter, &(), |_| Ok(()))?, source)
+            };
+            load_by_file(source, &filter, &Vec::new(), |loaded| {
+                let batch: Vec<PingRecord> = loaded
+                    .view
+                    .iter()
+                    .filter_map(|event| PingRecord::from_event(event, &machine))
+                    .collect();
+                let added = database.import(&batch)?;
+                counts.pings += added.pings;
+                counts.incidents += added.incidents;
+                files += loaded.source.files_read;
+                records += batch.len();
+                unreadable += loaded.view.len() - batch.len()
+                    + loaded.source.malformed_lines
+                    + loaded.source.truncated_lines;
+                Ok(())
+            })?;
+        }
+
+        println!(
+            "Imported {} ping record(s) and {} incident(s) from {files} log file(s) into {}",
+            counts.pings,
+            counts.incidents,
+            path.display()
+        );
+        let present = records - counts.pings;
+        if present > 0 {
+            println!("Skipped {present} ping record(s) already in the database");
+        }
+        if unreadable > 0 {
+            println!("Skipped {unreadable} log line(s) that could not be read");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+pub mod db_args;
+pub mod db_command;
+pub mod db_import_args;
+
+pub use db_args::DbArgs;
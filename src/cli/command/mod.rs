@@ -1,5 +1,6 @@
 pub mod audit;
 mod command;
+pub mod db;
 pub mod home;
 pub mod run;
 pub mod sound;
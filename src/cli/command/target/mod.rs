@@ -0,0 +1,7 @@
+pub mod target_add_args;
+pub mod target_args;
+pub mod target_command;
+pub mod target_list_args;
+pub mod target_remove_args;
+
+pub use target_args::TargetArgs;
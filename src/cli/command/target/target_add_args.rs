@@ -0,0 +1,101 @@
+use crate::config::Config;
+use crate::config::DEFAULT_RECOVERY_PROBES;
+use crate::config::Target;
+use crate::config::TargetId;
+use crate::config::sanitize_label;
+use crate::ping::PingMode;
+use crate::ping::parse_destination;
+use clap::Args;
+use eyre::Result;
+use hcl::edit::structure::Body;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct TargetAddArgs {
+    /// Destination to ping (domain, IP, or URL)
+    pub value: String,
+    /// Optional target name; defaults to a sanitized version of the value
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Ping mode to use for this target
+    #[arg(long, default_value = "icmp")]
+    pub mode: PingMode,
+    /// Interval between pings expressed with humantime syntax, e.g. "1s" or "2m"
+    #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+    /// Tag to attach to this target, e.g. "isp" or "critical"; repeat for several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// Faster interval to use while the target is failing, e.g. "2s"
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub interval_on_failure: Option<Duration>,
+    /// Consecutive successful probes required before returning to the regular interval
+    #[arg(long, default_value_t = DEFAULT_RECOVERY_PROBES)]
+    pub recovery_probes: u32,
+    /// Availability objective as a percentage, e.g. "99.9", checked by `piing audit sla`
+    #[arg(long)]
+    pub slo: Option<f64>,
+    /// Largest random delay before the first probe, overriding the runtime `start_jitter`; "0s" probes right away
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub start_jitter: Option<Duration>,
+}
+
+impl TargetAddArgs {
+    /// # Errors
+    /// Returns an error if config operations fail
+    pub fn invoke(self) -> Result<()> {
+        if self.value.trim().is_empty() {
+            eyre::bail!("Target value cannot be empty");
+        }
+        if self.slo.is_some_and(|slo| slo <= 0.0 || slo >= 100.0) {
+            eyre::bail!("--slo must be a percentage between 0 and 100");
+        }
+
+        let requested_name = self
+            .name
+            .as_deref()
+            .map_or_else(|| sanitize_label(self.value.trim()), str::to_string);
+        let sanitized = sanitize_label(&requested_name);
+        if sanitized.is_empty() {
+            eyre::bail!("Unable to derive a valid target name");
+        }
+
+        if Config::current()?
+            .targets
+            .iter()
+            .any(|target| target.id.name.eq_ignore_ascii_case(&sanitized))
+        {
+            eyre::bail!("Target with name '{}' already exists", sanitized);
+        }
+
+        let file_path = Config::unique_file_path(&sanitized);
+        let target = Target {
+            id: TargetId {
+                file_path: file_path.clone(),
+                name: sanitized.clone(),
+            },
+            value: Arc::new(parse_destination(self.value.trim(), self.mode)),
+            mode: self.mode,
+            interval: self.interval,
+            tags: self.tags,
+            interval_on_failure: self.interval_on_failure,
+            recovery_probes: self.recovery_probes.max(1),
+            slo: self.slo,
+            start_jitter: self.start_jitter,
+        };
+        let body = Body::builder().block(target.block()).build();
+        Config::write_body(&file_path, &body)?;
+
+        println!(
+            "Added target '{}' ({}) with {} mode every {} (file: {})",
+            sanitized,
+            self.value.trim(),
+            self.mode.as_str(),
+            humantime::format_duration(self.interval),
+            file_path.display()
+        );
+
+        Ok(())
+    }
+}
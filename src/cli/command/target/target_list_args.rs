@@ -0,0 +1,65 @@
+use crate::config::Config;
+use clap::Args;
+use eyre::Result;
+
+#[derive(Debug, Default, Args)]
+pub struct TargetListArgs {
+    /// Only list targets carrying this tag; repeat to match any of several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+}
+
+impl TargetListArgs {
+    /// # Errors
+    /// Returns an error if config operations fail
+    pub fn invoke(self) -> Result<()> {
+        let snapshot = Config::current()?;
+        let targets: Vec<_> = snapshot
+            .targets
+            .iter()
+            .filter(|target| target.matches_tags(&self.tags))
+            .collect();
+
+        if targets.is_empty() {
+            if self.tags.is_empty() {
+                println!("No targets configured.");
+            } else {
+                println!("No targets tagged with {}.", self.tags.join(", "));
+            }
+        } else {
+            for target in targets {
+                let on_failure = target
+                    .interval_on_failure
+                    .map(|interval| {
+                        format!(
+                            " interval_on_failure={} recovery_probes={}",
+                            humantime::format_duration(interval),
+                            target.recovery_probes
+                        )
+                    })
+                    .unwrap_or_default();
+                let slo = target
+                    .slo
+                    .map(|slo| format!(" slo={slo}"))
+                    .unwrap_or_default();
+                let start_jitter = target
+                    .start_jitter
+                    .map(|jitter| format!(" start_jitter={}", humantime::format_duration(jitter)))
+                    .unwrap_or_default();
+                println!(
+                    "{:<20} {:<20} mode={} interval={}{}{}{} tags=[{}]",
+                    target.id.name,
+                    target.value.display,
+                    target.mode.as_str(),
+                    humantime::format_duration(target.interval),
+                    on_failure,
+                    slo,
+                    start_jitter,
+                    target.tags.join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
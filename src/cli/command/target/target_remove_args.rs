@@ -0,0 +1,109 @@
+use crate::config::Config;
+use clap::Args;
+use eyre::Context as _;
+use eyre::Result;
+use hcl::edit::structure::Body;
+use hcl::edit::structure::Structure;
+use std::fs;
+
+#[derive(Debug, Args)]
+pub struct TargetRemoveArgs {
+    /// Name of the target to remove
+    pub name: String,
+}
+
+impl TargetRemoveArgs {
+    /// # Errors
+    /// Returns an error if the target does not exist or its config file cannot be written
+    pub fn invoke(self) -> Result<()> {
+        let snapshot = Config::current()?;
+        let Some(target) = snapshot
+            .targets
+            .iter()
+            .find(|target| target.id.name.eq_ignore_ascii_case(self.name.trim()))
+        else {
+            eyre::bail!("No target named '{}'", self.name.trim());
+        };
+        let file_path = &target.id.file_path;
+        let Some(mut body) = snapshot.files.get(file_path).cloned() else {
+            eyre::bail!("Config file {} is not loaded", file_path.display());
+        };
+        if !remove_target_block(&mut body, &target.id.name) {
+            eyre::bail!(
+                "Target '{}' not found in {}",
+                target.id.name,
+                file_path.display()
+            );
+        }
+
+        // A file that only held this target goes away with it.
+        if body.is_empty() {
+            fs::remove_file(file_path).wrap_err_with(|| {
+                format!("Failed to remove config file: {}", file_path.display())
+            })?;
+        } else {
+            Config::write_body(file_path, &body)?;
+        }
+
+        println!(
+            "Removed target '{}' ({}) from {}",
+            target.id.name,
+            target.value.display,
+            file_path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Remove the `piing_target` block named `name` from a config file body,
+/// leaving every other block as it was. Returns false if there is none.
+fn remove_target_block(body: &mut Body, name: &str) -> bool {
+    let Some(index) = body.iter().position(|structure| {
+        let Structure::Block(block) = structure else {
+            return false;
+        };
+        let mut labels = block.labels.iter();
+        labels
+            .next()
+            .is_some_and(|label| label.as_str() == "piing_target")
+            && labels.next().is_some_and(|label| label.as_str() == name)
+    }) else {
+        return false;
+    };
+    body.remove(index);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removing_a_target_leaves_the_other_blocks_alone() {
+        let source = r#"resource "piing_target" "google_dns" {
+  value = "8.8.8.8"
+  mode  = "icmp"
+}
+
+resource "piing_target" "cloudflare" {
+  value = "1.1.1.1" # primary resolver
+  mode  = "icmp"
+}
+
+resource "piing_schedule" "nightly" {
+  days  = ["sat"]
+  start = "02:00"
+  end   = "03:00"
+}
+"#;
+        let mut body: Body = source.parse().unwrap();
+        assert!(remove_target_block(&mut body, "google_dns"));
+        let remaining = body.to_string();
+        assert!(!remaining.contains("google_dns"));
+        assert!(source.ends_with(remaining.trim_start()));
+
+        assert!(!remove_target_block(&mut body, "google_dns"));
+        assert!(!remove_target_block(&mut body, "nightly"));
+        assert_eq!(body.to_string(), remaining);
+    }
+}
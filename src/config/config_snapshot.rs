@@ -1,14 +1,22 @@
 use crate::config::log_latency::LatencyColouration;
 use crate::config::log_latency::build_latency_body;
 use crate::config::log_latency::decode_latency_coloration;
+use crate::config::logging_settings::LoggingSettings;
+use crate::config::logging_settings::decode_logging_settings;
 use crate::config::problem_sound::DEFAULT_PROBLEM_SOUND_PATH;
 use crate::config::problem_sound::ProblemSound;
 use crate::config::problem_sound::build_problem_sound_body;
 use crate::config::problem_sound::decode_problem_sound;
+use crate::config::runtime_settings::RuntimeSettings;
+use crate::config::runtime_settings::decode_runtime_settings;
+use crate::config::schedule::Schedule;
+use crate::config::schedule::decode_schedules;
 use crate::config::targets::Target;
 use crate::config::targets::decode_targets;
 use crate::config::vpn_criterion::VpnCriterion;
 use crate::config::vpn_criterion::decode_vpn_criteria;
+use chrono::DateTime;
+use chrono::Local;
 use chrono::Utc;
 use eyre::Context as _;
 use eyre::Result;
@@ -28,17 +36,27 @@ pub struct ConfigSnapshot {
     pub vpn_criteria: Vec<VpnCriterion>,
     pub latency_colouration: LatencyColouration,
     pub problem_sound: Arc<ProblemSound>,
+    pub schedules: Vec<Schedule>,
+    pub runtime: RuntimeSettings,
+    pub logging: LoggingSettings,
     pub snapshot_time: Instant,
 }
 
 impl ConfigSnapshot {
     #[must_use]
+    #[expect(
+        clippy::too_many_arguments,
+        reason = "takes the decoded settings of every block type"
+    )]
     pub fn new(
         files: BTreeMap<PathBuf, Body>,
         targets: Vec<Target>,
         vpn_criteria: Vec<VpnCriterion>,
         latency_colouration: LatencyColouration,
         problem_sound: Arc<ProblemSound>,
+        schedules: Vec<Schedule>,
+        runtime: RuntimeSettings,
+        logging: LoggingSettings,
     ) -> Self {
         Self {
             files,
@@ -46,21 +64,39 @@ impl ConfigSnapshot {
             vpn_criteria,
             latency_colouration,
             problem_sound,
+            schedules,
+            runtime,
+            logging,
             snapshot_time: Instant::now(),
         }
     }
 
+    /// Returns true if a maintenance window covering `target` is open at `when`.
+    #[must_use]
+    pub fn in_maintenance(&self, target: &Target, when: DateTime<Local>) -> bool {
+        self.schedules
+            .iter()
+            .any(|schedule| schedule.applies_to(target) && schedule.is_active_at(when))
+    }
+
     /// # Errors
     /// Returns an error if reading or parsing any config file fails
+    #[expect(
+        clippy::too_many_lines,
+        reason = "decodes every block type and writes defaults in one pass"
+    )]
     pub fn try_from_dir(dir: impl AsRef<Path>) -> Result<Self> {
         let dir = dir.as_ref();
         let mut files = BTreeMap::new();
         let mut targets = Vec::new();
         let mut vpn_criteria = Vec::new();
+        let mut schedules = Vec::new();
         let mut latency_rules = Vec::new();
         let mut latency_rules_found = false;
         let mut problem_sound: Option<ProblemSound> = None;
         let mut problem_sound_found = false;
+        let mut runtime: Option<RuntimeSettings> = None;
+        let mut logging: Option<LoggingSettings> = None;
 
         if dir.exists() {
             for entry in fs::read_dir(dir)? {
@@ -75,6 +111,7 @@ impl ConfigSnapshot {
                     .wrap_err_with(|| format!("Failed to parse config file: {}", path.display()))?;
                 targets.extend(decode_targets(&path, &body)?);
                 vpn_criteria.extend(decode_vpn_criteria(&path, &body)?);
+                schedules.extend(decode_schedules(&path, &body)?);
                 let mut decoded = decode_latency_coloration(&path, &body)?;
                 if !decoded.is_empty() {
                     latency_rules_found = true;
@@ -89,6 +126,22 @@ impl ConfigSnapshot {
                     problem_sound = Some(sound);
                     problem_sound_found = true;
                 }
+                if let Some(settings) = decode_runtime_settings(&path, &body)? {
+                    if runtime.is_some() {
+                        return Err(eyre::eyre!(
+                            "Multiple piing_runtime blocks found; only one is allowed"
+                        ));
+                    }
+                    runtime = Some(settings);
+                }
+                if let Some(settings) = decode_logging_settings(&path, &body)? {
+                    if logging.is_some() {
+                        return Err(eyre::eyre!(
+                            "Multiple piing_logging blocks found; only one is allowed"
+                        ));
+                    }
+                    logging = Some(settings);
+                }
                 files.insert(path, body);
             }
         }
@@ -151,6 +204,9 @@ impl ConfigSnapshot {
             vpn_criteria,
             latency_colouration,
             resolved_problem_sound,
+            schedules,
+            runtime.unwrap_or_default(),
+            logging.unwrap_or_default(),
         ))
     }
 }
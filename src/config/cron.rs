@@ -0,0 +1,180 @@
+use chrono::Datelike;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use eyre::Result;
+use std::fmt;
+use std::str::FromStr;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week, such as `"30 2 * * sat,sun"`.
+///
+/// Each field takes `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`)
+/// and comma-separated lists of those. Months and days of the week may also
+/// be given by their three-letter English names, and Sunday is `0` or `7`.
+/// As in cron, when both the day of month and the day of week are
+/// restricted, a time matches if either does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpression {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronExpression {
+    /// Returns true if the expression fires at the minute of `when`.
+    #[must_use]
+    pub fn matches(&self, when: NaiveDateTime) -> bool {
+        let day_of_month = has(self.days_of_month, when.day());
+        let day_of_week = has(self.days_of_week, when.weekday().num_days_from_sunday());
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+        day && has(self.minutes, when.minute())
+            && has(self.hours, when.hour())
+            && has(self.months, when.month())
+    }
+}
+
+impl FromStr for CronExpression {
+    type Err = eyre::Report;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let fields: Vec<&str> = source.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            eyre::bail!(
+                "Cron expression '{source}' must have five fields: minute, hour, day of month, month and day of week"
+            );
+        };
+        let mut weekdays = parse_field(day_of_week, 0, 7, &WEEKDAYS)?;
+        // Sunday may be written as 7.
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            days_of_week: weekdays,
+            any_day_of_month: day_of_month == "*",
+            any_day_of_week: day_of_week == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse one field into the set of values it selects, as bits. Names count
+/// from `min`, so `names[0]` is `min`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |raw: &str| -> Result<u32> {
+        let value = match names.iter().position(|name| name.eq_ignore_ascii_case(raw)) {
+            Some(index) => min + u32::try_from(index)?,
+            None => raw
+                .parse()
+                .map_err(|_| eyre::eyre!("Invalid cron value '{raw}' in '{field}'"))?,
+        };
+        if !(min..=max).contains(&value) {
+            eyre::bail!("Cron value '{raw}' in '{field}' is outside {min}-{max}");
+        }
+        Ok(value)
+    };
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| eyre::eyre!("Invalid cron step '{step}' in '{field}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (first, last) = if range == "*" {
+            (min, max)
+        } else if let Some((first, last)) = range.split_once('-') {
+            (value(first)?, value(last)?)
+        } else {
+            let first = value(range)?;
+            // `5/10` runs from 5 to the end of the field.
+            (first, if part.contains('/') { max } else { first })
+        };
+        if first > last {
+            eyre::bail!("Cron range '{range}' in '{field}' runs backwards");
+        }
+        for value in (first..=last).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2025-12-01 is a Monday.
+        NaiveDate::from_ymd_opt(2025, 12, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn fields_select_minutes_hours_and_days() {
+        let cron: CronExpression = "*/15 2-3 * * sat,sun".parse().unwrap();
+        assert!(cron.matches(at(6, 2, 45)));
+        assert!(cron.matches(at(7, 3, 0)));
+        assert!(!cron.matches(at(6, 2, 50)));
+        assert!(!cron.matches(at(6, 4, 0)));
+        assert!(!cron.matches(at(1, 2, 0)));
+
+        let sunday: CronExpression = "0 0 * * 7".parse().unwrap();
+        assert!(sunday.matches(at(7, 0, 0)));
+    }
+
+    #[test]
+    fn restricted_day_of_month_and_week_match_either() {
+        let cron: CronExpression = "0 9 1 * fri".parse().unwrap();
+        assert!(cron.matches(at(1, 9, 0)));
+        assert!(cron.matches(at(5, 9, 0)));
+        assert!(!cron.matches(at(2, 9, 0)));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for source in [
+            "0 2 * *",
+            "60 * * * *",
+            "0 2 * * fun",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(source.parse::<CronExpression>().is_err(), "{source}");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::config::targets::read_optional_bool_attribute;
+use crate::config::targets::read_optional_duration_attribute;
+use crate::config::targets::read_optional_u32_attribute;
+use eyre::Result;
+use hcl::edit::structure::Body;
+use hcl::edit::structure::Structure;
+use std::path::Path;
+use std::time::Duration;
+
+/// How the log files of `piing run` are rotated, kept and compressed, and
+/// whether ping results also go to the ping database, declared with at most
+/// one `piing_logging` block.
+///
+/// ```terraform
+/// resource "piing_logging" "logging" {
+///   rotate_size_mb = 100
+///   rotate_daily = true
+///   retention = "90d"
+///   max_files = 200
+///   compress = true
+///   sqlite = true
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggingSettings {
+    /// Start a new log file once the current one reaches this many bytes;
+    /// `None` means no size limit.
+    pub rotate_size: Option<u64>,
+    /// Start a new log file when the local date changes.
+    pub rotate_daily: bool,
+    /// Delete log files last written longer ago than this; `None` keeps them.
+    pub retention: Option<Duration>,
+    /// Keep at most this many log files, deleting the oldest; `None` means no limit.
+    pub max_files: Option<usize>,
+    /// Gzip rotated log files, and idle files of earlier launches, in the
+    /// background; off unless turned on, as it rewrites existing logs.
+    pub compress: bool,
+    /// Also keep ping records, config changes and incidents in the ping
+    /// database, `$PIING_HOME/piing.sqlite3`.
+    pub sqlite: bool,
+}
+
+/// Size at which log files are rotated when none is configured.
+pub const DEFAULT_ROTATE_SIZE_MB: u32 = 100;
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            rotate_size: Some(u64::from(DEFAULT_ROTATE_SIZE_MB) * 1024 * 1024),
+            rotate_daily: true,
+            retention: None,
+            max_files: None,
+            compress: false,
+            sqlite: false,
+        }
+    }
+}
+
+/// Decode at most one `piing_logging` block from the provided body.
+///
+/// # Errors
+/// Returns an error if more than one block is present or attributes are malformed
+pub fn decode_logging_settings(file_path: &Path, body: &Body) -> Result<Option<LoggingSettings>> {
+    let mut settings: Option<LoggingSettings> = None;
+    for structure in body {
+        let Structure::Block(block) = structure else {
+            continue;
+        };
+        let mut labels = block.labels.iter();
+        let Some(resource_type) = labels.next() else {
+            continue;
+        };
+        if resource_type.as_str() != "piing_logging" {
+            continue;
+        }
+        if settings.is_some() {
+            return Err(eyre::eyre!(
+                "Multiple piing_logging blocks found; only one is allowed"
+            ));
+        }
+        let name = labels
+            .next()
+            .map_or("piing_logging", |label| label.as_str());
+
+        let defaults = LoggingSettings::default();
+        // Zero turns size rotation off.
+        let rotate_size =
+            match read_optional_u32_attribute(block, "rotate_size_mb", file_path, name)? {
+                Some(0) => None,
+                Some(megabytes) => Some(u64::from(megabytes) * 1024 * 1024),
+                None => defaults.rotate_size,
+            };
+        let rotate_daily = read_optional_bool_attribute(block, "rotate_daily", file_path, name)?
+            .unwrap_or(defaults.rotate_daily);
+        let retention = read_optional_duration_attribute(block, "retention", file_path, name)?
+            .filter(|retention| !retention.is_zero());
+        let max_files = read_optional_u32_attribute(block, "max_files", file_path, name)?
+            .filter(|max| *max > 0)
+            .map(|max| max as usize);
+        let compress = read_optional_bool_attribute(block, "compress", file_path, name)?
+            .unwrap_or(defaults.compress);
+        let sqlite = read_optional_bool_attribute(block, "sqlite", file_path, name)?
+            .unwrap_or(defaults.sqlite);
+
+        settings = Some(LoggingSettings {
+            rotate_size,
+            rotate_daily,
+            retention,
+            max_files,
+            compress,
+            sqlite,
+        });
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::edit::parser::parse_body;
+
+    #[test]
+    fn logging_block_overrides_defaults() {
+        let body = parse_body(
+            r#"
+resource "piing_logging" "logging" {
+  rotate_size_mb = 0
+  retention = "30d"
+  max_files = 50
+  compress = false
+  sqlite = true
+}
+"#,
+        )
+        .unwrap();
+        let settings = decode_logging_settings(Path::new("logging.piing_hcl"), &body)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            settings,
+            LoggingSettings {
+                rotate_size: None,
+                rotate_daily: true,
+                retention: Some(Duration::from_hours(30 * 24)),
+                max_files: Some(50),
+                compress: false,
+                sqlite: true,
+            }
+        );
+    }
+}
@@ -1,13 +1,21 @@
 mod config_snapshot;
+mod cron;
 mod global;
 mod log_latency;
+mod logging_settings;
 mod problem_sound;
+mod runtime_settings;
+mod schedule;
 mod targets;
 mod vpn_criterion;
 
 pub use config_snapshot::*;
+pub use cron::*;
 pub use global::*;
 pub use log_latency::*;
+pub use logging_settings::*;
 pub use problem_sound::*;
+pub use runtime_settings::*;
+pub use schedule::*;
 pub use targets::*;
 pub use vpn_criterion::*;
@@ -1,3 +1,5 @@
+use crate::config::targets::read_optional_string_list_attribute;
+use crate::config::targets::tags_match;
 use eyre::Context as _;
 use eyre::Result;
 use hcl::edit::Decorated;
@@ -66,6 +68,8 @@ pub struct ProblemSound {
     path: PathBuf,
     volume: f32,
     mode: SoundMode,
+    /// Only failures of targets carrying one of these tags trigger the sound; empty means all.
+    tags: Vec<String>,
     /// The config file where this problem sound is defined, if known.
     source_file: Option<PathBuf>,
 }
@@ -77,6 +81,7 @@ impl ProblemSound {
             path,
             volume,
             mode: SoundMode::default(),
+            tags: Vec::new(),
             source_file: None,
         }
     }
@@ -87,10 +92,17 @@ impl ProblemSound {
             path,
             volume,
             mode,
+            tags: Vec::new(),
             source_file: None,
         }
     }
 
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     #[must_use]
     pub fn with_source(mut self, source_file: PathBuf) -> Self {
         self.source_file = Some(source_file);
@@ -112,6 +124,17 @@ impl ProblemSound {
         self.mode
     }
 
+    #[must_use]
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns true if failures of a target with the given tags should trigger this sound.
+    #[must_use]
+    pub fn applies_to(&self, target_tags: &[String]) -> bool {
+        tags_match(target_tags, &self.tags)
+    }
+
     #[must_use]
     pub fn source_file(&self) -> Option<&Path> {
         self.source_file.as_deref()
@@ -137,6 +160,9 @@ pub fn decode_problem_sound(file_path: &Path, body: &Body) -> Result<Option<Prob
         if resource_type.as_str() != "piing_problem_sound" {
             continue;
         }
+        let name = labels
+            .next()
+            .map_or("piing_problem_sound", |label| label.as_str());
 
         // Enforce 0-1 blocks across all files.
         if sound.is_some() {
@@ -198,8 +224,12 @@ pub fn decode_problem_sound(file_path: &Path, body: &Body) -> Result<Option<Prob
             None => SoundMode::default(),
         };
 
+        let tags = read_optional_string_list_attribute(block, "tags", file_path, name)?;
+
         sound = Some(
-            ProblemSound::with_mode(path, volume, mode).with_source(file_path.to_path_buf()),
+            ProblemSound::with_mode(path, volume, mode)
+                .with_tags(tags)
+                .with_source(file_path.to_path_buf()),
         );
     }
 
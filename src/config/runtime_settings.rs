@@ -0,0 +1,168 @@
+use crate::config::targets::read_optional_duration_attribute;
+use crate::config::targets::read_optional_positive_number_attribute;
+use crate::config::targets::read_optional_string_attribute;
+use crate::config::targets::read_optional_u32_attribute;
+use eyre::Result;
+use hcl::edit::structure::Body;
+use hcl::edit::structure::Structure;
+use std::path::Path;
+use std::time::Duration;
+
+/// Settings for the probe scheduler, declared with at most one `piing_runtime` block.
+///
+/// ```terraform
+/// resource "piing_runtime" "runtime" {
+///   start_jitter = "5s"
+///   max_concurrent_probes = 4
+///   resume_grace = "30s"
+///   latency_anomaly_z = 3
+///   latency_anomaly_duration = "2m"
+///   latency_baseline_alpha = 0.05
+///   machine_id = "front-desk"
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RuntimeSettings {
+    /// Upper bound of the random phase offset given to each target's first probe,
+    /// so targets sharing an interval do not all fire at the same instant.
+    pub start_jitter: Duration,
+    /// Maximum number of probes allowed to run at once; `None` means unlimited.
+    pub max_concurrent_probes: Option<usize>,
+    /// How long after a detected system resume failures are tagged
+    /// `resume_grace` instead of alerting; zero disables the grace period.
+    pub resume_grace: Duration,
+    pub latency_anomaly: LatencyAnomalySettings,
+    /// Name logged with every ping to tell machines apart when their logs are
+    /// audited together; `None` uses the computer name.
+    pub machine_id: Option<String>,
+}
+
+impl RuntimeSettings {
+    /// The configured machine identifier, or else the computer name.
+    #[must_use]
+    pub fn machine(&self) -> String {
+        self.machine_id
+            .clone()
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Grace period applied after a system resume when none is configured.
+pub const DEFAULT_RESUME_GRACE: Duration = Duration::from_secs(30);
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            start_jitter: Duration::ZERO,
+            max_concurrent_probes: None,
+            resume_grace: DEFAULT_RESUME_GRACE,
+            latency_anomaly: LatencyAnomalySettings::default(),
+            machine_id: None,
+        }
+    }
+}
+
+/// When latency counts as anomalous against each target's rolling baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyAnomalySettings {
+    /// How many standard deviations above the baseline latency must be.
+    pub z: f64,
+    /// How long latency must stay above the threshold before it is reported.
+    pub duration: Duration,
+    /// Weight of each new ping in the exponentially weighted baseline, in (0, 1].
+    pub alpha: f64,
+}
+
+impl Default for LatencyAnomalySettings {
+    fn default() -> Self {
+        Self {
+            z: 3.0,
+            duration: Duration::from_mins(2),
+            alpha: 0.05,
+        }
+    }
+}
+
+/// Decode at most one `piing_runtime` block from the provided body.
+///
+/// # Errors
+/// Returns an error if more than one block is present or attributes are malformed
+pub fn decode_runtime_settings(file_path: &Path, body: &Body) -> Result<Option<RuntimeSettings>> {
+    let mut settings: Option<RuntimeSettings> = None;
+    for structure in body {
+        let Structure::Block(block) = structure else {
+            continue;
+        };
+        let mut labels = block.labels.iter();
+        let Some(resource_type) = labels.next() else {
+            continue;
+        };
+        if resource_type.as_str() != "piing_runtime" {
+            continue;
+        }
+        if settings.is_some() {
+            return Err(eyre::eyre!(
+                "Multiple piing_runtime blocks found; only one is allowed"
+            ));
+        }
+        let name = labels
+            .next()
+            .map_or("piing_runtime", |label| label.as_str());
+
+        let start_jitter =
+            read_optional_duration_attribute(block, "start_jitter", file_path, name)?
+                .unwrap_or_default();
+        let max_concurrent_probes =
+            read_optional_u32_attribute(block, "max_concurrent_probes", file_path, name)?
+                .filter(|max| *max > 0)
+                .map(|max| max as usize);
+        let resume_grace =
+            read_optional_duration_attribute(block, "resume_grace", file_path, name)?
+                .unwrap_or(DEFAULT_RESUME_GRACE);
+
+        let defaults = LatencyAnomalySettings::default();
+        let latency_anomaly = LatencyAnomalySettings {
+            z: read_optional_positive_number_attribute(
+                block,
+                "latency_anomaly_z",
+                file_path,
+                name,
+            )?
+            .unwrap_or(defaults.z),
+            duration: read_optional_duration_attribute(
+                block,
+                "latency_anomaly_duration",
+                file_path,
+                name,
+            )?
+            .unwrap_or(defaults.duration),
+            alpha: read_optional_positive_number_attribute(
+                block,
+                "latency_baseline_alpha",
+                file_path,
+                name,
+            )?
+            .unwrap_or(defaults.alpha),
+        };
+        if latency_anomaly.alpha > 1.0 {
+            return Err(eyre::eyre!(
+                "Attribute 'latency_baseline_alpha' must be at most 1 in {} -> {name}",
+                file_path.display()
+            ));
+        }
+
+        let machine_id = read_optional_string_attribute(block, "machine_id", file_path, name)?
+            .map(|machine| machine.trim().to_string())
+            .filter(|machine| !machine.is_empty());
+
+        settings = Some(RuntimeSettings {
+            start_jitter,
+            max_concurrent_probes,
+            resume_grace,
+            latency_anomaly,
+            machine_id,
+        });
+    }
+    Ok(settings)
+}
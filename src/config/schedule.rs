@@ -0,0 +1,334 @@
+use crate::config::cron::CronExpression;
+use crate::config::targets::Target;
+use crate::config::targets::read_optional_duration_attribute;
+use crate::config::targets::read_optional_string_attribute;
+use crate::config::targets::read_optional_string_list_attribute;
+use crate::config::targets::tags_match;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::Local;
+use chrono::NaiveTime;
+use chrono::Timelike;
+use chrono::Weekday;
+use eyre::Context;
+use eyre::Result;
+use hcl::edit::structure::Block;
+use hcl::edit::structure::Body;
+use hcl::edit::structure::Structure;
+use std::path::Path;
+
+/// A recurring maintenance window (quiet hours) declared with a `piing_schedule` block.
+///
+/// While a window is active, pings of the targets it applies to are still performed and
+/// logged (marked `maintenance = true`) but they do not raise any alerts.
+///
+/// ```terraform
+/// resource "piing_schedule" "nightly_backup" {
+///   days = ["sat", "sun"]
+///   start = "22:00"
+///   end = "06:00"
+///   targets = ["google_dns"]
+/// }
+///
+/// resource "piing_schedule" "patch_night" {
+///   cron = "0 2 * * tue"
+///   duration = "90m"
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub name: String,
+    pub window: ScheduleWindow,
+    /// Target names the window applies to; empty (together with `tags`) means every target.
+    pub targets: Vec<String>,
+    /// Target tags the window applies to.
+    pub tags: Vec<String>,
+}
+
+/// When a maintenance window is open.
+#[derive(Debug, Clone)]
+pub enum ScheduleWindow {
+    /// From `start` to `end` on the given days.
+    TimeOfDay {
+        /// Days on which the window opens; empty means every day.
+        days: Vec<Weekday>,
+        start: NaiveTime,
+        /// End of the window; a value at or before `start` wraps past midnight.
+        end: NaiveTime,
+    },
+    /// For `duration` from each minute the cron expression fires at.
+    Cron {
+        expression: CronExpression,
+        duration: Duration,
+    },
+}
+
+/// The longest a cron window may stay open, which bounds the minutes
+/// looked back over to find the time it opened.
+const MAX_CRON_DURATION: Duration = Duration::days(7);
+
+impl Schedule {
+    /// Returns true if the window is open at the given local time.
+    #[must_use]
+    pub fn is_active_at(&self, when: DateTime<Local>) -> bool {
+        match &self.window {
+            ScheduleWindow::TimeOfDay { days, start, end } => {
+                let opens_on = |day: Weekday| days.is_empty() || days.contains(&day);
+                let time = when.time();
+                let today = when.weekday();
+                if start < end {
+                    opens_on(today) && time >= *start && time < *end
+                } else {
+                    // The window wraps past midnight (equal bounds span a full 24
+                    // hours), so the early part belongs to the previous day.
+                    (opens_on(today) && time >= *start) || (opens_on(today.pred()) && time < *end)
+                }
+            }
+            ScheduleWindow::Cron {
+                expression,
+                duration,
+            } => {
+                let now = when.naive_local();
+                let Some(mut fired) = now.with_second(0).and_then(|time| time.with_nanosecond(0))
+                else {
+                    return false;
+                };
+                while now - fired < *duration {
+                    if expression.matches(fired) {
+                        return true;
+                    }
+                    fired -= Duration::minutes(1);
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns true if the window applies to the given target.
+    #[must_use]
+    pub fn applies_to(&self, target: &Target) -> bool {
+        if self.targets.is_empty() && self.tags.is_empty() {
+            return true;
+        }
+        self.targets
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&target.id.name))
+            || (!self.tags.is_empty() && tags_match(&target.tags, &self.tags))
+    }
+}
+
+/// Decode all `piing_schedule` blocks found in a config file body.
+///
+/// # Errors
+/// Returns an error if attributes are malformed
+pub fn decode_schedules(file_path: &Path, body: &Body) -> Result<Vec<Schedule>> {
+    let mut schedules = Vec::new();
+    for structure in body {
+        let Structure::Block(block) = structure else {
+            continue;
+        };
+        let mut labels = block.labels.iter();
+        let Some(resource_type) = labels.next() else {
+            continue;
+        };
+        if resource_type.as_str() != "piing_schedule" {
+            continue;
+        }
+        let name = labels
+            .next()
+            .map_or_else(|| "piing_schedule".to_string(), |label| label.to_string());
+
+        let window = match read_optional_string_attribute(block, "cron", file_path, &name)? {
+            Some(cron) => read_cron_window(block, &cron, file_path, &name)?,
+            None => read_time_of_day_window(block, file_path, &name)?,
+        };
+        let targets = read_optional_string_list_attribute(block, "targets", file_path, &name)?;
+        let tags = read_optional_string_list_attribute(block, "tags", file_path, &name)?;
+
+        schedules.push(Schedule {
+            name,
+            window,
+            targets,
+            tags,
+        });
+    }
+    Ok(schedules)
+}
+
+/// Read the `days`, `start` and `end` of a weekday and time-of-day window.
+fn read_time_of_day_window(block: &Block, file_path: &Path, name: &str) -> Result<ScheduleWindow> {
+    let days = read_optional_string_list_attribute(block, "days", file_path, name)?
+        .iter()
+        .map(|day| {
+            day.parse::<Weekday>().map_err(|_| {
+                eyre::eyre!(
+                    "Invalid day '{day}' in {} -> {name}; expected names such as 'mon' or 'monday'",
+                    file_path.display()
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ScheduleWindow::TimeOfDay {
+        days,
+        start: read_time_attribute(block, "start", file_path, name)?,
+        end: read_time_attribute(block, "end", file_path, name)?,
+    })
+}
+
+/// Read a window opened by the `cron` expression for the required `duration`.
+fn read_cron_window(
+    block: &Block,
+    cron: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<ScheduleWindow> {
+    if let Some(key) = ["days", "start", "end"]
+        .into_iter()
+        .find(|key| block.body.get_attribute(key).is_some())
+    {
+        eyre::bail!(
+            "Attribute '{key}' cannot be combined with 'cron' in {} -> {name}",
+            file_path.display()
+        );
+    }
+    let expression = cron
+        .parse()
+        .wrap_err_with(|| format!("Invalid 'cron' in {} -> {name}", file_path.display()))?;
+    let duration = read_optional_duration_attribute(block, "duration", file_path, name)?
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Missing attribute 'duration' in {} -> {name}",
+                file_path.display()
+            )
+        })?;
+    let duration = Duration::from_std(duration)
+        .ok()
+        .filter(|duration| *duration >= Duration::minutes(1) && *duration <= MAX_CRON_DURATION)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Attribute 'duration' must be between one minute and seven days in {} -> {name}",
+                file_path.display()
+            )
+        })?;
+    Ok(ScheduleWindow::Cron {
+        expression,
+        duration,
+    })
+}
+
+/// Read a required `HH:MM` or `HH:MM:SS` attribute.
+fn read_time_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<NaiveTime> {
+    let attribute = block.body.get_attribute(key).ok_or_else(|| {
+        eyre::eyre!(
+            "Missing attribute '{key}' in {} -> {name}",
+            file_path.display()
+        )
+    })?;
+    let raw = attribute.value.as_str().ok_or_else(|| {
+        eyre::eyre!(
+            "Attribute '{key}' must be a string in {} -> {name}",
+            file_path.display()
+        )
+    })?;
+    NaiveTime::parse_from_str(raw, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(raw, "%H:%M:%S"))
+        .map_err(|_| {
+            eyre::eyre!(
+                "Invalid time '{raw}' for '{key}' in {} -> {name}; expected HH:MM",
+                file_path.display()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule(days: Vec<Weekday>, start: &str, end: &str) -> Schedule {
+        Schedule {
+            name: "test".to_string(),
+            window: ScheduleWindow::TimeOfDay {
+                days,
+                start: NaiveTime::parse_from_str(start, "%H:%M").unwrap(),
+                end: NaiveTime::parse_from_str(end, "%H:%M").unwrap(),
+            },
+            targets: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        // 2025-12-01 is a Monday.
+        Local
+            .with_ymd_and_hms(2025, 12, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn same_day_window() {
+        let window = schedule(vec![Weekday::Mon], "09:00", "10:00");
+        assert!(window.is_active_at(at(1, 9, 30)));
+        assert!(!window.is_active_at(at(1, 10, 0)));
+        assert!(!window.is_active_at(at(2, 9, 30)));
+    }
+
+    #[test]
+    fn window_wraps_past_midnight() {
+        let window = schedule(vec![Weekday::Mon], "22:00", "06:00");
+        assert!(window.is_active_at(at(1, 23, 0)));
+        assert!(window.is_active_at(at(2, 5, 59)));
+        assert!(!window.is_active_at(at(1, 5, 0)));
+        assert!(!window.is_active_at(at(2, 22, 30)));
+    }
+
+    #[test]
+    fn missing_end_is_an_error() {
+        let body = hcl::edit::parser::parse_body(
+            "resource \"piing_schedule\" \"nightly\" {\n  start = \"22:00\"\n}\n",
+        )
+        .unwrap();
+        let error = decode_schedules(Path::new("schedule.piing_hcl"), &body).unwrap_err();
+        assert!(error.to_string().contains("Missing attribute 'end'"));
+    }
+
+    #[test]
+    fn equal_bounds_cover_whole_day() {
+        let window = schedule(vec![Weekday::Sun], "00:00", "00:00");
+        assert!(window.is_active_at(at(7, 12, 0)));
+        assert!(!window.is_active_at(at(8, 12, 0)));
+    }
+
+    #[test]
+    fn cron_window_stays_open_for_its_duration() {
+        let body = hcl::edit::parser::parse_body(
+            "resource \"piing_schedule\" \"patch_night\" {\n  cron = \"30 23 * * mon\"\n  duration = \"1h\"\n}\n",
+        )
+        .unwrap();
+        let schedules = decode_schedules(Path::new("schedule.piing_hcl"), &body).unwrap();
+        let window = &schedules[0];
+        assert!(!window.is_active_at(at(1, 23, 29)));
+        assert!(window.is_active_at(at(1, 23, 30)));
+        assert!(window.is_active_at(at(2, 0, 29)));
+        assert!(!window.is_active_at(at(2, 0, 30)));
+        assert!(!window.is_active_at(at(8, 23, 0)));
+    }
+
+    #[test]
+    fn cron_needs_a_duration_and_no_time_of_day() {
+        for source in [
+            "resource \"piing_schedule\" \"x\" {\n  cron = \"0 2 * * *\"\n}\n",
+            "resource \"piing_schedule\" \"x\" {\n  cron = \"0 2 * * *\"\n  duration = \"1h\"\n  start = \"02:00\"\n}\n",
+            "resource \"piing_schedule\" \"x\" {\n  cron = \"0 2 * *\"\n  duration = \"1h\"\n}\n",
+        ] {
+            let body = hcl::edit::parser::parse_body(source).unwrap();
+            assert!(decode_schedules(Path::new("schedule.piing_hcl"), &body).is_err());
+        }
+    }
+}
@@ -22,53 +22,113 @@ pub struct TargetId {
     pub name: String,
 }
 
+/// Number of consecutive successful probes required before a failing target
+/// returns from `interval_on_failure` to its regular `interval`.
+pub const DEFAULT_RECOVERY_PROBES: u32 = 3;
+
 #[derive(Debug, Clone)]
 pub struct Target {
     pub id: TargetId,
     pub value: Arc<Destination>,
     pub mode: PingMode,
     pub interval: Duration,
+    pub tags: Vec<String>,
+    /// Faster cadence used while the target is failing, so outages are measured precisely.
+    pub interval_on_failure: Option<Duration>,
+    /// Consecutive successes needed before leaving `interval_on_failure`.
+    pub recovery_probes: u32,
+    /// Availability objective as a percentage, such as `99.9`, checked by `piing audit sla`.
+    pub slo: Option<f64>,
+    /// Bound of the random phase offset of this target's first probe,
+    /// overriding the `piing_runtime` block's `start_jitter`.
+    pub start_jitter: Option<Duration>,
 }
 
 impl Target {
     #[must_use]
     pub fn block(&self) -> Block {
-        build_target_block(&self.id.name, &self.value, self.mode, self.interval)
+        let mut block = Block::builder(Ident::new("resource"))
+            .label("piing_target")
+            .label(self.id.name.as_str())
+            .build();
+        block.body = self.build_body();
+        block
+    }
+
+    /// Returns true when `filter` is empty or this target carries at least one of its tags.
+    #[must_use]
+    pub fn matches_tags(&self, filter: &[String]) -> bool {
+        tags_match(&self.tags, filter)
+    }
+
+    fn build_body(&self) -> Body {
+        let mut body = Body::builder();
+        body = body.attribute(Attribute::new(
+            Decorated::new(Ident::new("value")).decorated(("  ", " ")),
+            Expression::String(Decorated::new(self.value.display.clone())),
+        ));
+        body = body.attribute(Attribute::new(
+            Decorated::new(Ident::new("mode")).decorated(("  ", " ")),
+            Expression::String(Decorated::new(self.mode.as_str().to_string())),
+        ));
+        body = body.attribute(Attribute::new(
+            Decorated::new(Ident::new("interval")).decorated(("  ", " ")),
+            Expression::String(Decorated::new(
+                humantime::format_duration(self.interval).to_string(),
+            )),
+        ));
+        if !self.tags.is_empty() {
+            body = body.attribute(Attribute::new(
+                Decorated::new(Ident::new("tags")).decorated(("  ", " ")),
+                Expression::Array(
+                    self.tags
+                        .iter()
+                        .map(|tag| Expression::String(Decorated::new(tag.clone())))
+                        .collect(),
+                ),
+            ));
+        }
+        if let Some(interval_on_failure) = self.interval_on_failure {
+            body = body.attribute(Attribute::new(
+                Decorated::new(Ident::new("interval_on_failure")).decorated(("  ", " ")),
+                Expression::String(Decorated::new(
+                    humantime::format_duration(interval_on_failure).to_string(),
+                )),
+            ));
+        }
+        if self.recovery_probes != DEFAULT_RECOVERY_PROBES {
+            body = body.attribute(Attribute::new(
+                Decorated::new(Ident::new("recovery_probes")).decorated(("  ", " ")),
+                Expression::from(self.recovery_probes),
+            ));
+        }
+        if let Some(slo) = self.slo {
+            body = body.attribute(Attribute::new(
+                Decorated::new(Ident::new("slo")).decorated(("  ", " ")),
+                Expression::from(slo),
+            ));
+        }
+        if let Some(start_jitter) = self.start_jitter {
+            body = body.attribute(Attribute::new(
+                Decorated::new(Ident::new("start_jitter")).decorated(("  ", " ")),
+                Expression::String(Decorated::new(
+                    humantime::format_duration(start_jitter).to_string(),
+                )),
+            ));
+        }
+        body.build()
     }
 }
 
+/// Returns true when `filter` is empty or `tags` contains at least one of its entries.
+///
+/// Tags are compared case-insensitively, matching how target names are compared.
 #[must_use]
-pub fn build_target_block(
-    name: &str,
-    value: &Destination,
-    mode: PingMode,
-    interval: Duration,
-) -> Block {
-    let mut block = Block::builder(Ident::new("resource"))
-        .label("piing_target")
-        .label(name)
-        .build();
-    block.body = build_body(value.display.as_str(), mode, interval);
-    block
-}
-
-fn build_body(value: &str, mode: PingMode, interval: Duration) -> Body {
-    let mut body = Body::builder();
-    body = body.attribute(Attribute::new(
-        Decorated::new(Ident::new("value")).decorated(("  ", " ")),
-        Expression::String(Decorated::new(value.to_string())),
-    ));
-    body = body.attribute(Attribute::new(
-        Decorated::new(Ident::new("mode")).decorated(("  ", " ")),
-        Expression::String(Decorated::new(mode.as_str().to_string())),
-    ));
-    body = body.attribute(Attribute::new(
-        Decorated::new(Ident::new("interval")).decorated(("  ", " ")),
-        Expression::String(Decorated::new(
-            humantime::format_duration(interval).to_string(),
-        )),
-    ));
-    body.build()
+pub fn tags_match(tags: &[String], filter: &[String]) -> bool {
+    filter.is_empty()
+        || filter
+            .iter()
+            .any(|wanted| tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)))
 }
 
 /// # Errors
@@ -103,6 +163,16 @@ pub fn decode_targets(file_path: &Path, body: &Body) -> Result<Vec<Target>> {
                 file_path.display()
             )
         })?;
+        let tags = read_optional_string_list_attribute(&block, "tags", file_path, &name)?;
+        let interval_on_failure =
+            read_optional_duration_attribute(&block, "interval_on_failure", file_path, &name)?;
+        let recovery_probes =
+            read_optional_u32_attribute(&block, "recovery_probes", file_path, &name)?
+                .unwrap_or(DEFAULT_RECOVERY_PROBES)
+                .max(1);
+        let slo = read_optional_percentage_attribute(&block, "slo", file_path, &name)?;
+        let start_jitter =
+            read_optional_duration_attribute(&block, "start_jitter", file_path, &name)?;
         let destination = parse_destination(&value, mode);
         targets.push(Target {
             id: TargetId {
@@ -112,6 +182,11 @@ pub fn decode_targets(file_path: &Path, body: &Body) -> Result<Vec<Target>> {
             value: Arc::new(destination),
             mode,
             interval,
+            tags,
+            interval_on_failure,
+            recovery_probes,
+            slo,
+            start_jitter,
         });
     }
     Ok(targets)
@@ -140,6 +215,183 @@ fn read_string_attribute(block: &Block, key: &str, file_path: &Path, name: &str)
         })
 }
 
+/// Read an optional string attribute.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a string
+pub fn read_optional_string_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<String>> {
+    if block.body.get_attribute(key).is_none() {
+        return Ok(None);
+    }
+    read_string_attribute(block, key, file_path, name).map(Some)
+}
+
+/// Read an optional humantime duration attribute such as `"5s"`.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a valid duration string
+pub fn read_optional_duration_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<Duration>> {
+    if block.body.get_attribute(key).is_none() {
+        return Ok(None);
+    }
+    let raw = read_string_attribute(block, key, file_path, name)?;
+    humantime::parse_duration(&raw).map(Some).wrap_err_with(|| {
+        format!(
+            "Invalid duration '{raw}' for '{key}' in {} -> {name}",
+            file_path.display()
+        )
+    })
+}
+
+/// Read an optional non-negative integer attribute, accepting numeric or string values.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a non-negative integer
+pub fn read_optional_u32_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<u32>> {
+    let Some(attribute) = block.body.get_attribute(key) else {
+        return Ok(None);
+    };
+    let raw_num = attribute.value.as_number().and_then(hcl::Number::as_u64);
+    let raw_from_str = attribute.value.as_str().and_then(|s| s.parse::<u64>().ok());
+    raw_num
+        .or(raw_from_str)
+        .and_then(|value| u32::try_from(value).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Attribute '{key}' must be a non-negative integer in {} -> {name}",
+                file_path.display()
+            )
+        })
+}
+
+/// Read an optional boolean attribute, accepting `true`/`false` or their string forms.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a boolean
+pub fn read_optional_bool_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<bool>> {
+    let Some(attribute) = block.body.get_attribute(key) else {
+        return Ok(None);
+    };
+    let raw_bool = attribute.value.as_bool();
+    let raw_from_str = attribute
+        .value
+        .as_str()
+        .and_then(|s| s.parse::<bool>().ok());
+    raw_bool.or(raw_from_str).map(Some).ok_or_else(|| {
+        eyre::eyre!(
+            "Attribute '{key}' must be true or false in {} -> {name}",
+            file_path.display()
+        )
+    })
+}
+
+/// Read an optional percentage strictly between 0 and 100, accepting numeric or string values.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a number in that range
+pub fn read_optional_percentage_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<f64>> {
+    let Some(attribute) = block.body.get_attribute(key) else {
+        return Ok(None);
+    };
+    let raw_num = attribute.value.as_number().and_then(hcl::Number::as_f64);
+    let raw_from_str = attribute.value.as_str().and_then(|s| s.parse::<f64>().ok());
+    raw_num
+        .or(raw_from_str)
+        .filter(|value| *value > 0.0 && *value < 100.0)
+        .map(Some)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Attribute '{key}' must be a percentage between 0 and 100 in {} -> {name}",
+                file_path.display()
+            )
+        })
+}
+
+/// Read an optional positive number, accepting numeric or string values.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not a positive number
+pub fn read_optional_positive_number_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Option<f64>> {
+    let Some(attribute) = block.body.get_attribute(key) else {
+        return Ok(None);
+    };
+    let raw_num = attribute.value.as_number().and_then(hcl::Number::as_f64);
+    let raw_from_str = attribute.value.as_str().and_then(|s| s.parse::<f64>().ok());
+    raw_num
+        .or(raw_from_str)
+        .filter(|value| value.is_finite() && *value > 0.0)
+        .map(Some)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Attribute '{key}' must be a positive number in {} -> {name}",
+                file_path.display()
+            )
+        })
+}
+
+/// Read an optional attribute holding a list of strings, returning an empty list when absent.
+///
+/// # Errors
+/// Returns an error if the attribute is present but is not an array of strings
+pub fn read_optional_string_list_attribute(
+    block: &Block,
+    key: &str,
+    file_path: &Path,
+    name: &str,
+) -> Result<Vec<String>> {
+    let Some(attribute) = block.body.get_attribute(key) else {
+        return Ok(Vec::new());
+    };
+    let invalid = || {
+        eyre::eyre!(
+            "Attribute '{}' must be a list of strings in {} -> {}",
+            key,
+            file_path.display(),
+            name
+        )
+    };
+    let array = attribute.value.as_array().ok_or_else(invalid)?;
+    array
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .map(std::string::ToString::to_string)
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
 #[must_use]
 pub fn sanitize_label(input: &str) -> String {
     let mut sanitized = String::new();
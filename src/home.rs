@@ -1,3 +1,4 @@
+use crate::ping_database::PING_DATABASE_FILE;
 use eyre::Context;
 use eyre::Result;
 use std::env;
@@ -56,6 +57,7 @@ impl PiingHome {
         create_dir_all(self).wrap_err("Failed to create piing home directory")?;
         create_dir_all(self.logs_dir()).wrap_err("Failed to create piing logs directory")?;
         create_dir_all(self.config_dir()).wrap_err("Failed to create piing config directory")?;
+        create_dir_all(self.cache_dir()).wrap_err("Failed to create piing cache directory")?;
         Ok(())
     }
 
@@ -68,4 +70,14 @@ impl PiingHome {
     pub fn config_dir(&self) -> PathBuf {
         self.0.join("config")
     }
+
+    #[must_use]
+    pub fn cache_dir(&self) -> PathBuf {
+        self.0.join("cache")
+    }
+
+    #[must_use]
+    pub fn database_path(&self) -> PathBuf {
+        self.0.join(PING_DATABASE_FILE)
+    }
 }
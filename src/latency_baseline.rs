@@ -0,0 +1,172 @@
+use crate::config::LatencyAnomalySettings;
+use chrono::DateTime;
+use chrono::Local;
+
+/// Pings needed before the baseline is trusted to judge latency.
+const WARMUP_SAMPLES: u64 = 20;
+/// The spread used for the threshold never drops below this share of the
+/// baseline, so very steady targets are not flagged for a millisecond of jitter.
+const MIN_RELATIVE_SPREAD: f64 = 0.05;
+const MIN_SPREAD_MS: f64 = 1.0;
+
+/// A period during which a target's latency stayed above its threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LatencyAnomaly {
+    /// Time of the first ping above the threshold.
+    pub since: DateTime<Local>,
+    /// Time of the latest ping above the threshold.
+    pub last: DateTime<Local>,
+    /// Baseline latency when the anomaly started.
+    pub baseline_ms: f64,
+    pub threshold_ms: f64,
+    pub peak_ms: f64,
+    pub pings: u64,
+}
+
+/// A change in whether a target's latency is anomalous.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AnomalyChange {
+    /// Latency has stayed above the threshold for the configured duration.
+    Started(LatencyAnomaly),
+    /// Latency came back under the threshold at the given time.
+    Ended(LatencyAnomaly, DateTime<Local>),
+}
+
+/// A rolling latency baseline for one target: an exponentially weighted
+/// moving average of latency and of its variance.
+///
+/// Each successful ping is compared against `mean + z * spread` before it is
+/// folded in. Latency above that threshold for at least the configured
+/// duration starts an anomaly, which ends with the first ping back under it.
+/// Pings above the threshold are left out of the baseline, so that spikes and
+/// degradations do not quietly become the new normal.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LatencyBaseline {
+    mean: f64,
+    variance: f64,
+    samples: u64,
+    above: Option<LatencyAnomaly>,
+    active: bool,
+}
+
+impl LatencyBaseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The latency above which a ping counts towards an anomaly, once the
+    /// baseline has seen enough pings.
+    pub fn threshold_ms(&self, z: f64) -> Option<f64> {
+        let spread = self
+            .variance
+            .sqrt()
+            .max(self.mean * MIN_RELATIVE_SPREAD)
+            .max(MIN_SPREAD_MS);
+        (self.samples >= WARMUP_SAMPLES).then_some(self.mean + z * spread)
+    }
+
+    /// Forget latency above the threshold that has not come back down, as
+    /// when the data stops, and return the anomaly that was in progress.
+    pub fn interrupt(&mut self) -> Option<LatencyAnomaly> {
+        let above = self.above.take();
+        above.filter(|_| std::mem::take(&mut self.active))
+    }
+
+    /// Judge the latency of a successful ping, then fold it into the baseline.
+    pub fn observe(
+        &mut self,
+        timestamp: DateTime<Local>,
+        latency: f64,
+        settings: &LatencyAnomalySettings,
+    ) -> Option<AnomalyChange> {
+        let threshold_ms = self.threshold_ms(settings.z);
+        let mut change = None;
+        if let Some(threshold_ms) = threshold_ms {
+            if latency > threshold_ms {
+                let anomaly = self.above.get_or_insert(LatencyAnomaly {
+                    since: timestamp,
+                    last: timestamp,
+                    baseline_ms: self.mean,
+                    threshold_ms,
+                    peak_ms: latency,
+                    pings: 0,
+                });
+                anomaly.last = timestamp;
+                anomaly.peak_ms = anomaly.peak_ms.max(latency);
+                anomaly.pings += 1;
+                let lasted = (timestamp - anomaly.since).to_std().unwrap_or_default();
+                if !self.active && lasted >= settings.duration {
+                    self.active = true;
+                    change = Some(AnomalyChange::Started(*anomaly));
+                }
+            } else if let Some(anomaly) = self.above.take()
+                && std::mem::take(&mut self.active)
+            {
+                change = Some(AnomalyChange::Ended(anomaly, timestamp));
+            }
+        }
+        if threshold_ms.is_none_or(|threshold_ms| latency <= threshold_ms) {
+            self.update(latency, settings.alpha);
+        }
+        change
+    }
+
+    fn update(&mut self, latency: f64, alpha: f64) {
+        if self.samples == 0 {
+            self.mean = latency;
+        } else {
+            let difference = latency - self.mean;
+            let increment = alpha * difference;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + difference * increment);
+        }
+        self.samples += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::time::Duration;
+
+    #[test]
+    fn sustained_high_latency_starts_and_ends_an_anomaly() {
+        let settings = LatencyAnomalySettings {
+            z: 3.0,
+            duration: Duration::from_mins(1),
+            alpha: 0.1,
+        };
+        let start = Local.with_ymd_and_hms(2025, 12, 1, 8, 0, 0).unwrap();
+        let at = |seconds: i64| start + chrono::Duration::seconds(seconds);
+        let mut baseline = LatencyBaseline::new();
+        for ping in 0..40_i32 {
+            let latency_ms = 20.0 + f64::from(ping % 3);
+            assert_eq!(
+                baseline.observe(at(i64::from(ping) * 10), latency_ms, &settings),
+                None
+            );
+        }
+        // A single spike is not an anomaly.
+        assert_eq!(baseline.observe(at(400), 90.0, &settings), None);
+        assert_eq!(baseline.observe(at(410), 21.0, &settings), None);
+
+        let mut changes = Vec::new();
+        for ping in 0..8 {
+            changes.extend(baseline.observe(at(420 + ping * 10), 90.0, &settings));
+        }
+        changes.extend(baseline.observe(at(500), 20.0, &settings));
+        let [
+            AnomalyChange::Started(started),
+            AnomalyChange::Ended(ended, end),
+        ] = changes[..]
+        else {
+            panic!("expected a start and an end, got {changes:?}");
+        };
+        assert_eq!(started.since, at(420));
+        assert_eq!(started.last, at(480));
+        assert_eq!(ended.pings, 8);
+        assert_eq!(end, at(500));
+        assert_eq!(baseline.interrupt(), None);
+    }
+}
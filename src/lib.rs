@@ -1,9 +1,16 @@
+pub mod audit;
 pub mod cli;
 pub mod config;
 pub mod home;
+pub mod latency_baseline;
+pub mod log_rotation;
 pub mod logging;
 pub mod ping;
+pub mod ping_database;
+pub mod ping_record;
+pub mod resume_detector;
 pub mod runtime;
+pub mod scheduler;
 pub mod sound;
 pub mod tray;
 pub mod ui;
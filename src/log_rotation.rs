@@ -0,0 +1,420 @@
+use crate::config::LoggingSettings;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDate;
+use eyre::Context;
+use eyre::Result;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::SystemTime;
+use tracing::warn;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// How long a log file of an earlier launch must go unwritten before it is
+/// compressed, so that the file of another running piing is left alone.
+const IDLE_BEFORE_COMPRESSING: Duration = Duration::from_hours(1);
+
+/// Kind of the diagnostic log files, as in `piing_<timestamp>.log.ndjson`.
+pub const DIAGNOSTIC_LOG_KIND: &str = "log";
+
+/// The path of a new log file of the given kind in `dir`, named after the
+/// time it was started, as in `piing_<timestamp>.<kind>.ndjson`.
+#[must_use]
+pub fn new_log_path(dir: &Path, kind: &str, now: DateTime<Local>) -> PathBuf {
+    let timestamp = now.format("%Y-%m-%d_%H-%M-%S");
+    dir.join(format!("piing_{timestamp}.{kind}.ndjson"))
+}
+
+/// The path of the next log file in `dir`, numbered as in
+/// `piing_<timestamp>.1.<kind>.ndjson` when a file started within the same
+/// second already exists, compressed or not.
+fn next_log_path(dir: &Path, kind: &str, now: DateTime<Local>) -> PathBuf {
+    let timestamp = now.format("%Y-%m-%d_%H-%M-%S");
+    let taken = |path: &Path| {
+        path.exists()
+            || ["gz", "zst"].into_iter().any(|extension| {
+                let mut compressed = path.as_os_str().to_os_string();
+                compressed.push(".");
+                compressed.push(extension);
+                Path::new(&compressed).exists()
+            })
+    };
+    let mut path = new_log_path(dir, kind, now);
+    let mut number = 0;
+    while taken(&path) {
+        number += 1;
+        path = dir.join(format!("piing_{timestamp}.{number}.{kind}.ndjson"));
+    }
+    path
+}
+
+/// A log file of one kind, such as the one written by the JSON layer,
+/// started afresh when it reaches the size limit or the day changes.
+///
+/// Each event is written while holding the lock on the current file, and
+/// files are only switched when the next event asks for a writer, so an
+/// event is never split across two files. Rotated files are compressed and
+/// old files deleted on a background thread.
+#[derive(Debug)]
+pub struct RotatingLogFile {
+    dir: PathBuf,
+    kind: &'static str,
+    settings: LoggingSettings,
+    current: Mutex<CurrentLog>,
+    housekeeping: mpsc::Sender<Housekeeping>,
+}
+
+#[derive(Debug)]
+struct CurrentLog {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    opened: NaiveDate,
+}
+
+impl CurrentLog {
+    fn open(path: PathBuf, now: DateTime<Local>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            written: file.metadata()?.len(),
+            path,
+            file,
+            opened: now.date_naive(),
+        })
+    }
+}
+
+/// A pass of the housekeeping thread, run after each rotation.
+#[derive(Debug)]
+struct Housekeeping {
+    /// The file being written, which is never touched.
+    current: PathBuf,
+    /// The file just rotated out, compressed without waiting for it to idle.
+    rotated: Option<PathBuf>,
+}
+
+impl RotatingLogFile {
+    /// Start a log file of the given kind in `dir` and the housekeeping
+    /// thread, which first tidies up the files of earlier launches. Files of
+    /// other kinds are left to their own housekeeping.
+    ///
+    /// # Errors
+    /// Returns an error if the log file cannot be created
+    pub fn open(dir: &Path, kind: &'static str, settings: LoggingSettings) -> Result<Self> {
+        Self::open_at(dir, kind, settings, Local::now())
+    }
+
+    fn open_at(
+        dir: &Path,
+        kind: &'static str,
+        settings: LoggingSettings,
+        now: DateTime<Local>,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("Failed to create logs directory: {}", dir.display()))?;
+        let current = CurrentLog::open(next_log_path(dir, kind, now), now)
+            .wrap_err("Failed to create log file")?;
+
+        let (housekeeping, jobs) = mpsc::channel::<Housekeeping>();
+        let thread_dir = dir.to_path_buf();
+        let thread_settings = settings.clone();
+        std::thread::Builder::new()
+            .name(format!("{kind} housekeeping"))
+            .spawn(move || {
+                for job in jobs {
+                    housekeep(&thread_dir, kind, &thread_settings, &job);
+                }
+            })
+            .wrap_err("Failed to start the log housekeeping thread")?;
+        let _ = housekeeping.send(Housekeeping {
+            current: current.path.clone(),
+            rotated: None,
+        });
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            kind,
+            settings,
+            current: Mutex::new(current),
+            housekeeping,
+        })
+    }
+
+    /// The path of the file being written.
+    ///
+    /// # Panics
+    /// Never; a poisoned lock is recovered
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.lock().path.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CurrentLog> {
+        self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A writer for the next event, rotating first if the file is due.
+    fn writer_at(&self, now: DateTime<Local>) -> RotatingWriter<'_> {
+        let mut current = self.lock();
+        let too_big = self
+            .settings
+            .rotate_size
+            .is_some_and(|limit| current.written >= limit);
+        let new_day = self.settings.rotate_daily && now.date_naive() != current.opened;
+        if too_big || new_day {
+            // Keep writing to the current file if the next cannot be opened.
+            if let Ok(next) = CurrentLog::open(next_log_path(&self.dir, self.kind, now), now) {
+                let rotated = std::mem::replace(&mut *current, next);
+                drop(rotated.file);
+                let _ = self.housekeeping.send(Housekeeping {
+                    current: current.path.clone(),
+                    rotated: Some(rotated.path),
+                });
+            }
+        }
+        RotatingWriter { current }
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingLogFile {
+    type Writer = RotatingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.writer_at(Local::now())
+    }
+}
+
+/// Writes one event to the current log file, holding it until done.
+#[derive(Debug)]
+pub struct RotatingWriter<'a> {
+    current: MutexGuard<'a, CurrentLog>,
+}
+
+impl Write for RotatingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.current.file.write(buf)?;
+        self.current.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.file.flush()
+    }
+}
+
+/// Compress rotated files and delete those past retention. Failures are
+/// logged and left for the next pass.
+fn housekeep(dir: &Path, kind: &str, settings: &LoggingSettings, job: &Housekeeping) {
+    if settings.compress {
+        let idle_since = SystemTime::now() - IDLE_BEFORE_COMPRESSING;
+        for (path, modified) in piing_log_files(dir, kind, &job.current) {
+            let due = modified <= idle_since || job.rotated.as_ref() == Some(&path);
+            if due
+                && !is_compressed(&path)
+                && let Err(error) = compress(&path)
+            {
+                warn!("Failed to compress log file: {error:#}");
+            }
+        }
+    }
+    if let Err(error) = apply_retention(dir, kind, settings, &job.current, SystemTime::now()) {
+        warn!("Failed to delete old log files: {error:#}");
+    }
+}
+
+/// The log files of the given kind written by piing in `dir`, other than
+/// `current`, with when each was last written, newest first. Subdirectories
+/// hold logs archived by hand, which housekeeping leaves alone.
+fn piing_log_files(dir: &Path, kind: &str, current: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let suffix = format!(".{kind}.ndjson");
+    let of_kind = |name: &str| {
+        let name = name
+            .strip_suffix(".gz")
+            .or_else(|| name.strip_suffix(".zst"))
+            .unwrap_or(name);
+        name.starts_with("piing_") && name.ends_with(&suffix)
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut log_files: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if path == current || !of_kind(&entry.file_name().to_string_lossy()) {
+                return None;
+            }
+            let metadata = entry.metadata().ok().filter(std::fs::Metadata::is_file)?;
+            Some((path, metadata.modified().ok()?))
+        })
+        .collect();
+    log_files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    log_files
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "gz" || ext == "zst")
+}
+
+/// Gzip a log file beside itself and then remove it. The compressed file
+/// keeps the original's last write time, which audits use to skip files
+/// outside the audited time range. Should the removal not happen, audits
+/// read the original and leave out its compressed copy.
+fn compress(path: &Path) -> Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".gz");
+    let compressed = path.with_file_name(name);
+    let mut temporary = compressed.clone().into_os_string();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let source = File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+    let modified = source
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let target = File::create(&temporary)
+        .wrap_err_with(|| format!("Failed to create {}", temporary.display()))?;
+    let mut encoder = GzEncoder::new(target, flate2::Compression::default());
+    io::copy(&mut BufReader::new(source), &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|target| {
+            if let Some(modified) = modified {
+                target.set_modified(modified)?;
+            }
+            target.sync_all()
+        })
+        .wrap_err_with(|| format!("Failed to compress {}", path.display()))?;
+    std::fs::rename(&temporary, &compressed)
+        .wrap_err_with(|| format!("Failed to rename {}", temporary.display()))?;
+    std::fs::remove_file(path).wrap_err_with(|| format!("Failed to remove {}", path.display()))?;
+    Ok(())
+}
+
+/// Delete the log files last written before the retention age, and the
+/// oldest files beyond the maximum count, which includes the current file.
+fn apply_retention(
+    dir: &Path,
+    kind: &str,
+    settings: &LoggingSettings,
+    current: &Path,
+    now: SystemTime,
+) -> Result<()> {
+    let keep = settings
+        .max_files
+        .map_or(usize::MAX, |max| max.saturating_sub(1));
+    let cutoff = settings.retention.map(|retention| now - retention);
+    for (index, (path, modified)) in piing_log_files(dir, kind, current).into_iter().enumerate() {
+        if index >= keep || cutoff.is_some_and(|cutoff| modified < cutoff) {
+            std::fs::remove_file(&path)
+                .wrap_err_with(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn log_files_rotate_by_size_and_day_and_are_compressed() {
+        let dir = std::env::temp_dir().join(format!("piing_rotation_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let start = Local.with_ymd_and_hms(2025, 12, 1, 23, 59, 0).unwrap();
+        let settings = LoggingSettings {
+            rotate_size: Some(10),
+            rotate_daily: true,
+            retention: None,
+            max_files: Some(3),
+            compress: true,
+            sqlite: false,
+        };
+        let log =
+            RotatingLogFile::open_at(&dir, DIAGNOSTIC_LOG_KIND, settings.clone(), start).unwrap();
+        // Ping records are a kind of their own, left out of the count.
+        std::fs::write(new_log_path(&dir, "pings", start), "").unwrap();
+
+        log.writer_at(start).write_all(b"{\"n\":1}\n").unwrap();
+        // Still under the size limit on the same day.
+        log.writer_at(start + chrono::Duration::milliseconds(500))
+            .write_all(b"{\"n\":2}\n")
+            .unwrap();
+        // Over the limit within the same second as the first file, then a
+        // new day.
+        log.writer_at(start + chrono::Duration::milliseconds(800))
+            .write_all(b"{\"n\":3}\n")
+            .unwrap();
+        log.writer_at(start + chrono::Duration::seconds(90))
+            .write_all(b"{\"n\":4}\n")
+            .unwrap();
+        assert_eq!(log.path(), dir.join("piing_2025-12-02_00-00-30.log.ndjson"));
+
+        // The housekeeping thread compresses both rotated files.
+        let expected = [
+            "piing_2025-12-01_23-59-00.1.log.ndjson.gz",
+            "piing_2025-12-01_23-59-00.log.ndjson.gz",
+            "piing_2025-12-01_23-59-00.pings.ndjson",
+            "piing_2025-12-02_00-00-30.log.ndjson",
+        ];
+        let mut names = Vec::new();
+        for _ in 0..100 {
+            names = std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            if names == expected {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(names, expected);
+
+        // A name taken by a zstd-compressed file is not reused.
+        let zst = dir.join("piing_2025-12-03_08-00-00.log.ndjson.zst");
+        std::fs::write(&zst, "").unwrap();
+        let at = Local.with_ymd_and_hms(2025, 12, 3, 8, 0, 0).unwrap();
+        assert_eq!(
+            next_log_path(&dir, DIAGNOSTIC_LOG_KIND, at),
+            dir.join("piing_2025-12-03_08-00-00.1.log.ndjson")
+        );
+        std::fs::remove_file(&zst).unwrap();
+
+        // Logs archived into a subdirectory are neither counted nor deleted.
+        let archived = dir
+            .join("2025-11")
+            .join("piing_2025-11-01_00-00-00.log.ndjson");
+        std::fs::create_dir(archived.parent().unwrap()).unwrap();
+        std::fs::write(&archived, "").unwrap();
+
+        // One file too many once a third is rotated out.
+        apply_retention(
+            &dir,
+            DIAGNOSTIC_LOG_KIND,
+            &LoggingSettings {
+                max_files: Some(2),
+                ..settings
+            },
+            &log.path(),
+            SystemTime::now(),
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+        assert!(archived.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,11 @@
 use crate::config::Config;
 use crate::config::LatencyColouration;
+use crate::config::LoggingSettings;
 use crate::home::PIING_HOME;
+use crate::log_rotation::DIAGNOSTIC_LOG_KIND;
+use crate::log_rotation::RotatingLogFile;
+use crate::log_rotation::new_log_path;
+use crate::ping_record::PING_RESULT_LOG_TARGET;
 use chrono::Local;
 use eyre::Result;
 use owo_colors::OwoColorize;
@@ -17,6 +22,7 @@ use tracing::field::Field;
 use tracing::field::Visit;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::fmt::FmtContext;
 use tracing_subscriber::fmt::FormatEvent;
 use tracing_subscriber::fmt::FormatFields;
@@ -39,17 +45,26 @@ impl LogWritingBehaviour {
     pub fn log_output_path(self) -> Option<PathBuf> {
         match self {
             LogWritingBehaviour::TerminalOnly => None,
-            LogWritingBehaviour::TerminalAndDefaultFile => Some({
-                let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-                PIING_HOME
-                    .logs_dir()
-                    .join(format!("piing_{timestamp}.log.ndjson"))
-            }),
+            LogWritingBehaviour::TerminalAndDefaultFile => Some(new_log_path(
+                &PIING_HOME.logs_dir(),
+                DIAGNOSTIC_LOG_KIND,
+                Local::now(),
+            )),
             LogWritingBehaviour::TerminalAndSpecificFile(path) => Some(path),
         }
     }
 }
 
+fn load_logging_settings() -> LoggingSettings {
+    match Config::current() {
+        Ok(snapshot) => snapshot.logging.clone(),
+        Err(error) => {
+            eprintln!("Failed to load logging config: {error}. Falling back to defaults");
+            LoggingSettings::default()
+        }
+    }
+}
+
 fn load_latency_colouration() -> LatencyColouration {
     match Config::current() {
         Ok(snapshot) => snapshot.latency_colouration.clone(),
@@ -63,22 +78,40 @@ fn load_latency_colouration() -> LatencyColouration {
     }
 }
 
+/// Initialize logging. The default log file is rotated, compressed and
+/// pruned as the `piing_logging` block says; a specific log file is not.
+/// Ping results only reach the terminal, their records having a sink of
+/// their own.
+///
 /// # Errors
 /// Returns an error if log file creation or initialization fails
 ///
 /// # Panics
 /// Panics if the log file mutex is poisoned
 pub fn initialize(level: Level, behaviour: LogWritingBehaviour) -> Result<Option<PathBuf>> {
-    let log_path = behaviour.log_output_path();
-
-    let shared_file = if let Some(ref path) = log_path {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+    let (log_path, json_writer) = match behaviour {
+        LogWritingBehaviour::TerminalOnly => (None, None),
+        LogWritingBehaviour::TerminalAndDefaultFile => {
+            let log_file = RotatingLogFile::open(
+                &PIING_HOME.logs_dir(),
+                DIAGNOSTIC_LOG_KIND,
+                load_logging_settings(),
+            )?;
+            (Some(log_file.path()), Some(BoxMakeWriter::new(log_file)))
+        }
+        LogWritingBehaviour::TerminalAndSpecificFile(path) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = Arc::new(Mutex::new(File::create(&path)?));
+            let json_writer = BoxMakeWriter::new(move || {
+                let guard = file.lock().expect("log file poisoned");
+                guard
+                    .try_clone()
+                    .expect("failed to clone log file handle for json writer")
+            });
+            (Some(path), Some(json_writer))
         }
-        let log_file = File::create(path)?;
-        Some(Arc::new(Mutex::new(log_file)))
-    } else {
-        None
     };
 
     let latency_colouration = load_latency_colouration();
@@ -92,20 +125,16 @@ pub fn initialize(level: Level, behaviour: LogWritingBehaviour) -> Result<Option
         )
         .with_writer(std::io::stderr.and(LOG_BUFFER.clone()));
 
-    let json_layer = shared_file.map(|file| {
-        let json_writer = BoxMakeWriter::new(move || {
-            let guard = file.lock().expect("log file poisoned");
-            guard
-                .try_clone()
-                .expect("failed to clone log file handle for json writer")
-        });
-
+    let json_layer = json_writer.map(|json_writer| {
         tracing_subscriber::fmt::layer()
             .json()
             .with_file(false)
             .with_line_number(false)
             .with_target(false)
             .with_writer(json_writer)
+            .with_filter(filter_fn(|metadata| {
+                metadata.target() != PING_RESULT_LOG_TARGET
+            }))
     });
 
     let env_filter = EnvFilter::builder()
@@ -3,13 +3,17 @@ use eyre::Context;
 use eyre::Result;
 use reqwest::Client;
 use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::Serialize;
+use std::net::IpAddr;
 use std::net::ToSocketAddrs;
 use std::time::Duration;
 use std::time::Instant;
 use tokio::net::TcpStream;
 use tokio::task;
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum PingMode {
     Icmp,
     Tcp,
@@ -102,6 +106,8 @@ pub fn parse_destination(input: &str, mode: PingMode) -> Destination {
 #[derive(Debug, Clone)]
 pub struct PingOutcome {
     pub host: String,
+    /// The address that answered, when the probe got that far.
+    pub resolved_ip: Option<IpAddr>,
     pub mode: PingMode,
     pub latency: Option<Duration>,
     pub status: Option<StatusCode>,
@@ -119,6 +125,7 @@ impl PingOutcome {
     ) -> Self {
         Self {
             host: host.to_string(),
+            resolved_ip: None,
             mode,
             latency: Some(latency),
             status,
@@ -132,13 +139,22 @@ impl PingOutcome {
     pub fn failure(host: &str, mode: PingMode, error: eyre::Error) -> Self {
         Self {
             host: host.to_string(),
+            resolved_ip: None,
             mode,
             latency: None,
             status: None,
             success: false,
-            error: Some(error.to_string()),
+            // Keep the whole error chain: the outer context alone ("ICMP echo
+            // failed", "error sending request") does not say what went wrong.
+            error: Some(format!("{error:#}")),
         }
     }
+
+    #[must_use]
+    pub fn with_resolved_ip(mut self, resolved_ip: Option<IpAddr>) -> Self {
+        self.resolved_ip = resolved_ip;
+        self
+    }
 }
 
 /// # Errors
@@ -154,7 +170,8 @@ pub async fn execute_ping(
 ) -> PingOutcome {
     match mode {
         PingMode::Tcp => match tcp_ping(&destination.host, destination.port).await {
-            Ok(latency) => PingOutcome::success(&destination.host, mode, latency, None),
+            Ok((latency, ip)) => PingOutcome::success(&destination.host, mode, latency, None)
+                .with_resolved_ip(Some(ip)),
             Err(e) => PingOutcome::failure(&destination.host, mode, eyre::eyre!(e)),
         },
         PingMode::HttpGet => match http_ping(
@@ -164,8 +181,9 @@ pub async fn execute_ping(
         )
         .await
         {
-            Ok((latency, status)) => {
+            Ok((latency, status, ip)) => {
                 PingOutcome::success(&destination.host, mode, latency, Some(status))
+                    .with_resolved_ip(ip)
             }
             Err(e) => PingOutcome::failure(&destination.host, mode, eyre::eyre!(e)),
         },
@@ -176,13 +194,15 @@ pub async fn execute_ping(
         )
         .await
         {
-            Ok((latency, status)) => {
+            Ok((latency, status, ip)) => {
                 PingOutcome::success(&destination.host, mode, latency, Some(status))
+                    .with_resolved_ip(ip)
             }
             Err(e) => PingOutcome::failure(&destination.host, mode, eyre::eyre!(e)),
         },
         PingMode::Icmp => match icmp_ping(&destination.host).await {
-            Ok(latency) => PingOutcome::success(&destination.host, mode, latency, None),
+            Ok((latency, ip)) => PingOutcome::success(&destination.host, mode, latency, None)
+                .with_resolved_ip(Some(ip)),
             Err(e) => PingOutcome::failure(&destination.host, mode, e),
         },
     }
@@ -191,27 +211,29 @@ pub async fn execute_ping(
 async fn tcp_ping(
     host: &str,
     port: u16,
-) -> Result<Duration, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<(Duration, IpAddr), Box<dyn std::error::Error + Send + Sync>> {
     let start = Instant::now();
-    let _stream = TcpStream::connect((host, port)).await?;
-    Ok(start.elapsed())
+    let stream = TcpStream::connect((host, port)).await?;
+    let latency = start.elapsed();
+    Ok((latency, stream.peer_addr()?.ip()))
 }
 
 async fn http_ping(
     client: &Client,
     url: &str,
     use_head: bool,
-) -> Result<(Duration, StatusCode), reqwest::Error> {
+) -> Result<(Duration, StatusCode, Option<IpAddr>), reqwest::Error> {
     let start = Instant::now();
     let response = if use_head {
         client.head(url).send().await?
     } else {
         client.get(url).send().await?
     };
-    Ok((start.elapsed(), response.status()))
+    let remote = response.remote_addr().map(|addr| addr.ip());
+    Ok((start.elapsed(), response.status(), remote))
 }
 
-async fn icmp_ping(host: &str) -> Result<Duration> {
+async fn icmp_ping(host: &str) -> Result<(Duration, IpAddr)> {
     let host = host.to_string();
     task::spawn_blocking(move || {
         use ping::ping;
@@ -225,7 +247,7 @@ async fn icmp_ping(host: &str) -> Result<Duration> {
         let start = Instant::now();
         let timeout = Some(Duration::from_secs(2));
         ping(ip, timeout, None, None, None, None).wrap_err("ICMP echo failed")?;
-        Ok(start.elapsed())
+        Ok((start.elapsed(), ip))
     })
     .await
     .unwrap_or_else(|e| Err(eyre::eyre!("ICMP task failed: {e}")))
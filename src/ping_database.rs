@@ -0,0 +1,839 @@
+use crate::audit::Accumulator;
+use crate::audit::DEFAULT_GAP_FACTOR;
+use crate::audit::EventFilter;
+use crate::audit::FilterVerdict;
+use crate::audit::Loaded;
+use crate::audit::TargetKey;
+use crate::audit::TimeRange;
+use crate::audit::median_spacing;
+use crate::config::ConfigSnapshot;
+use crate::ping::PingMode;
+use crate::ping_record::PingRecord;
+use crate::ping_record::RecordOutcome;
+use chrono::DateTime;
+use chrono::Local;
+use clap::ValueEnum;
+use eyre::Context;
+use eyre::Result;
+use rusqlite::Connection;
+use rusqlite::Row;
+use rusqlite::params;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// File name of the ping database in the piing home directory.
+pub const PING_DATABASE_FILE: &str = "piing.sqlite3";
+
+/// Version of the database layout, kept in `PRAGMA user_version`.
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS pings (
+    id INTEGER PRIMARY KEY,
+    version INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    host TEXT NOT NULL,
+    resolved_ip TEXT,
+    mode TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    latency_ms REAL,
+    status INTEGER,
+    failure_kind TEXT,
+    error TEXT,
+    vpn_active INTEGER NOT NULL,
+    machine_id TEXT NOT NULL,
+    tags TEXT NOT NULL,
+    maintenance INTEGER NOT NULL,
+    resume_grace INTEGER NOT NULL,
+    UNIQUE (machine_id, host, mode, timestamp_ms)
+);
+CREATE INDEX IF NOT EXISTS pings_by_time ON pings (timestamp_ms);
+CREATE INDEX IF NOT EXISTS pings_by_target ON pings (target_id COLLATE NOCASE, timestamp_ms);
+
+CREATE TABLE IF NOT EXISTS config_changes (
+    id INTEGER PRIMARY KEY,
+    timestamp_ms INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    machine_id TEXT NOT NULL,
+    targets TEXT NOT NULL,
+    config TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS config_changes_by_time ON config_changes (timestamp_ms);
+
+CREATE TABLE IF NOT EXISTS incidents (
+    id INTEGER PRIMARY KEY,
+    machine_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    host TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    started TEXT NOT NULL,
+    ended TEXT NOT NULL,
+    failed_probes INTEGER NOT NULL,
+    recovered INTEGER NOT NULL,
+    UNIQUE (machine_id, host, mode, start_ms)
+);
+CREATE INDEX IF NOT EXISTS incidents_by_time ON incidents (start_ms);
+CREATE INDEX IF NOT EXISTS incidents_by_target ON incidents (target_id, start_ms);
+";
+
+/// How many of a target's latest pings its usual interval is judged by
+/// while recording.
+const RECENT_PINGS: usize = 32;
+
+const INSERT_PING: &str = "
+INSERT OR IGNORE INTO pings (
+    version, timestamp_ms, timestamp, target_id, host, resolved_ip, mode, success,
+    latency_ms, status, failure_kind, error, vpn_active, machine_id, tags,
+    maintenance, resume_grace
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)";
+
+const INSERT_INCIDENT: &str = "
+INSERT OR IGNORE INTO incidents (
+    machine_id, target_id, host, mode, start_ms, started, ended, failed_probes, recovered
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)";
+
+/// A period during which one target was failing, as kept in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredIncident {
+    pub machine_id: String,
+    pub target_id: String,
+    pub host: String,
+    pub mode: String,
+    /// Time of the first failed ping.
+    pub start: DateTime<Local>,
+    /// Time of the first successful ping afterwards, or of the last failure
+    /// while the target has not recovered.
+    pub end: DateTime<Local>,
+    pub failed_probes: usize,
+    pub recovered: bool,
+}
+
+/// How many rows an import added; rows already present are left alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportCounts {
+    pub pings: usize,
+    pub incidents: usize,
+}
+
+/// The database that ping records, config changes and incidents are
+/// kept in when the `piing_logging` block turns `sqlite` on.
+#[derive(Debug)]
+pub struct PingDatabase {
+    connection: Connection,
+    path: PathBuf,
+    incidents: IncidentTracker,
+}
+
+/// What following the incidents of stored pings needs to remember between
+/// pings, and between the batches of an import.
+#[derive(Debug, Default)]
+struct IncidentTracker {
+    /// The incident still open for each target.
+    open: HashMap<TargetKey, i64>,
+    /// The times of each target's latest pings outside maintenance windows
+    /// and resume grace periods, to tell data gaps by.
+    recent: HashMap<TargetKey, VecDeque<DateTime<Local>>>,
+}
+
+impl PingDatabase {
+    /// Open the database at `path`, creating it and its tables as needed.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened, or was written by a
+    /// newer version of piing
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(path)
+            .wrap_err_with(|| format!("Failed to open ping database: {}", path.display()))?;
+        // `piing run` and `piing db import` may write at the same time.
+        connection.busy_timeout(Duration::from_secs(5))?;
+        connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            eyre::bail!(
+                "Ping database {} has layout version {version}, newer than the {SCHEMA_VERSION} this piing knows",
+                path.display()
+            );
+        }
+        if version == 1 {
+            // Target names are matched ignoring case, which the first
+            // layout's index could not serve.
+            connection.execute_batch("DROP INDEX IF EXISTS pings_by_target")?;
+        }
+        connection
+            .execute_batch(SCHEMA)
+            .wrap_err("Failed to create the ping database tables")?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        let incidents = IncidentTracker {
+            open: open_incidents(&connection)?,
+            recent: HashMap::new(),
+        };
+        Ok(Self {
+            connection,
+            path: path.to_path_buf(),
+            incidents,
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store a ping record and follow the incident of its target: a failure
+    /// opens or extends one, and a success closes it. A data gap ends the
+    /// incident without recovery, as `piing audit incidents` does, once the
+    /// target's usual interval is known. Pings inside maintenance windows or
+    /// resume grace periods leave incidents alone.
+    ///
+    /// # Errors
+    /// Returns an error if the record cannot be stored
+    pub fn record_ping(&mut self, record: &PingRecord) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        if insert_ping(&transaction, record)? {
+            self.incidents.follow(&transaction, record)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Store the configuration `machine_id` switched to at `when`.
+    ///
+    /// # Errors
+    /// Returns an error if the change cannot be stored
+    pub fn record_config_change(
+        &self,
+        snapshot: &ConfigSnapshot,
+        machine_id: &str,
+        when: DateTime<Local>,
+    ) -> Result<()> {
+        let targets: Vec<&str> = snapshot
+            .targets
+            .iter()
+            .map(|target| target.id.name.as_str())
+            .collect();
+        let mut config = String::new();
+        for (path, body) in &snapshot.files {
+            let _ = writeln!(config, "# {}\n{body}", path.display());
+        }
+        self.connection.execute(
+            "INSERT INTO config_changes (timestamp_ms, timestamp, machine_id, targets, config)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                when.timestamp_millis(),
+                when.to_rfc3339(),
+                machine_id,
+                targets.join(","),
+                config
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Store a batch of ping records read from elsewhere, in time order,
+    /// skipping those already present and following incidents as
+    /// [`record_ping`](Self::record_ping) does. Incidents left open carry
+    /// over to the next batch.
+    ///
+    /// # Errors
+    /// Returns an error if the rows cannot be stored; nothing of the batch
+    /// is stored then
+    pub fn import(&mut self, records: &[PingRecord]) -> Result<ImportCounts> {
+        let transaction = self.connection.transaction()?;
+        let mut counts = ImportCounts::default();
+        for record in records {
+            if insert_ping(&transaction, record)? {
+                counts.pings += 1;
+                counts.incidents += usize::from(self.incidents.follow(&transaction, record)?);
+            }
+        }
+        transaction.commit()?;
+        Ok(counts)
+    }
+
+    /// Read the ping events selected by the filter into `view`. The time
+    /// range and target names are looked up through the indexes.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be queried
+    pub fn load_into<A: Accumulator>(&self, filter: &EventFilter, view: A) -> Result<Loaded<A>> {
+        let mut sql = String::from(
+            "SELECT version, timestamp, target_id, host, resolved_ip, mode, success, latency_ms,
+                    status, failure_kind, error, vpn_active, machine_id, tags, maintenance,
+                    resume_grace
+             FROM pings WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(since) = filter.range.since {
+            sql.push_str(" AND timestamp_ms >= ?");
+            values.push(Value::Integer(since.timestamp_millis()));
+        }
+        if let Some(until) = filter.range.until {
+            // Millisecond rounding is settled by the filter below.
+            sql.push_str(" AND timestamp_ms <= ?");
+            values.push(Value::Integer(until.timestamp_millis()));
+        }
+        if !filter.targets.is_empty() {
+            let placeholders = vec!["?"; filter.targets.len()].join(", ");
+            let _ = write!(sql, " AND target_id COLLATE NOCASE IN ({placeholders})");
+            values.extend(filter.targets.iter().cloned().map(Value::Text));
+        }
+        sql.push_str(" ORDER BY timestamp_ms, id");
+
+        let mut loaded = Loaded::new(view, filter.clone());
+        loaded.source.database = Some(self.path.clone());
+        let mut statement = self.connection.prepare(&sql)?;
+        let mut rows = statement.query(params_from_iter(values))?;
+        while let Some(row) = rows.next()? {
+            let Some(record) = read_ping(row)? else {
+                loaded.source.malformed_lines += 1;
+                continue;
+            };
+            let event = record.to_event();
+            match filter.check(&event) {
+                FilterVerdict::Keep => loaded.keep(event),
+                FilterVerdict::OutOfScope => {}
+                FilterVerdict::Maintenance => loaded.source.excluded_maintenance += 1,
+                FilterVerdict::ResumeGrace => loaded.source.excluded_resume_grace += 1,
+            }
+        }
+        loaded.settle_machines();
+        Ok(loaded)
+    }
+
+    /// The incidents that started within the time range, ordered by start.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be queried
+    pub fn incidents(&self, range: &TimeRange) -> Result<Vec<StoredIncident>> {
+        let mut statement = self.connection.prepare(
+            "SELECT machine_id, target_id, host, mode, started, ended, failed_probes, recovered
+             FROM incidents
+             WHERE start_ms >= ?1 AND start_ms < ?2
+             ORDER BY start_ms, id",
+        )?;
+        let since = range
+            .since
+            .map_or(i64::MIN, |since| since.timestamp_millis());
+        let until = range
+            .until
+            .map_or(i64::MAX, |until| until.timestamp_millis());
+        let mut incidents = Vec::new();
+        for incident in statement.query_map(params![since, until], |row| {
+            let started: String = row.get(4)?;
+            let ended: String = row.get(5)?;
+            let (Some(start), Some(end)) = (parse_timestamp(&started), parse_timestamp(&ended))
+            else {
+                return Ok(None);
+            };
+            Ok(Some(StoredIncident {
+                machine_id: row.get(0)?,
+                target_id: row.get(1)?,
+                host: row.get(2)?,
+                mode: row.get(3)?,
+                start,
+                end,
+                failed_probes: row.get(6)?,
+                recovered: row.get(7)?,
+            }))
+        })? {
+            incidents.extend(incident?);
+        }
+        Ok(incidents)
+    }
+
+    /// The number of ping records stored.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be queried
+    pub fn ping_count(&self) -> Result<usize> {
+        Ok(self
+            .connection
+            .query_row("SELECT COUNT(*) FROM pings", [], |row| row.get(0))?)
+    }
+}
+
+/// A write for the database thread.
+#[derive(Debug)]
+enum DatabaseWrite {
+    Ping(PingRecord),
+    ConfigChange {
+        snapshot: Arc<ConfigSnapshot>,
+        machine_id: String,
+        when: DateTime<Local>,
+    },
+}
+
+/// Hands ping records and config changes to a thread that stores them, so
+/// that a busy or slow database never holds up the ping loop. Dropping the
+/// writer waits for the writes already handed over.
+#[derive(Debug)]
+pub struct PingDatabaseWriter {
+    writes: Option<mpsc::Sender<DatabaseWrite>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PingDatabaseWriter {
+    /// Start the thread that writes to `database`.
+    ///
+    /// # Errors
+    /// Returns an error if the thread cannot be started
+    pub fn spawn(mut database: PingDatabase) -> Result<Self> {
+        let (writes, jobs) = mpsc::channel::<DatabaseWrite>();
+        let thread = std::thread::Builder::new()
+            .name("ping database".to_string())
+            .spawn(move || {
+                for job in jobs {
+                    match job {
+                        DatabaseWrite::Ping(record) => {
+                            if let Err(error) = database.record_ping(&record) {
+                                warn!("Failed to store the ping record: {error:#}");
+                            }
+                        }
+                        DatabaseWrite::ConfigChange {
+                            snapshot,
+                            machine_id,
+                            when,
+                        } => {
+                            if let Err(error) =
+                                database.record_config_change(&snapshot, &machine_id, when)
+                            {
+                                warn!("Failed to record the config change: {error:#}");
+                            }
+                        }
+                    }
+                }
+            })
+            .wrap_err("Failed to start the ping database thread")?;
+        Ok(Self {
+            writes: Some(writes),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queue a ping record, as [`PingDatabase::record_ping`] stores it.
+    pub fn record_ping(&self, record: PingRecord) {
+        self.send(DatabaseWrite::Ping(record));
+    }
+
+    /// Queue a config change, as [`PingDatabase::record_config_change`]
+    /// stores it.
+    pub fn record_config_change(
+        &self,
+        snapshot: Arc<ConfigSnapshot>,
+        machine_id: String,
+        when: DateTime<Local>,
+    ) {
+        self.send(DatabaseWrite::ConfigChange {
+            snapshot,
+            machine_id,
+            when,
+        });
+    }
+
+    fn send(&self, write: DatabaseWrite) {
+        if let Some(writes) = &self.writes
+            && writes.send(write).is_err()
+        {
+            warn!("The ping database thread has stopped; dropping the write");
+        }
+    }
+}
+
+impl Drop for PingDatabaseWriter {
+    fn drop(&mut self) {
+        drop(self.writes.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl IncidentTracker {
+    /// Follow the incident of the target of a ping just stored. Pings older
+    /// than the latest one counted for the target cannot be placed in its
+    /// incidents and leave them alone. Returns true if the ping opened an
+    /// incident.
+    fn follow(&mut self, connection: &Connection, record: &PingRecord) -> Result<bool> {
+        let event = record.to_event();
+        if event.maintenance || event.resume_grace {
+            return Ok(false);
+        }
+        if !self.recent.contains_key(&event.target) {
+            let times = recent_pings(connection, record)?;
+            self.recent.insert(event.target.clone(), times);
+        }
+        let recent = self.recent.entry(event.target.clone()).or_default();
+        if recent.back().is_some_and(|last| event.timestamp < *last) {
+            return Ok(false);
+        }
+        let limit = median_spacing(recent.iter().copied()).map(|usual| usual * DEFAULT_GAP_FACTOR);
+        if let (Some(limit), Some(last)) = (limit, recent.back())
+            && event.timestamp - *last > limit
+        {
+            self.open.remove(&event.target);
+        }
+        recent.push_back(event.timestamp);
+        if recent.len() > RECENT_PINGS {
+            recent.pop_front();
+        }
+
+        let open = self.open.get(&event.target).copied();
+        let ended = event.timestamp.to_rfc3339();
+        match (event.success, open) {
+            (true, Some(id)) => {
+                connection.execute(
+                    "UPDATE incidents SET ended = ?1, recovered = 1 WHERE id = ?2",
+                    params![ended, id],
+                )?;
+                self.open.remove(&event.target);
+            }
+            (true, None) => {}
+            (false, Some(id)) => {
+                connection.execute(
+                    "UPDATE incidents SET ended = ?1, failed_probes = failed_probes + 1 WHERE id = ?2",
+                    params![ended, id],
+                )?;
+            }
+            (false, None) => {
+                let incident = StoredIncident {
+                    machine_id: record.machine_id.clone(),
+                    target_id: record.target_id.clone(),
+                    host: record.host.clone(),
+                    mode: record.mode.as_str().to_string(),
+                    start: event.timestamp,
+                    end: event.timestamp,
+                    failed_probes: 1,
+                    recovered: false,
+                };
+                if insert_incident(connection, &incident)? {
+                    self.open
+                        .insert(event.target, connection.last_insert_rowid());
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The incidents still open, by target: the latest unrecovered incident of
+/// each target that has had no successful ping since it started.
+fn open_incidents(connection: &Connection) -> Result<HashMap<TargetKey, i64>> {
+    let mut statement = connection.prepare(
+        "SELECT id, machine_id, host, mode FROM incidents
+         WHERE recovered = 0 AND NOT EXISTS (
+             SELECT 1 FROM pings
+             WHERE pings.machine_id = incidents.machine_id
+               AND pings.host = incidents.host
+               AND pings.mode = incidents.mode
+               AND pings.timestamp_ms > incidents.start_ms
+               AND success = 1 AND maintenance = 0 AND resume_grace = 0
+         )
+         ORDER BY start_ms, id",
+    )?;
+    let mut open = HashMap::new();
+    for row in statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            TargetKey {
+                host: row.get(2)?,
+                mode: row.get(3)?,
+                machine: Some(row.get(1)?),
+            },
+        ))
+    })? {
+        let (id, target) = row?;
+        open.insert(target, id);
+    }
+    Ok(open)
+}
+
+/// The times of the latest pings of the record's target that count
+/// towards incidents, oldest first.
+fn recent_pings(connection: &Connection, record: &PingRecord) -> Result<VecDeque<DateTime<Local>>> {
+    let mut statement = connection.prepare(
+        "SELECT timestamp FROM pings
+         WHERE machine_id = ?1 AND host = ?2 AND mode = ?3
+           AND maintenance = 0 AND resume_grace = 0
+         ORDER BY timestamp_ms DESC
+         LIMIT ?4",
+    )?;
+    let mut times = VecDeque::new();
+    for timestamp in statement.query_map(
+        params![
+            record.machine_id,
+            record.host,
+            record.mode.as_str(),
+            RECENT_PINGS
+        ],
+        |row| row.get::<_, String>(0),
+    )? {
+        if let Some(timestamp) = parse_timestamp(&timestamp?) {
+            times.push_front(timestamp);
+        }
+    }
+    Ok(times)
+}
+
+/// Insert a ping record, returning false if it was already stored.
+fn insert_ping(connection: &Connection, record: &PingRecord) -> Result<bool> {
+    let inserted = connection.execute(
+        INSERT_PING,
+        params![
+            record.version,
+            record.timestamp.timestamp_millis(),
+            record.timestamp.to_rfc3339(),
+            record.target_id,
+            record.host,
+            record.resolved_ip.map(|ip| ip.to_string()),
+            record.mode.as_str(),
+            record.outcome == RecordOutcome::Success,
+            record.latency_ms,
+            record.status,
+            record
+                .failure_kind
+                .and_then(|kind| serde_json::to_value(kind).ok())
+                .and_then(|kind| kind.as_str().map(str::to_string)),
+            record.error,
+            record.vpn_active,
+            record.machine_id,
+            record.tags.join(","),
+            record.maintenance,
+            record.resume_grace,
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Insert an incident, returning false if it was already stored.
+fn insert_incident(connection: &Connection, incident: &StoredIncident) -> Result<bool> {
+    let inserted = connection.execute(
+        INSERT_INCIDENT,
+        params![
+            incident.machine_id,
+            incident.target_id,
+            incident.host,
+            incident.mode,
+            incident.start.timestamp_millis(),
+            incident.start.to_rfc3339(),
+            incident.end.to_rfc3339(),
+            incident.failed_probes,
+            incident.recovered,
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Read a row of the pings table, or `None` if it holds values this piing
+/// cannot read.
+fn read_ping(row: &Row<'_>) -> rusqlite::Result<Option<PingRecord>> {
+    let timestamp: String = row.get(1)?;
+    let mode: String = row.get(5)?;
+    let (Some(timestamp), Ok(mode)) = (
+        parse_timestamp(&timestamp),
+        PingMode::from_str(&mode, false),
+    ) else {
+        return Ok(None);
+    };
+    let success: bool = row.get(6)?;
+    let tags: String = row.get(13)?;
+    Ok(Some(PingRecord {
+        version: row.get(0)?,
+        timestamp,
+        target_id: row.get(2)?,
+        host: row.get(3)?,
+        resolved_ip: row
+            .get::<_, Option<String>>(4)?
+            .and_then(|ip| ip.parse().ok()),
+        mode,
+        outcome: if success {
+            RecordOutcome::Success
+        } else {
+            RecordOutcome::Failure
+        },
+        latency_ms: row.get(7)?,
+        status: row.get(8)?,
+        failure_kind: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|kind| serde_json::from_value(serde_json::Value::String(kind)).ok()),
+        error: row.get(10)?,
+        vpn_active: row.get(11)?,
+        machine_id: row.get(12)?,
+        tags: tags
+            .split(',')
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        maintenance: row.get(14)?,
+        resume_grace: row.get(15)?,
+    }))
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Local))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping_record::PING_RECORD_VERSION;
+    use chrono::TimeZone;
+
+    fn stored_incidents(database: &PingDatabase) -> Vec<StoredIncident> {
+        database.incidents(&TimeRange::default()).unwrap()
+    }
+
+    fn record(second: i64, success: bool) -> PingRecord {
+        PingRecord {
+            version: PING_RECORD_VERSION,
+            timestamp: Local.with_ymd_and_hms(2025, 12, 1, 8, 0, 0).unwrap()
+                + chrono::Duration::seconds(second),
+            target_id: "google_dns".to_string(),
+            host: "8.8.8.8".to_string(),
+            resolved_ip: Some("8.8.8.8".parse().unwrap()),
+            mode: PingMode::Icmp,
+            outcome: if success {
+                RecordOutcome::Success
+            } else {
+                RecordOutcome::Failure
+            },
+            latency_ms: success.then_some(12.5),
+            status: None,
+            failure_kind: None,
+            error: (!success).then(|| "Request timed out".to_string()),
+            vpn_active: false,
+            machine_id: "DESKTOP-1".to_string(),
+            tags: vec!["dns".to_string()],
+            maintenance: false,
+            resume_grace: false,
+        }
+    }
+
+    #[test]
+    fn pings_and_incidents_are_stored_and_queried() {
+        let path = std::env::temp_dir().join(format!("piing_db_{}.sqlite3", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut database = PingDatabase::open(&path).unwrap();
+        let records: Vec<PingRecord> = [(0, true), (10, false), (20, false), (30, true)]
+            .into_iter()
+            .map(|(second, success)| record(second, success))
+            .collect();
+        for record in &records {
+            database.record_ping(record).unwrap();
+        }
+
+        let incidents = stored_incidents(&database);
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].start, records[1].timestamp);
+        assert_eq!(incidents[0].end, records[3].timestamp);
+        assert_eq!(incidents[0].failed_probes, 2);
+        assert!(incidents[0].recovered);
+
+        // Importing the same pings again adds nothing.
+        let counts = database.import(&records).unwrap();
+        assert_eq!(counts, ImportCounts::default());
+        assert_eq!(database.ping_count().unwrap(), 4);
+
+        let filter = EventFilter {
+            range: TimeRange::new(Some(records[1].timestamp), Some(records[3].timestamp)),
+            targets: vec!["GOOGLE_DNS".to_string()],
+            ..EventFilter::default()
+        };
+        let loaded = database.load_into(&filter, Vec::new()).unwrap();
+        assert_eq!(loaded.view.len(), 2);
+        assert!(loaded.view.iter().all(|event| !event.success));
+        assert_eq!(loaded.view[0].target.machine, None);
+        assert_eq!(loaded.view[0].tags, ["dns"]);
+        assert_eq!(loaded.source.machines, ["DESKTOP-1"]);
+
+        drop(database);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn incidents_continue_across_restarts_and_end_at_data_gaps() {
+        let path =
+            std::env::temp_dir().join(format!("piing_db_restart_{}.sqlite3", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut database = PingDatabase::open(&path).unwrap();
+        for (second, success) in [(0, true), (10, false), (20, false)] {
+            database.record_ping(&record(second, success)).unwrap();
+        }
+        drop(database);
+
+        // The failure after a restart extends the open incident; the one
+        // after a long silence opens another.
+        let mut database = PingDatabase::open(&path).unwrap();
+        for (second, success) in [(30, false), (300, false), (310, true)] {
+            database.record_ping(&record(second, success)).unwrap();
+        }
+        let incidents = stored_incidents(&database);
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].end, record(30, false).timestamp);
+        assert_eq!(incidents[0].failed_probes, 3);
+        assert!(!incidents[0].recovered);
+        assert_eq!(incidents[1].start, record(300, false).timestamp);
+        assert_eq!(incidents[1].end, record(310, true).timestamp);
+        assert!(incidents[1].recovered);
+
+        drop(database);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn imported_incidents_carry_over_between_batches() {
+        let path =
+            std::env::temp_dir().join(format!("piing_db_import_{}.sqlite3", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut database = PingDatabase::open(&path).unwrap();
+        let first = [record(0, true), record(10, false)];
+        let second = [record(20, false), record(30, true), record(5, false)];
+        let counts = database.import(&first).unwrap();
+        assert_eq!(
+            counts,
+            ImportCounts {
+                pings: 2,
+                incidents: 1
+            }
+        );
+        let counts = database.import(&second).unwrap();
+        assert_eq!(
+            counts,
+            ImportCounts {
+                pings: 3,
+                incidents: 0
+            }
+        );
+
+        // The late failure is stored but does not reopen the incident.
+        let incidents = stored_incidents(&database);
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].start, record(10, false).timestamp);
+        assert_eq!(incidents[0].end, record(30, true).timestamp);
+        assert_eq!(incidents[0].failed_probes, 2);
+        assert!(incidents[0].recovered);
+        assert_eq!(database.ping_count().unwrap(), 5);
+
+        drop(database);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::audit::ErrorCategory;
+use crate::audit::PingEvent;
+use crate::audit::TargetKey;
+use crate::config::LoggingSettings;
+use crate::log_rotation::RotatingLogFile;
+use crate::ping::PingMode;
+use chrono::DateTime;
+use chrono::Local;
+use clap::ValueEnum;
+use eyre::Context;
+use eyre::Result;
+use serde::Deserialize;
+use serde::Serialize;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Version of the [`PingRecord`] layout, bumped whenever a field changes
+/// meaning or is removed. Adding a field keeps the version.
+pub const PING_RECORD_VERSION: u32 = 1;
+
+/// Kind of the ping record files, as in `piing_<timestamp>.pings.ndjson`.
+pub const PING_RECORD_KIND: &str = "pings";
+
+/// Tracing target of the ping results shown on the terminal, left out of the
+/// diagnostic log since their records are written by [`PingRecordSink`].
+pub const PING_RESULT_LOG_TARGET: &str = "piing::ping_result";
+
+/// Whether a ping got an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordOutcome {
+    Success,
+    Failure,
+}
+
+/// The result of one ping, one JSON object per line of a ping record file.
+///
+/// ```json
+/// {"record":"ping","version":1,"timestamp":"2025-12-01T08:00:00.123-05:00","target_id":"google_dns","host":"8.8.8.8","resolved_ip":"8.8.8.8","mode":"icmp","outcome":"success","latency_ms":12.4,"vpn_active":false,"machine_id":"DESKTOP-1","tags":["dns"],"maintenance":false,"resume_grace":false}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename = "ping")]
+pub struct PingRecord {
+    pub version: u32,
+    pub timestamp: DateTime<Local>,
+    /// Name of the `piing_target` block.
+    pub target_id: String,
+    pub host: String,
+    /// The address that answered, when the probe got that far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_ip: Option<IpAddr>,
+    pub mode: PingMode,
+    pub outcome: RecordOutcome,
+    /// Round trip time in milliseconds of a successful ping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    /// HTTP status code the target answered with, for HTTP pings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Why the ping failed, normalised.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<ErrorCategory>,
+    /// Why the ping failed, as reported by the probe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub vpn_active: bool,
+    pub machine_id: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the ping happened inside a `piing_schedule` maintenance window.
+    #[serde(default)]
+    pub maintenance: bool,
+    /// Whether the ping failed during the grace period after a system resume.
+    #[serde(default)]
+    pub resume_grace: bool,
+}
+
+impl PingRecord {
+    /// The record of a ping event read from any log, crediting events that
+    /// name no machine to `machine_id`. Returns `None` for an unknown mode.
+    #[must_use]
+    pub fn from_event(event: &PingEvent, machine_id: &str) -> Option<Self> {
+        let mode = PingMode::from_str(&event.target.mode, false).ok()?;
+        Some(Self {
+            version: PING_RECORD_VERSION,
+            timestamp: event.timestamp,
+            target_id: event.target_name.clone().unwrap_or_default(),
+            host: event.target.host.clone(),
+            resolved_ip: None,
+            mode,
+            outcome: if event.success {
+                RecordOutcome::Success
+            } else {
+                RecordOutcome::Failure
+            },
+            latency_ms: event.latency_ms,
+            status: event.status,
+            failure_kind: ErrorCategory::of(event),
+            error: event.error.clone(),
+            vpn_active: event.vpn_active,
+            machine_id: event
+                .target
+                .machine
+                .clone()
+                .unwrap_or_else(|| machine_id.to_string()),
+            tags: event.tags.clone(),
+            maintenance: event.maintenance,
+            resume_grace: event.resume_grace,
+        })
+    }
+
+    /// The ping event the audit reads from this record.
+    #[must_use]
+    pub fn to_event(&self) -> PingEvent {
+        let success = self.outcome == RecordOutcome::Success;
+        let latency_ms = self.latency_ms.filter(|_| success);
+        PingEvent {
+            timestamp: self.timestamp,
+            target: TargetKey {
+                host: self.host.clone(),
+                mode: self.mode.as_str().to_string(),
+                machine: Some(self.machine_id.clone()),
+            },
+            target_name: Some(self.target_id.clone()),
+            success,
+            latency_ms,
+            status: self.status,
+            error: self.error.clone().filter(|_| !success),
+            vpn_active: self.vpn_active,
+            tags: self.tags.clone(),
+            maintenance: self.maintenance,
+            resume_grace: self.resume_grace,
+        }
+    }
+}
+
+/// Appends ping records to `piing_<timestamp>.pings.ndjson` files, rotated,
+/// compressed and pruned like the diagnostic log.
+#[derive(Debug)]
+pub struct PingRecordSink {
+    file: RotatingLogFile,
+}
+
+impl PingRecordSink {
+    /// Start a ping record file in `dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created
+    pub fn open(dir: &Path, settings: LoggingSettings) -> Result<Self> {
+        Ok(Self {
+            file: RotatingLogFile::open(dir, PING_RECORD_KIND, settings)?,
+        })
+    }
+
+    /// Append a record as one line.
+    ///
+    /// # Errors
+    /// Returns an error if the record cannot be written
+    pub fn write(&self, record: &PingRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // A single write keeps the line whole within one file.
+        self.file
+            .make_writer()
+            .write_all(&line)
+            .wrap_err("Failed to write ping record")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::LogLine;
+    use crate::audit::parse_log_line;
+
+    #[test]
+    fn ping_records_read_back_as_ping_events() {
+        let record = PingRecord {
+            version: PING_RECORD_VERSION,
+            timestamp: DateTime::parse_from_rfc3339("2025-12-01T08:00:00.123-05:00")
+                .unwrap()
+                .with_timezone(&Local),
+            target_id: "example".to_string(),
+            host: "https://example.com".to_string(),
+            resolved_ip: Some("93.184.215.14".parse().unwrap()),
+            mode: PingMode::HttpGet,
+            outcome: RecordOutcome::Failure,
+            latency_ms: None,
+            status: Some(503),
+            failure_kind: ErrorCategory::of_result(false, Some(503), Some("HTTP 503")),
+            error: Some("HTTP 503".to_string()),
+            vpn_active: true,
+            machine_id: "DESKTOP-1".to_string(),
+            tags: vec!["web".to_string()],
+            maintenance: false,
+            resume_grace: false,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(line.starts_with(r#"{"record":"ping","version":1,"#));
+        assert!(line.contains(r#""mode":"http-get","outcome":"failure""#));
+        assert!(line.contains(r#""failure_kind":"http_5xx""#));
+        assert_eq!(serde_json::from_str::<PingRecord>(&line).unwrap(), record);
+
+        let LogLine::Ping(event) = parse_log_line(&line) else {
+            panic!("ping record not read as a ping");
+        };
+        assert_eq!(
+            event.target.to_string(),
+            "http-get https://example.com @ DESKTOP-1"
+        );
+        assert_eq!(event.target_name.as_deref(), Some("example"));
+        assert!(!event.success);
+        assert_eq!(event.error.as_deref(), Some("HTTP 503"));
+        assert_eq!(event.tags, ["web"]);
+
+        // Records are told apart by their fields, not by how they are laid out.
+        let reordered = r#"{ "version": 1, "timestamp": "2025-12-01T08:00:00-05:00",
+            "record": "ping", "target_id": "google_dns", "host": "8.8.8.8", "mode": "icmp",
+            "outcome": "success", "latency_ms": 9.5, "vpn_active": false,
+            "machine_id": "DESKTOP-1" }"#
+            .replace('\n', " ");
+        let LogLine::Ping(event) = parse_log_line(&reordered) else {
+            panic!("reordered ping record not read as a ping");
+        };
+        assert_eq!(event.latency_ms, Some(9.5));
+
+        let future = line.replace(r#""version":1,"#, r#""version":2,"#);
+        assert!(matches!(parse_log_line(&future), LogLine::Malformed));
+        let other = r#"{"record":"config","version":1}"#;
+        assert!(matches!(parse_log_line(other), LogLine::Other));
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::DateTime;
+use chrono::Utc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Gaps shorter than this are treated as ordinary scheduling noise.
+const GAP_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// A discontinuity noticed between two observations of the ping loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClockEvent {
+    /// The loop was suspended well past its expected wake time, typically
+    /// because the machine was asleep.
+    Resumed { gap: Duration },
+    /// The wall clock moved backwards relative to the monotonic clock.
+    WallClockJumpedBack { delta: Duration },
+    /// The wall clock moved forwards while the monotonic clock kept time,
+    /// as after an NTP correction or a manual change of the clock.
+    WallClockJumpedForward { delta: Duration },
+}
+
+/// Detects system sleep/resume and wall-clock jumps by comparing how far the
+/// monotonic and wall clocks advanced between iterations of the ping loop
+/// against the time the loop expected to sleep for.
+///
+/// A resume shows up as the loop waking far later than requested on the
+/// monotonic clock. Only then does a grace period start, during which
+/// failures caused by the network coming back up are tagged rather than
+/// counted; the wall clock jumping while the loop woke on time is a clock
+/// change, not a sleep, and is reported as such.
+#[derive(Debug, Default)]
+pub(crate) struct ResumeDetector {
+    last: Option<(Instant, DateTime<Utc>)>,
+    expected_wake: Option<Instant>,
+    grace_until: Option<Instant>,
+}
+
+impl ResumeDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the instant the loop intends to wake up at next.
+    pub fn expect_wake(&mut self, wake: Instant) {
+        self.expected_wake = Some(wake);
+    }
+
+    /// Compare the clocks against the previous observation, starting a grace
+    /// period of length `grace` when a resume is detected.
+    pub fn observe(
+        &mut self,
+        now: Instant,
+        wall_now: DateTime<Utc>,
+        grace: Duration,
+    ) -> Option<ClockEvent> {
+        let previous = self.last.replace((now, wall_now));
+        let expected_wake = self.expected_wake.take();
+        let (last_instant, last_wall) = previous?;
+
+        let monotonic_delta = now.saturating_duration_since(last_instant);
+        let wall_delta = wall_now - last_wall;
+        let overshoot =
+            expected_wake.map_or(Duration::ZERO, |wake| now.saturating_duration_since(wake));
+
+        let event = match wall_delta.to_std() {
+            // The wall clock went backwards.
+            Err(_) => Some(ClockEvent::WallClockJumpedBack {
+                delta: (last_wall - wall_now).to_std().unwrap_or_default() + monotonic_delta,
+            }),
+            Ok(wall_delta) => {
+                let wall_ahead = wall_delta.saturating_sub(monotonic_delta);
+                let wall_behind = monotonic_delta.saturating_sub(wall_delta);
+                if overshoot > GAP_THRESHOLD {
+                    Some(ClockEvent::Resumed {
+                        gap: overshoot.max(wall_ahead),
+                    })
+                } else if wall_ahead > GAP_THRESHOLD {
+                    Some(ClockEvent::WallClockJumpedForward { delta: wall_ahead })
+                } else if wall_behind > GAP_THRESHOLD {
+                    Some(ClockEvent::WallClockJumpedBack { delta: wall_behind })
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(ClockEvent::Resumed { .. }) = event
+            && !grace.is_zero()
+        {
+            self.grace_until = Some(now + grace);
+        }
+        event
+    }
+
+    /// Returns true while the post-resume grace period is running.
+    pub fn in_grace(&self, now: Instant) -> bool {
+        self.grace_until.is_some_and(|until| now < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRACE: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn late_wake_is_reported_as_resume() {
+        let start = Instant::now();
+        let wall = Utc::now();
+        let mut detector = ResumeDetector::new();
+        assert_eq!(detector.observe(start, wall, GRACE), None);
+
+        detector.expect_wake(start + Duration::from_secs(1));
+        let now = start + Duration::from_secs(121);
+        let event = detector.observe(now, wall + Duration::from_secs(121), GRACE);
+        assert_eq!(
+            event,
+            Some(ClockEvent::Resumed {
+                gap: Duration::from_mins(2)
+            })
+        );
+        assert!(detector.in_grace(now + Duration::from_secs(29)));
+        assert!(!detector.in_grace(now + GRACE));
+    }
+
+    #[test]
+    fn wall_clock_jump_on_time_is_not_a_resume() {
+        let start = Instant::now();
+        let wall = Utc::now();
+        let mut detector = ResumeDetector::new();
+        detector.observe(start, wall, GRACE);
+
+        detector.expect_wake(start + Duration::from_secs(1));
+        let now = start + Duration::from_secs(1);
+        let event = detector.observe(now, wall + Duration::from_secs(3601), GRACE);
+        assert_eq!(
+            event,
+            Some(ClockEvent::WallClockJumpedForward {
+                delta: Duration::from_hours(1)
+            })
+        );
+        assert!(!detector.in_grace(now));
+    }
+
+    #[test]
+    fn on_time_wake_is_quiet() {
+        let start = Instant::now();
+        let wall = Utc::now();
+        let mut detector = ResumeDetector::new();
+        detector.observe(start, wall, GRACE);
+
+        detector.expect_wake(start + Duration::from_secs(5));
+        let now = start + Duration::from_secs(5);
+        assert_eq!(
+            detector.observe(now, wall + Duration::from_secs(5), GRACE),
+            None
+        );
+        assert!(!detector.in_grace(now));
+    }
+}
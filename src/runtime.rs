@@ -1,18 +1,43 @@
+use crate::audit::ErrorCategory;
 use crate::config::Config;
+use crate::config::ConfigSnapshot;
+use crate::config::ProblemSound;
 use crate::config::Target;
+use crate::config::TargetId;
+use crate::home::PIING_HOME;
+use crate::latency_baseline::AnomalyChange;
+use crate::latency_baseline::LatencyBaseline;
 use crate::ping::PingOutcome;
 use crate::ping::{self};
+use crate::ping_database::PingDatabase;
+use crate::ping_database::PingDatabaseWriter;
+use crate::ping_record::PING_RECORD_VERSION;
+use crate::ping_record::PING_RESULT_LOG_TARGET;
+use crate::ping_record::PingRecord;
+use crate::ping_record::PingRecordSink;
+use crate::ping_record::RecordOutcome;
+use crate::resume_detector::ClockEvent;
+use crate::resume_detector::ResumeDetector;
+use crate::scheduler::ProbeScheduler;
 use crate::sound;
 use crate::tray;
 use crate::ui::dialogs::retry_config_operation;
 use crate::vpn_detector::VpnDetector;
+use chrono::Local;
+use chrono::Utc;
 use eyre::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
+use std::time::Instant;
 use teamy_windows::hicon::get_icon_from_current_module;
+use tokio::sync::Semaphore;
 use tokio::sync::watch;
+use tokio::task::JoinError;
 use tokio::task::JoinSet;
-use tokio::time::sleep;
+use tokio::task::{self};
+use tokio::time::sleep_until;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
@@ -66,131 +91,417 @@ fn spawn_ping_runtime(mut shutdown_rx: watch::Receiver<bool>) -> thread::JoinHan
 
 const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
 
+#[derive(Clone, Copy)]
+struct TrayIcons {
+    success: HICON,
+    failure: HICON,
+}
+
+#[expect(
+    clippy::too_many_lines,
+    reason = "the ping loop owns scheduling, probing and alerting state in one place"
+)]
 async fn ping_loop(
     client: reqwest::Client,
     shutdown_rx: &mut watch::Receiver<bool>,
 ) -> eyre::Result<()> {
-    let success_icon = get_icon_from_current_module(w!("green_check_icon"))?;
-    let failure_icon = get_icon_from_current_module(w!("red_x_icon"))?;
+    let icons = TrayIcons {
+        success: get_icon_from_current_module(w!("green_check_icon"))?,
+        failure: get_icon_from_current_module(w!("red_x_icon"))?,
+    };
     let mut last_success_state: Option<bool> = None;
     // Construct a detector once and reuse it across ticks to avoid
     // re-enumerating adapters every iteration.
     let mut vpn_detector = VpnDetector::new();
+    // Every target runs on its own cadence. Probes run concurrently and are
+    // handled as they complete so a slow target never delays the others.
+    let mut scheduler = ProbeScheduler::new();
+    let mut probes: JoinSet<ProbeResult> = JoinSet::new();
+    let mut in_flight: HashMap<task::Id, Target> = HashMap::new();
+    let mut latest: HashMap<TargetId, ProbeResult> = HashMap::new();
+    // Rolling latency baseline of every target, to notice sustained slowdowns.
+    let mut baselines: HashMap<TargetId, LatencyBaseline> = HashMap::new();
+    // Caps how many probes run at once; rebuilt when the configured limit changes.
+    let mut probe_limit: Option<(usize, Arc<Semaphore>)> = None;
+    // Notices when the loop was suspended (system sleep) or the wall clock
+    // jumped, so the failures that follow a resume are not counted as outages.
+    let mut resume_detector = ResumeDetector::new();
+    // Ping results are recorded apart from the diagnostic log.
+    let records = PingRecordSink::open(&PIING_HOME.logs_dir(), Config::current()?.logging.clone())?;
+    // With sqlite turned on, ping records, config changes and incidents also
+    // go to the ping database, written on a thread of its own.
+    let database = open_database(&*Config::current()?);
+    let mut recorded_config: Option<Arc<ConfigSnapshot>> = None;
 
     loop {
         let snapshot = Config::current()?;
-        let targets = &snapshot.targets;
-        let vpn_criteria = &snapshot.vpn_criteria;
-        let problem_sound = &snapshot.problem_sound;
-        let snapshot_time = snapshot.snapshot_time;
+        if let Some(database) = &database
+            && recorded_config
+                .as_ref()
+                .is_none_or(|recorded| !Arc::ptr_eq(recorded, &snapshot))
+        {
+            database.record_config_change(
+                Arc::clone(&snapshot),
+                snapshot.runtime.machine(),
+                Local::now(),
+            );
+            recorded_config = Some(Arc::clone(&snapshot));
+        }
+        let now = Instant::now();
+        scheduler.sync(&snapshot.targets, now, snapshot.runtime.start_jitter);
+        let max_concurrent_probes = snapshot.runtime.max_concurrent_probes;
+        if probe_limit.as_ref().map(|(max, _)| *max) != max_concurrent_probes {
+            probe_limit = max_concurrent_probes.map(|max| (max, Arc::new(Semaphore::new(max))));
+        }
+        latest.retain(|id, _| snapshot.targets.iter().any(|target| &target.id == id));
+        baselines.retain(|id, _| snapshot.targets.iter().any(|target| &target.id == id));
 
-        if targets.is_empty() {
+        if snapshot.targets.is_empty() {
             info!("No targets configured; waiting interval");
+            last_success_state = None;
         }
 
-        // Check VPN state using in-memory snapshot `vpn_criteria` to avoid
-        // re-loading config on every tick. Use the shared `vpn_detector`
-        // instance to keep adapter enumeration minimal.
-        let vpn_active = vpn_detector.is_vpn_active(vpn_criteria, snapshot_time);
+        let due = scheduler.take_due(&snapshot.targets, now);
+        if !due.is_empty() {
+            // Check VPN state using in-memory snapshot `vpn_criteria` to avoid
+            // re-loading config on every tick. Use the shared `vpn_detector`
+            // instance to keep adapter enumeration minimal.
+            let vpn_active =
+                vpn_detector.is_vpn_active(&snapshot.vpn_criteria, snapshot.snapshot_time);
+            let wall_now = Local::now();
+            for target in due {
+                let maintenance = snapshot.in_maintenance(&target, wall_now);
+                let client = client.clone();
+                let probe_target = target.clone();
+                let semaphore = probe_limit.as_ref().map(|(_, semaphore)| semaphore.clone());
+                let handle = probes.spawn(async move {
+                    // Wait for a slot before starting the clock so queueing
+                    // behind other probes never inflates measured latency.
+                    let _permit = match semaphore {
+                        Some(semaphore) => semaphore.acquire_owned().await.ok(),
+                        None => None,
+                    };
+                    let outcome =
+                        ping::execute_ping(&client, probe_target.mode, &probe_target.value).await;
+                    ProbeResult {
+                        target: probe_target,
+                        outcome,
+                        vpn_active,
+                        maintenance,
+                        resume_grace: false,
+                    }
+                });
+                in_flight.insert(handle.id(), target);
+            }
+        }
 
-        if targets.is_empty() {
-            last_success_state = None;
-        } else {
-            let outcomes = run_targets(&client, targets, vpn_active).await;
-            if outcomes.iter().any(|outcome| !outcome.success)
-                && last_success_state != Some(false)
-                && let Err(error) = sound::play_problem_sound(problem_sound.clone())
-            {
-                warn!("Failed to play problem sound: {error}");
+        let wake = scheduler.next_wake().unwrap_or(now + DEFAULT_INTERVAL);
+        resume_detector.expect_wake(wake);
+        let woken_by = tokio::select! {
+            _ = shutdown_rx.changed() => Wake::Shutdown,
+            Some(joined) = probes.join_next_with_id(), if !probes.is_empty() => {
+                Wake::Probe(Box::new(joined))
             }
-            apply_tray_icon(&outcomes, success_icon, failure_icon);
-            last_success_state = Some(outcomes.iter().all(|outcome| outcome.success));
+            () = sleep_until(wake.into()) => Wake::Timer,
+        };
+
+        // Check the clocks before handling the wake-up so that probes which
+        // were cut off by a suspend already fall inside the grace period.
+        let now = Instant::now();
+        let resume_grace = snapshot.runtime.resume_grace;
+        if let Some(event) = resume_detector.observe(now, Utc::now(), resume_grace) {
+            log_clock_event(event, resume_grace);
         }
 
-        let interval = targets
-            .iter()
-            .map(|target| target.interval)
-            .min()
-            .unwrap_or(DEFAULT_INTERVAL);
-        tokio::select! {
-            _ = shutdown_rx.changed() => {
+        match woken_by {
+            Wake::Shutdown => {
                 if *shutdown_rx.borrow() {
                     info!("Ping loop shutting down");
                     break;
                 }
             }
-            () = sleep(interval) => {}
+            Wake::Probe(joined) => match *joined {
+                Ok((id, mut result)) => {
+                    in_flight.remove(&id);
+                    // Failures while the network comes back after a resume are
+                    // logged and tagged, but neither alert nor count as outages.
+                    result.resume_grace = !result.outcome.success && resume_detector.in_grace(now);
+                    log_outcome(
+                        &result,
+                        &snapshot.runtime.machine(),
+                        &records,
+                        database.as_ref(),
+                    );
+                    if !result.is_suppressed()
+                        && let Some(latency) =
+                            result.outcome.latency.filter(|_| result.outcome.success)
+                        && let Some(change) = baselines
+                            .entry(result.target.id.clone())
+                            .or_default()
+                            .observe(
+                                Local::now(),
+                                latency.as_secs_f64() * 1000.0,
+                                &snapshot.runtime.latency_anomaly,
+                            )
+                    {
+                        log_latency_anomaly(&result, change);
+                    }
+                    scheduler.record(&result.target, result.outcome.success, now);
+                    latest.insert(result.target.id.clone(), result);
+                    last_success_state = Some(apply_alerts(
+                        &latest,
+                        &snapshot.problem_sound,
+                        last_success_state,
+                        icons,
+                    ));
+                }
+                Err(error) => {
+                    error!("Ping task failed: {error}");
+                    if let Some(target) = in_flight.remove(&error.id()) {
+                        scheduler.record(&target, false, now);
+                    }
+                }
+            },
+            Wake::Timer => {}
         }
     }
+    probes.abort_all();
     Ok(())
 }
 
-async fn run_targets(
-    client: &reqwest::Client,
-    targets: &[Target],
+/// What woke the ping loop up.
+enum Wake {
+    Shutdown,
+    Probe(Box<Result<(task::Id, ProbeResult), JoinError>>),
+    Timer,
+}
+
+/// The outcome of probing one target, along with the context it was probed in.
+struct ProbeResult {
+    target: Target,
+    outcome: PingOutcome,
     vpn_active: bool,
-) -> Vec<PingOutcome> {
-    let mut join_set = JoinSet::new();
-
-    for target in targets {
-        let mode = target.mode;
-        let destination = target.value.clone();
-        let client = client.clone();
-        join_set.spawn(async move {
-            let outcome = ping::execute_ping(&client, mode, &destination).await;
-            log_outcome(&outcome, vpn_active);
-            outcome
-        });
+    /// Whether a `piing_schedule` maintenance window covered the target.
+    maintenance: bool,
+    /// Whether the probe failed during the grace period after a system resume.
+    resume_grace: bool,
+}
+
+impl ProbeResult {
+    /// Maintenance windows and resume grace periods never raise alerts.
+    fn is_suppressed(&self) -> bool {
+        self.maintenance || self.resume_grace
     }
+}
 
-    let mut outcomes = Vec::with_capacity(targets.len());
-    while let Some(result) = join_set.join_next().await {
-        match result {
-            Ok(outcome) => outcomes.push(outcome),
-            Err(error) => error!("Ping task failed: {error}"),
+/// Play the problem sound when alerting targets start failing and refresh the
+/// tray icon from the latest result of every target. Returns whether all
+/// alerting targets are currently succeeding.
+fn apply_alerts(
+    latest: &HashMap<TargetId, ProbeResult>,
+    problem_sound: &Arc<ProblemSound>,
+    last_success_state: Option<bool>,
+    icons: TrayIcons,
+) -> bool {
+    // Targets inside a maintenance window or failing right after a resume
+    // are logged but never alert. Only failures of targets matching the sound's tag filter are
+    // alert-worthy; the tray icon still reflects every other target.
+    let alerting_success = latest
+        .values()
+        .filter(|result| !result.is_suppressed())
+        .filter(|result| problem_sound.applies_to(&result.target.tags))
+        .all(|result| result.outcome.success);
+    if !alerting_success
+        && last_success_state != Some(false)
+        && let Err(error) = sound::play_problem_sound(problem_sound.clone())
+    {
+        warn!("Failed to play problem sound: {error}");
+    }
+    let outcomes: Vec<&PingOutcome> = latest
+        .values()
+        .filter(|result| !result.is_suppressed())
+        .map(|result| &result.outcome)
+        .collect();
+    apply_tray_icon(&outcomes, icons);
+    alerting_success
+}
+
+fn log_clock_event(event: ClockEvent, resume_grace: Duration) {
+    match event {
+        ClockEvent::Resumed { gap } => {
+            info!(
+                event = "system_resumed",
+                gap_ms = u64::try_from(gap.as_millis()).unwrap_or(u64::MAX),
+                grace_ms = u64::try_from(resume_grace.as_millis()).unwrap_or(u64::MAX),
+                "System resumed after {}",
+                humantime::format_duration(Duration::from_secs(gap.as_secs()))
+            );
+        }
+        ClockEvent::WallClockJumpedBack { delta } => {
+            warn!(
+                event = "clock_jump",
+                delta_ms = -i64::try_from(delta.as_millis()).unwrap_or(i64::MAX),
+                "Wall clock moved back by {}",
+                humantime::format_duration(Duration::from_secs(delta.as_secs()))
+            );
+        }
+        ClockEvent::WallClockJumpedForward { delta } => {
+            warn!(
+                event = "clock_jump",
+                delta_ms = i64::try_from(delta.as_millis()).unwrap_or(i64::MAX),
+                "Wall clock moved forward by {}",
+                humantime::format_duration(Duration::from_secs(delta.as_secs()))
+            );
         }
     }
+}
 
-    outcomes
+/// Open the ping database when the `piing_logging` block turns sqlite on.
+fn open_database(snapshot: &ConfigSnapshot) -> Option<PingDatabaseWriter> {
+    if !snapshot.logging.sqlite {
+        return None;
+    }
+    match PingDatabase::open(&PIING_HOME.database_path()).and_then(PingDatabaseWriter::spawn) {
+        Ok(database) => Some(database),
+        Err(error) => {
+            warn!("Failed to open the ping database, continuing without it: {error:#}");
+            None
+        }
+    }
 }
 
-fn log_outcome(outcome: &PingOutcome, vpn_active: bool) {
+/// Record the result of a probe and show it on the terminal.
+fn log_outcome(
+    result: &ProbeResult,
+    machine: &str,
+    records: &PingRecordSink,
+    database: Option<&PingDatabaseWriter>,
+) {
+    let outcome = &result.outcome;
+    let vpn_active = result.vpn_active;
+    let maintenance = result.maintenance;
+    let resume_grace = result.resume_grace;
+    let status = outcome.status.map(|s| s.as_u16());
+    let record = PingRecord {
+        version: PING_RECORD_VERSION,
+        timestamp: Local::now(),
+        target_id: result.target.id.name.clone(),
+        host: outcome.host.clone(),
+        resolved_ip: outcome.resolved_ip,
+        mode: outcome.mode,
+        outcome: if outcome.success {
+            RecordOutcome::Success
+        } else {
+            RecordOutcome::Failure
+        },
+        latency_ms: outcome
+            .latency
+            .filter(|_| outcome.success)
+            .map(|latency| latency.as_secs_f64() * 1000.0),
+        status,
+        failure_kind: ErrorCategory::of_result(outcome.success, status, outcome.error.as_deref()),
+        error: outcome.error.clone().filter(|_| !outcome.success),
+        vpn_active,
+        machine_id: machine.to_string(),
+        tags: result.target.tags.clone(),
+        maintenance,
+        resume_grace,
+    };
+    if let Err(error) = records.write(&record) {
+        warn!("{error:#}");
+    }
+    if let Some(database) = database {
+        database.record_ping(record);
+    }
+
     let latency_ms = outcome
         .latency
         .map(|dur| dur.as_millis())
         .unwrap_or_default();
+    let tags = result.target.tags.join(",");
     if outcome.success {
         info!(
+            target: PING_RESULT_LOG_TARGET,
+            target = %result.target.id.name,
             host = %outcome.host,
             mode = outcome.mode.as_str(),
+            machine,
             success = true,
             latency_ms,
-            status = outcome.status.map(|s| s.as_u16()),
+            status,
             vpn_active,
+            tags = tags.as_str(),
+            maintenance,
             "Ping succeeded"
         );
     } else {
         warn!(
+            target: PING_RESULT_LOG_TARGET,
+            target = %result.target.id.name,
             host = %outcome.host,
             mode = outcome.mode.as_str(),
+            machine,
             success = false,
             error = outcome.error.as_deref().unwrap_or("unknown"),
             vpn_active,
+            tags = tags.as_str(),
+            maintenance,
+            resume_grace,
             "Ping failed"
         );
     }
 }
 
-fn apply_tray_icon(outcomes: &[PingOutcome], success_icon: HICON, failure_icon: HICON) {
+fn log_latency_anomaly(result: &ProbeResult, change: AnomalyChange) {
+    let target = &result.target;
+    match change {
+        AnomalyChange::Started(anomaly) => {
+            warn!(
+                event = "latency_anomaly",
+                state = "started",
+                target = %target.id.name,
+                host = %result.outcome.host,
+                mode = result.outcome.mode.as_str(),
+                since = %anomaly.since.to_rfc3339(),
+                peak_ms = anomaly.peak_ms,
+                baseline_ms = anomaly.baseline_ms.round(),
+                threshold_ms = anomaly.threshold_ms.round(),
+                "Latency of {} has stayed above {:.0} ms (baseline {:.0} ms)",
+                target.id.name,
+                anomaly.threshold_ms,
+                anomaly.baseline_ms
+            );
+        }
+        AnomalyChange::Ended(anomaly, end) => {
+            let duration = (end - anomaly.since).to_std().unwrap_or_default();
+            info!(
+                event = "latency_anomaly",
+                state = "ended",
+                target = %target.id.name,
+                host = %result.outcome.host,
+                mode = result.outcome.mode.as_str(),
+                since = %anomaly.since.to_rfc3339(),
+                duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+                peak_ms = anomaly.peak_ms,
+                baseline_ms = anomaly.baseline_ms.round(),
+                "Latency of {} is back to normal after {}",
+                target.id.name,
+                humantime::format_duration(Duration::from_secs(duration.as_secs()))
+            );
+        }
+    }
+}
+
+fn apply_tray_icon(outcomes: &[&PingOutcome], icons: TrayIcons) {
     if outcomes.is_empty() {
         return;
     }
 
     let is_success = outcomes.iter().all(|outcome| outcome.success);
     let icon = if is_success {
-        success_icon
+        icons.success
     } else {
-        failure_icon
+        icons.failure
     };
     if let Err(e) = tray::set_tray_icon(icon) {
         warn!("Failed to set tray icon: {e:?}");
@@ -0,0 +1,227 @@
+use crate::config::Target;
+use crate::config::TargetId;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::RandomState;
+use std::time::Duration;
+use std::time::Instant;
+use tracing::info;
+
+/// Tracks when each configured target is next due to be probed.
+///
+/// Every target runs on its own cadence. A target that fails switches to its
+/// `interval_on_failure` (when configured) and only returns to its regular
+/// `interval` once it has succeeded `recovery_probes` times in a row, so the
+/// start and end of an outage are measured precisely without probing quickly
+/// all the time.
+///
+/// Newly tracked targets start after a random phase offset (up to the
+/// target's own `start_jitter`, or else the runtime one) so that targets
+/// sharing an interval stay spread out instead of probing in bursts.
+#[derive(Debug, Default)]
+pub(crate) struct ProbeScheduler {
+    states: HashMap<TargetId, TargetState>,
+    random: RandomState,
+}
+
+#[derive(Debug)]
+struct TargetState {
+    next_due: Instant,
+    in_flight: bool,
+    /// True from the first failure until `recovery_probes` consecutive successes.
+    failing: bool,
+    consecutive_successes: u32,
+}
+
+impl ProbeScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking newly configured targets (due within their own
+    /// `start_jitter`, or else `start_jitter`) and forget targets that are no
+    /// longer configured.
+    pub fn sync(&mut self, targets: &[Target], now: Instant, start_jitter: Duration) {
+        self.states
+            .retain(|id, _| targets.iter().any(|target| &target.id == id));
+        for target in targets {
+            if self.states.contains_key(&target.id) {
+                continue;
+            }
+            let offset = self.phase_offset(&target.id, target.start_jitter.unwrap_or(start_jitter));
+            self.states.insert(
+                target.id.clone(),
+                TargetState {
+                    next_due: now + offset,
+                    in_flight: false,
+                    failing: false,
+                    consecutive_successes: 0,
+                },
+            );
+        }
+    }
+
+    /// Return the targets due at `now` and mark them as in flight until
+    /// their result is passed to [`ProbeScheduler::record`].
+    pub fn take_due(&mut self, targets: &[Target], now: Instant) -> Vec<Target> {
+        let mut due = Vec::new();
+        for target in targets {
+            if let Some(state) = self.states.get_mut(&target.id)
+                && !state.in_flight
+                && state.next_due <= now
+            {
+                state.in_flight = true;
+                due.push(target.clone());
+            }
+        }
+        due
+    }
+
+    /// Record a probe result and schedule the target's next probe.
+    pub fn record(&mut self, target: &Target, success: bool, now: Instant) {
+        let Some(state) = self.states.get_mut(&target.id) else {
+            return;
+        };
+        state.in_flight = false;
+        if success {
+            state.consecutive_successes = state.consecutive_successes.saturating_add(1);
+            if state.failing && state.consecutive_successes >= target.recovery_probes {
+                state.failing = false;
+                if target.interval_on_failure.is_some() {
+                    info!(
+                        target = %target.id.name,
+                        interval = %humantime::format_duration(target.interval),
+                        "Target recovered; returning to regular interval"
+                    );
+                }
+            }
+        } else {
+            state.consecutive_successes = 0;
+            if !state.failing {
+                state.failing = true;
+                if let Some(interval) = target.interval_on_failure {
+                    info!(
+                        target = %target.id.name,
+                        interval = %humantime::format_duration(interval),
+                        "Target failing; switching to failure interval"
+                    );
+                }
+            }
+        }
+
+        // Keep the target's phase by stepping from its previous due time,
+        // but never schedule into the past after a slow probe.
+        let interval = Self::effective_interval(target, state.failing);
+        state.next_due += interval;
+        if state.next_due <= now {
+            state.next_due = now + interval;
+        }
+    }
+
+    /// The earliest instant at which a target that is not in flight becomes due.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.states
+            .values()
+            .filter(|state| !state.in_flight)
+            .map(|state| state.next_due)
+            .min()
+    }
+
+    /// A pseudo-random offset in `[0, max)`, derived from the target id with
+    /// a per-process random seed.
+    fn phase_offset(&self, id: &TargetId, max: Duration) -> Duration {
+        if max.is_zero() {
+            return Duration::ZERO;
+        }
+        let scaled = (u128::from(self.random.hash_one(id)) * max.as_nanos()) >> 64;
+        Duration::from_nanos(u64::try_from(scaled).unwrap_or(u64::MAX))
+    }
+
+    fn effective_interval(target: &Target, failing: bool) -> Duration {
+        match target.interval_on_failure {
+            Some(interval) if failing => interval.min(target.interval),
+            _ => target.interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_RECOVERY_PROBES;
+    use crate::ping::PingMode;
+    use crate::ping::parse_destination;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn target() -> Target {
+        Target {
+            id: TargetId {
+                file_path: PathBuf::from("test.piing_hcl"),
+                name: "test".to_string(),
+            },
+            value: Arc::new(parse_destination("8.8.8.8", PingMode::Icmp)),
+            mode: PingMode::Icmp,
+            interval: Duration::from_secs(30),
+            tags: Vec::new(),
+            interval_on_failure: Some(Duration::from_secs(2)),
+            recovery_probes: DEFAULT_RECOVERY_PROBES,
+            slo: None,
+            start_jitter: None,
+        }
+    }
+
+    fn probe(scheduler: &mut ProbeScheduler, target: &Target, success: bool, now: Instant) {
+        assert_eq!(
+            scheduler.take_due(std::slice::from_ref(target), now).len(),
+            1
+        );
+        scheduler.record(target, success, now);
+    }
+
+    #[test]
+    fn failure_switches_cadence_until_recovered() {
+        let target = target();
+        let start = Instant::now();
+        let mut scheduler = ProbeScheduler::new();
+        scheduler.sync(std::slice::from_ref(&target), start, Duration::ZERO);
+
+        probe(&mut scheduler, &target, false, start);
+        let mut now = start + Duration::from_secs(2);
+        assert_eq!(scheduler.next_wake(), Some(now));
+
+        for _ in 0..DEFAULT_RECOVERY_PROBES - 1 {
+            probe(&mut scheduler, &target, true, now);
+            now += Duration::from_secs(2);
+            assert_eq!(scheduler.next_wake(), Some(now));
+        }
+
+        probe(&mut scheduler, &target, true, now);
+        assert_eq!(scheduler.next_wake(), Some(now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn start_jitter_delays_first_probe_within_bound() {
+        let target = target();
+        let start = Instant::now();
+        let jitter = Duration::from_secs(5);
+        let mut scheduler = ProbeScheduler::new();
+        scheduler.sync(std::slice::from_ref(&target), start, jitter);
+
+        let first = scheduler.next_wake().unwrap();
+        assert!(first >= start && first < start + jitter);
+    }
+
+    #[test]
+    fn target_start_jitter_overrides_runtime_setting() {
+        let target = Target {
+            start_jitter: Some(Duration::ZERO),
+            ..target()
+        };
+        let start = Instant::now();
+        let mut scheduler = ProbeScheduler::new();
+        scheduler.sync(std::slice::from_ref(&target), start, Duration::from_secs(5));
+
+        assert_eq!(scheduler.next_wake(), Some(start));
+    }
+}
@@ -12,6 +12,8 @@ use eyre::eyre;
 use std::io::Write;
 use std::process::Command;
 use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use teamy_windows::console::console_attach;
 use teamy_windows::console::console_create;
 use teamy_windows::console::console_detach;
@@ -208,10 +210,25 @@ impl TrayWindowState {
             self.show_logs();
         }
 
-        // Run audit
-        match AuditArgs::default().invoke() {
-            Ok(()) => info!("Audit completed"),
-            Err(error) => error!("Audit failed: {error}"),
+        // Run the audit off the UI thread so the tray keeps responding while
+        // the logs are read, and only one at a time.
+        static AUDIT_RUNNING: AtomicBool = AtomicBool::new(false);
+        if AUDIT_RUNNING.swap(true, Ordering::AcqRel) {
+            info!("An audit is already running");
+            return;
+        }
+        let spawned = std::thread::Builder::new()
+            .name("audit".to_string())
+            .spawn(|| {
+                match AuditArgs::default().invoke() {
+                    Ok(()) => info!("Audit completed"),
+                    Err(error) => error!("Audit failed: {error}"),
+                }
+                AUDIT_RUNNING.store(false, Ordering::Release);
+            });
+        if let Err(error) = spawned {
+            AUDIT_RUNNING.store(false, Ordering::Release);
+            error!("Failed to start the audit: {error}");
         }
     }
 